@@ -0,0 +1,296 @@
+//! Idempotent card template import.
+//!
+//! Compares a manifest with what is already on chain and works out the
+//! templates still to create and the pool entries missing (or with a
+//! different weight) in each rarity pool, so running the same import twice
+//! sends nothing the second time. Cards of a card set go into their set's pool,
+//! and into the standard pool once the set is released; retired templates go
+//! into neither.
+
+use zoo_contract::{Ability, CardTemplate, PoolEntry, Rarity, TraitType};
+
+use crate::manifest::{Manifest, TemplateEntry};
+
+pub const RARITIES: [Rarity; 3] = [Rarity::Common, Rarity::Rare, Rarity::Legendary];
+
+pub struct ImportPlan<'a> {
+    /// Templates that don't exist yet
+    pub create: Vec<&'a TemplateEntry>,
+    /// Existing templates identical to the manifest
+    pub unchanged: Vec<u32>,
+    /// Existing templates that differ from the manifest (left as they are)
+    pub conflicting: Vec<u32>,
+    /// Entries to add or re-weight per (set_id, rarity) pool, set 0 being the
+    /// standard pools; standard pools first, then in manifest order
+    pub pool_additions: Vec<(u16, Rarity, Vec<PoolEntry>)>,
+    /// Cards of a set that isn't released yet, kept out of the standard pools
+    pub unreleased: Vec<u32>,
+}
+
+/// `existing` returns the on-chain template for an id, if any;
+/// `pool` returns the current entries of a (set_id, rarity) pool (empty if not created);
+/// `released` tells whether a card set is active and past its release time.
+pub fn plan<'a>(
+    manifest: &'a Manifest,
+    existing: impl Fn(u32) -> Option<CardTemplate>,
+    pool: impl Fn(u16, Rarity) -> Vec<PoolEntry>,
+    released: impl Fn(u16) -> bool,
+) -> ImportPlan<'a> {
+    let mut create = Vec::new();
+    let mut unchanged = Vec::new();
+    let mut conflicting = Vec::new();
+    let mut unreleased = Vec::new();
+    let mut pool_additions: Vec<(u16, Rarity, Vec<PoolEntry>)> =
+        RARITIES.iter().map(|r| (0, *r, Vec::new())).collect();
+
+    for entry in &manifest.templates {
+        // The on-chain rarity and set win for existing templates: that's what gacha rolls use
+        let (rarity, set_id) = match existing(entry.card_type_id) {
+            Some(template) => {
+                if matches(entry, &template) {
+                    unchanged.push(entry.card_type_id);
+                } else {
+                    conflicting.push(entry.card_type_id);
+                }
+                // Retired cards no longer drop, don't put them back in the pools
+                if template.is_retired {
+                    continue;
+                }
+                (template.rarity, template.set_id)
+            }
+            None => {
+                create.push(entry);
+                (entry.rarity.into(), entry.set_id)
+            }
+        };
+
+        let pool_entry = PoolEntry { card_type_id: entry.card_type_id, weight: entry.weight };
+        // Cards of an unreleased set would drop from the standard gacha before their packs sell
+        if set_id == 0 || released(set_id) {
+            additions_for(&mut pool_additions, 0, rarity).push(pool_entry);
+        } else {
+            unreleased.push(entry.card_type_id);
+        }
+        if set_id != 0 {
+            additions_for(&mut pool_additions, set_id, rarity).push(pool_entry);
+        }
+    }
+
+    for (set_id, rarity, additions) in pool_additions.iter_mut() {
+        let current = pool(*set_id, *rarity);
+        additions.retain(|entry| !current.contains(entry));
+    }
+    pool_additions.retain(|(_, _, additions)| !additions.is_empty());
+
+    ImportPlan { create, unchanged, conflicting, pool_additions, unreleased }
+}
+
+fn additions_for(
+    pool_additions: &mut Vec<(u16, Rarity, Vec<PoolEntry>)>,
+    set_id: u16,
+    rarity: Rarity,
+) -> &mut Vec<PoolEntry> {
+    let index = match pool_additions.iter().position(|(s, r, _)| *s == set_id && *r == rarity) {
+        Some(index) => index,
+        None => {
+            pool_additions.push((set_id, rarity, Vec::new()));
+            pool_additions.len() - 1
+        }
+    };
+    &mut pool_additions[index].2
+}
+
+fn matches(entry: &TemplateEntry, template: &CardTemplate) -> bool {
+    entry.name == template.name
+        && TraitType::from(entry.trait_type) == template.trait_type
+        && Rarity::from(entry.rarity) == template.rarity
+        && entry.min_attack == template.min_attack
+        && entry.max_attack == template.max_attack
+        && entry.min_health == template.min_health
+        && entry.max_health == template.max_health
+        && entry.description == template.description
+        && entry.image_uri == template.image_uri
+        && entry.set_id == template.set_id
+        && Ability::from(entry.ability) == template.ability
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::{AbilityArg, RarityArg, TraitArg};
+    use anchor_lang::prelude::Pubkey;
+
+    fn entry(card_type_id: u32, rarity: RarityArg) -> TemplateEntry {
+        TemplateEntry {
+            card_type_id,
+            name: format!("Card {}", card_type_id),
+            trait_type: TraitArg::Archer,
+            rarity,
+            min_attack: 1,
+            max_attack: 2,
+            min_health: 3,
+            max_health: 4,
+            description: "desc".to_string(),
+            image_uri: "ipfs://card".to_string(),
+            weight: PoolEntry::DEFAULT_WEIGHT,
+            set_id: 0,
+            ability: AbilityArg::None,
+        }
+    }
+
+    fn on_chain(entry: &TemplateEntry) -> CardTemplate {
+        CardTemplate {
+            card_type_id: entry.card_type_id,
+            name: entry.name.clone(),
+            trait_type: entry.trait_type.into(),
+            rarity: entry.rarity.into(),
+            min_attack: entry.min_attack,
+            max_attack: entry.max_attack,
+            min_health: entry.min_health,
+            max_health: entry.max_health,
+            description: entry.description.clone(),
+            image_uri: entry.image_uri.clone(),
+            bump: 255,
+            creator: Pubkey::new_unique(),
+            version: 1,
+            is_retired: false,
+            set_id: entry.set_id,
+            ability: entry.ability.into(),
+        }
+    }
+
+    #[test]
+    fn test_plan_is_empty_when_everything_exists() {
+        let manifest = Manifest {
+            templates: vec![entry(404, RarityArg::Common), entry(418, RarityArg::Legendary)],
+        };
+        let existing: Vec<CardTemplate> = manifest.templates.iter().map(on_chain).collect();
+
+        let plan = plan(
+            &manifest,
+            |id| existing.iter().find(|t| t.card_type_id == id).cloned(),
+            |_, rarity| match rarity {
+                Rarity::Common => vec![PoolEntry { card_type_id: 404, weight: 100 }],
+                Rarity::Legendary => vec![PoolEntry { card_type_id: 418, weight: 100 }],
+                Rarity::Rare => vec![],
+            },
+            |_| true,
+        );
+
+        assert!(plan.create.is_empty());
+        assert_eq!(plan.unchanged, vec![404, 418]);
+        assert!(plan.conflicting.is_empty());
+        assert!(plan.pool_additions.is_empty());
+    }
+
+    #[test]
+    fn test_plan_creates_missing_and_fills_pools() {
+        let mut manifest = Manifest {
+            templates: vec![
+                entry(404, RarityArg::Common),
+                entry(418, RarityArg::Legendary),
+                entry(429, RarityArg::Common),
+            ],
+        };
+        // Rate-up for 429, which is already in the pool at the default weight
+        manifest.templates[2].weight = 250;
+        // 404 exists on chain as Rare with a different description
+        let mut deer = on_chain(&manifest.templates[0]);
+        deer.rarity = Rarity::Rare;
+        deer.description = "patched".to_string();
+
+        let plan = plan(
+            &manifest,
+            |id| (id == 404).then(|| deer.clone()),
+            |set_id, rarity| match (set_id, rarity) {
+                (0, Rarity::Common) => vec![PoolEntry { card_type_id: 429, weight: 100 }],
+                _ => vec![],
+            },
+            |_| true,
+        );
+
+        let created: Vec<u32> = plan.create.iter().map(|t| t.card_type_id).collect();
+        assert_eq!(created, vec![418, 429]);
+        assert_eq!(plan.conflicting, vec![404]);
+        let pools: Vec<(Rarity, Vec<u32>)> = plan
+            .pool_additions
+            .iter()
+            .map(|(_, rarity, entries)| (*rarity, entries.iter().map(|e| e.card_type_id).collect()))
+            .collect();
+        assert_eq!(
+            pools,
+            vec![(Rarity::Common, vec![429]), (Rarity::Rare, vec![404]), (Rarity::Legendary, vec![418])]
+        );
+        assert_eq!(plan.pool_additions[0].2[0].weight, 250);
+    }
+
+    #[test]
+    fn test_plan_fills_standard_and_set_pools() {
+        let mut manifest = Manifest {
+            templates: vec![entry(503, RarityArg::Rare), entry(2, RarityArg::Rare)],
+        };
+        manifest.templates[0].set_id = 1;
+        manifest.templates[1].set_id = 2;
+        // 2 already exists on chain in set 2 and is in the standard pool
+        let errno = on_chain(&manifest.templates[1]);
+
+        let plan = plan(
+            &manifest,
+            |id| (id == 2).then(|| errno.clone()),
+            |set_id, _| match set_id {
+                0 => vec![PoolEntry { card_type_id: 2, weight: 100 }],
+                _ => vec![],
+            },
+            |_| true,
+        );
+
+        let pools: Vec<(u16, Vec<u32>)> = plan
+            .pool_additions
+            .iter()
+            .map(|(set_id, _, entries)| (*set_id, entries.iter().map(|e| e.card_type_id).collect()))
+            .collect();
+        assert_eq!(pools, vec![(0, vec![503]), (1, vec![503]), (2, vec![2])]);
+        assert!(plan.unreleased.is_empty());
+    }
+
+    #[test]
+    fn test_plan_keeps_unreleased_sets_out_of_standard_pools() {
+        let mut manifest = Manifest {
+            templates: vec![entry(503, RarityArg::Rare), entry(2, RarityArg::Rare), entry(429, RarityArg::Rare)],
+        };
+        manifest.templates[0].set_id = 1;
+        manifest.templates[1].set_id = 2;
+
+        // Set 1 is out, set 2 isn't yet: its cards only go into its own pool
+        let plan = plan(&manifest, |_| None, |_, _| vec![], |set_id| set_id == 1);
+
+        let pools: Vec<(u16, Vec<u32>)> = plan
+            .pool_additions
+            .iter()
+            .map(|(set_id, _, entries)| (*set_id, entries.iter().map(|e| e.card_type_id).collect()))
+            .collect();
+        assert_eq!(pools, vec![(0, vec![503, 429]), (1, vec![503]), (2, vec![2])]);
+        assert_eq!(plan.unreleased, vec![2]);
+    }
+
+    #[test]
+    fn test_plan_leaves_retired_templates_out_of_pools() {
+        let mut manifest = Manifest {
+            templates: vec![entry(404, RarityArg::Common), entry(429, RarityArg::Common)],
+        };
+        manifest.templates[0].set_id = 1;
+        let mut retired = on_chain(&manifest.templates[0]);
+        retired.is_retired = true;
+
+        let plan = plan(&manifest, |id| (id == 404).then(|| retired.clone()), |_, _| vec![], |_| true);
+
+        assert!(plan.create.iter().all(|t| t.card_type_id != 404));
+        assert_eq!(plan.unchanged, vec![404]);
+        let pools: Vec<(u16, Vec<u32>)> = plan
+            .pool_additions
+            .iter()
+            .map(|(set_id, _, entries)| (*set_id, entries.iter().map(|e| e.card_type_id).collect()))
+            .collect();
+        assert_eq!(pools, vec![(0, vec![429])]);
+    }
+}
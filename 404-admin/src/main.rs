@@ -0,0 +1,772 @@
+//! zoo-admin: game setup and operations for zoo_contract against any RPC
+//! endpoint, including a local test validator.
+
+mod import;
+mod manifest;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountDeserialize, Discriminator};
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signature, Signer};
+use solana_sdk::transaction::Transaction;
+use std::path::PathBuf;
+use zoo_contract::client;
+use zoo_contract::{
+    ArenaTier, CardInstance, CardTemplate, EconomyConfig, PlayerProfile, PoolEntry, Rarity, SynergyTier, TierThreshold,
+};
+
+use manifest::{CurrencyArg, RarityArg, TraitArg};
+
+#[derive(Parser)]
+#[command(name = "zoo-admin", about = "Administer the 404 ZOO program")]
+struct Cli {
+    /// RPC endpoint
+    #[arg(long, default_value = "http://127.0.0.1:8899")]
+    url: String,
+
+    /// Authority keypair (defaults to the Solana CLI keypair)
+    #[arg(long)]
+    keypair: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create the GameConfig with the signer as authority
+    /// (then set-economy, set-tiers and init-leaderboard for ranked matches)
+    Initialize {
+        #[arg(long, default_value_t = 100)]
+        normal_pack_price: u64,
+        /// BUG tokens per 1 SOL
+        #[arg(long, default_value_t = 1000)]
+        sol_to_bug_rate: u64,
+        #[arg(long, default_value_t = 10)]
+        ticket_price: u64,
+    },
+    /// Authorize card creators
+    AddCreator { creators: Vec<Pubkey> },
+    /// Set trophies and BUG rewards per match (defaults are the values used until it exists)
+    SetEconomy {
+        #[arg(long, default_value_t = EconomyConfig::DEFAULTS.base_trophy_gain)]
+        base_trophy_gain: u32,
+        #[arg(long, default_value_t = EconomyConfig::DEFAULTS.trophy_loss)]
+        trophy_loss: u32,
+        /// Cap on win streak bonus trophies (0 = uncapped)
+        #[arg(long, default_value_t = EconomyConfig::DEFAULTS.max_streak_bonus)]
+        max_streak_bonus: u32,
+        #[arg(long, default_value_t = EconomyConfig::DEFAULTS.win_reward)]
+        win_reward: u64,
+        #[arg(long, default_value_t = EconomyConfig::DEFAULTS.loss_reward)]
+        loss_reward: u64,
+        /// Max BUG per player per day from wins and losses (0 = no cap)
+        #[arg(long, default_value_t = EconomyConfig::DEFAULTS.daily_reward_cap)]
+        daily_reward_cap: u64,
+        /// Trophies both players gain on a draw
+        #[arg(long, default_value_t = EconomyConfig::DEFAULTS.draw_trophy_delta)]
+        draw_trophy_delta: u32,
+    },
+    /// Set the arena tier thresholds, Bronze to Legend
+    SetTiers {
+        /// MIN_TROPHIES[:floor] per tier, ascending from Bronze at 0, e.g. 0 400 800:floor
+        #[arg(value_parser = manifest::parse_tier_threshold, required = true)]
+        tiers: Vec<TierThreshold>,
+    },
+    /// Create the on-chain top-100 leaderboard
+    InitLeaderboard,
+    /// Create or update a pack type for purchase_pack
+    SetPackType {
+        pack_type: u8,
+        name: String,
+        /// Price in BUG
+        #[arg(long)]
+        price: u64,
+        /// Cards per pack (1-10)
+        #[arg(long, default_value_t = 10)]
+        cards: u8,
+        /// Minimum rarity of the last card
+        #[arg(long, value_enum, default_value = "common")]
+        guaranteed: RarityArg,
+        /// Only cards of this trait
+        #[arg(long = "trait", value_enum)]
+        trait_filter: Option<TraitArg>,
+        /// Cards of that trait the pack may contain (repeat for each card)
+        #[arg(long = "card", requires = "trait_filter")]
+        card_type_ids: Vec<u32>,
+        /// Stop selling this pack type
+        #[arg(long)]
+        inactive: bool,
+        /// Draw from this card set's pools instead of the standard ones
+        #[arg(long = "set", default_value_t = 0)]
+        set_id: u16,
+    },
+    /// Set card XP per match and the level thresholds and bonuses
+    SetProgression {
+        #[arg(long, default_value_t = 100)]
+        xp_per_win: u32,
+        #[arg(long, default_value_t = 40)]
+        xp_per_loss: u32,
+        /// Attack gained per level
+        #[arg(long, default_value_t = 1)]
+        attack_per_level: u16,
+        /// Health gained per level
+        #[arg(long, default_value_t = 2)]
+        health_per_level: u16,
+        /// How far levelled stats may go above the template max, in basis points
+        #[arg(long, default_value_t = 1000)]
+        max_bonus_bps: u16,
+        /// Total XP for each level, ascending (max 20)
+        level_thresholds: Vec<u32>,
+    },
+    /// Replace the trait synergy tiers
+    SetSynergy {
+        /// TRAIT:MIN_UNITS:ATTACK_BPS:HEALTH_BPS, e.g. tank:2:1000:1500 (none clears them)
+        #[arg(value_parser = manifest::parse_synergy_tier)]
+        tiers: Vec<SynergyTier>,
+    },
+    /// Set the price and rules of card stat rerolls
+    SetReroll {
+        #[arg(long, value_enum, default_value = "bug")]
+        currency: CurrencyArg,
+        /// Cost of one reroll
+        #[arg(long)]
+        price: u64,
+        /// Keep the higher of the old and new roll for each stat
+        #[arg(long)]
+        keep_better: bool,
+    },
+    /// Create or update a card set (expansion)
+    SetCardSet {
+        set_id: u16,
+        name: String,
+        /// Unix timestamp before which the set's packs can't be bought (defaults to now)
+        #[arg(long)]
+        release: Option<i64>,
+        /// Stop selling the set's packs
+        #[arg(long)]
+        inactive: bool,
+    },
+    /// Create the missing card templates of a TOML/JSON manifest and add
+    /// them to their rarity pools (safe to re-run)
+    ImportTemplates { manifest: PathBuf },
+    /// Add cards to a rarity pool, or change their drop weight
+    UpdatePool {
+        #[arg(value_enum)]
+        rarity: RarityArg,
+        /// Card set pool (0 = the standard pool)
+        #[arg(long = "set", default_value_t = 0)]
+        set_id: u16,
+        /// ID or ID:WEIGHT (default weight 100)
+        #[arg(value_parser = manifest::parse_pool_entry)]
+        entries: Vec<PoolEntry>,
+    },
+    /// Remove card ids from a rarity pool
+    RemoveFromPool {
+        #[arg(value_enum)]
+        rarity: RarityArg,
+        /// Card set pool (0 = the standard pool)
+        #[arg(long = "set", default_value_t = 0)]
+        set_id: u16,
+        card_type_ids: Vec<u32>,
+    },
+    /// Retire a card template: it is taken out of its rarity pool (and its card
+    /// set's pool) and stops dropping from banners; minted copies stay valid
+    RetireTemplate {
+        card_type_id: u32,
+        /// Clear the retired flag instead (drops from its banners again, but is
+        /// not re-added to the pool)
+        #[arg(long)]
+        undo: bool,
+    },
+    /// Schedule a limited-time gacha banner with its own drop table
+    CreateBanner {
+        banner_id: u32,
+        name: String,
+        /// Unix timestamp (defaults to now)
+        #[arg(long)]
+        start: Option<i64>,
+        /// Unix timestamp
+        #[arg(long)]
+        end: i64,
+        #[arg(long, value_enum, default_value = "tickets")]
+        currency: CurrencyArg,
+        /// Cost of one pull
+        #[arg(long, default_value_t = 1)]
+        price: u64,
+        /// Max pulls per player (0 = unlimited)
+        #[arg(long, default_value_t = 0)]
+        pull_limit: u32,
+        /// Restrict the banner to cards of this card set
+        #[arg(long = "set", default_value_t = 0)]
+        set_id: u16,
+        /// ID or ID:WEIGHT (default weight 100)
+        #[arg(required = true, value_parser = manifest::parse_pool_entry)]
+        entries: Vec<PoolEntry>,
+    },
+    /// End a banner now
+    EndBanner { banner_id: u32 },
+    /// Grant gacha tickets to a registered player
+    GrantTickets { wallet: Pubkey, amount: u64 },
+    /// Grow every account of a type still in an older layout (the signer pays the rent)
+    Migrate {
+        #[arg(value_enum)]
+        accounts: MigrateArg,
+    },
+    /// Print GameConfig, EconomyConfig and the rarity pools
+    DumpConfig,
+    /// Print a player's profile
+    DumpPlayer { wallet: Pubkey },
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let rpc = RpcClient::new_with_commitment(cli.url.clone(), CommitmentConfig::confirmed());
+
+    match cli.command {
+        Command::DumpConfig => dump_config(&rpc),
+        Command::DumpPlayer { wallet } => dump_player(&rpc, &wallet),
+        command => {
+            let authority = load_keypair(cli.keypair)?;
+            run(&rpc, &authority, command)
+        }
+    }
+}
+
+fn run(rpc: &RpcClient, authority: &Keypair, command: Command) -> Result<()> {
+    let signer = authority.pubkey();
+
+    match command {
+        Command::Initialize { normal_pack_price, sol_to_bug_rate, ticket_price } => {
+            let ix = client::initialize(&signer, normal_pack_price, sol_to_bug_rate, ticket_price);
+            let signature = send(rpc, authority, vec![ix])?;
+            println!("Initialized game config {} ({})", client::get_game_config_pda(&zoo_contract::ID).0, signature);
+        }
+        Command::AddCreator { creators } => {
+            for creator in creators {
+                let signature = send(rpc, authority, vec![client::add_card_creator(&signer, &creator)])?;
+                println!("Added card creator {} ({})", creator, signature);
+            }
+        }
+        Command::SetPackType { pack_type, name, price, cards, guaranteed, trait_filter, card_type_ids, inactive, set_id } => {
+            let ix = client::update_pack_type(
+                &signer,
+                pack_type,
+                name,
+                price,
+                cards,
+                guaranteed.into(),
+                trait_filter.map(Into::into),
+                !inactive,
+                set_id,
+                &card_type_ids,
+            );
+            let signature = send(rpc, authority, vec![ix])?;
+            println!("Updated pack type {} ({})", pack_type, signature);
+        }
+        Command::SetEconomy {
+            base_trophy_gain,
+            trophy_loss,
+            max_streak_bonus,
+            win_reward,
+            loss_reward,
+            daily_reward_cap,
+            draw_trophy_delta,
+        } => {
+            let ix = client::update_economy_config(
+                &signer,
+                base_trophy_gain,
+                trophy_loss,
+                max_streak_bonus,
+                win_reward,
+                loss_reward,
+                daily_reward_cap,
+                draw_trophy_delta,
+            );
+            let signature = send(rpc, authority, vec![ix])?;
+            println!("Updated economy config ({})", signature);
+        }
+        Command::SetTiers { tiers } => {
+            let signature = send(rpc, authority, vec![client::update_tier_config(&signer, tiers)])?;
+            println!("Updated tier config ({})", signature);
+        }
+        Command::InitLeaderboard => {
+            let signature = send(rpc, authority, vec![client::initialize_leaderboard(&signer)])?;
+            println!("Initialized leaderboard {} ({})", client::get_leaderboard_pda(&zoo_contract::ID).0, signature);
+        }
+        Command::SetProgression {
+            xp_per_win,
+            xp_per_loss,
+            attack_per_level,
+            health_per_level,
+            max_bonus_bps,
+            level_thresholds,
+        } => {
+            let ix = client::update_progression_config(
+                &signer,
+                xp_per_win,
+                xp_per_loss,
+                level_thresholds,
+                attack_per_level,
+                health_per_level,
+                max_bonus_bps,
+            );
+            let signature = send(rpc, authority, vec![ix])?;
+            println!("Updated progression config ({})", signature);
+        }
+        Command::SetSynergy { tiers } => {
+            let ix = client::update_synergy_config(&signer, tiers);
+            let signature = send(rpc, authority, vec![ix])?;
+            println!("Updated synergy config ({})", signature);
+        }
+        Command::SetReroll { currency, price, keep_better } => {
+            let ix = client::update_reroll_config(&signer, currency.into(), price, keep_better);
+            let signature = send(rpc, authority, vec![ix])?;
+            println!("Updated reroll config ({})", signature);
+        }
+        Command::SetCardSet { set_id, name, release, inactive } => {
+            let release = match release {
+                Some(release) => release,
+                None => now()?,
+            };
+            let ix = client::update_card_set(&signer, set_id, name, release, !inactive);
+            let signature = send(rpc, authority, vec![ix])?;
+            println!("Updated card set {} ({})", set_id, signature);
+        }
+        Command::ImportTemplates { manifest } => import_templates(rpc, authority, &manifest)?,
+        Command::UpdatePool { rarity, set_id, entries } => {
+            let rarity: Rarity = rarity.into();
+            let count = entries.len();
+            let signature = send(rpc, authority, vec![update_pool(&signer, set_id, rarity, entries)])?;
+            println!("Updated {} cards in the {} ({})", count, pool_name(set_id, rarity), signature);
+        }
+        Command::RemoveFromPool { rarity, set_id, card_type_ids } => {
+            let rarity: Rarity = rarity.into();
+            let count = card_type_ids.len();
+            let ix = client::remove_from_rarity_pool(&signer, set_id, rarity, card_type_ids);
+            let signature = send(rpc, authority, vec![ix])?;
+            println!("Removed {} cards from the {} ({})", count, pool_name(set_id, rarity), signature);
+        }
+        Command::RetireTemplate { card_type_id, undo } => {
+            let template = client::fetch_card_template(rpc, card_type_id)?;
+            // Only pass the set pool if it exists; cards of a set may never have been pooled there
+            let set_pool = client::get_set_pool_pda(template.set_id, template.rarity, &zoo_contract::ID).0;
+            let has_set_pool = template.set_id != 0
+                && fetch_optional::<zoo_contract::RarityPool>(rpc, &set_pool)?.is_some();
+            let set_id = has_set_pool.then_some(template.set_id);
+            let ix = client::set_card_template_retired(&signer, card_type_id, template.rarity, set_id, !undo);
+            let signature = send(rpc, authority, vec![ix])?;
+            let action = if undo { "Un-retired" } else { "Retired" };
+            println!("{} card template {} ({})", action, card_type_id, signature);
+        }
+        Command::CreateBanner { banner_id, name, start, end, currency, price, pull_limit, set_id, entries } => {
+            let start = match start {
+                Some(start) => start,
+                None => now()?,
+            };
+            let ix = client::create_banner(
+                &signer,
+                banner_id,
+                name,
+                start,
+                end,
+                entries,
+                currency.into(),
+                price,
+                pull_limit,
+                set_id,
+            );
+            let signature = send(rpc, authority, vec![ix])?;
+            println!("Created banner {} ({})", banner_id, signature);
+        }
+        Command::EndBanner { banner_id } => {
+            let signature = send(rpc, authority, vec![client::end_banner(&signer, banner_id)])?;
+            println!("Ended banner {} ({})", banner_id, signature);
+        }
+        Command::GrantTickets { wallet, amount } => {
+            let signature = send(rpc, authority, vec![client::add_gacha_tickets(&signer, &wallet, amount)])?;
+            println!("Granted {} tickets to {} ({})", amount, wallet, signature);
+        }
+        Command::Migrate { accounts } => migrate(rpc, authority, accounts)?,
+        Command::DumpConfig | Command::DumpPlayer { .. } => unreachable!("read-only commands need no signer"),
+    }
+
+    Ok(())
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum MigrateArg {
+    /// CardInstance accounts minted before card XP
+    Cards,
+    /// PlayerProfile accounts registered before ratings and seasons
+    Profiles,
+    /// Standard rarity pools still holding plain card id lists
+    Pools,
+    /// The GameConfig created before seasons (migrate it first)
+    Config,
+    /// CardTemplate accounts created before creators, versions and abilities
+    Templates,
+}
+
+/// Migration instructions per transaction
+const MIGRATIONS_PER_TX: usize = 8;
+
+fn migrate(rpc: &RpcClient, authority: &Keypair, accounts: MigrateArg) -> Result<()> {
+    let signer = authority.pubkey();
+    let instructions: Vec<Instruction> = match accounts {
+        MigrateArg::Cards => stale_accounts(rpc, &CardInstance::DISCRIMINATOR, CardInstance::LEN)?
+            .iter()
+            .map(|data| client::migrate_card_instance(&signer, &Pubkey::try_from(&data[8..40]).unwrap()))
+            .collect(),
+        MigrateArg::Profiles => stale_accounts(rpc, &PlayerProfile::DISCRIMINATOR, PlayerProfile::LEN)?
+            .iter()
+            .map(|data| client::migrate_player_profile(&signer, &Pubkey::try_from(&data[8..40]).unwrap()))
+            .collect(),
+        MigrateArg::Pools => {
+            let mut instructions = Vec::new();
+            for rarity in [Rarity::Common, Rarity::Rare, Rarity::Legendary] {
+                let address = client::get_rarity_pool_pda(rarity, &zoo_contract::ID).0;
+                let account = rpc.get_account_with_commitment(&address, rpc.commitment())?.value;
+                if account.is_some_and(|account| account.data.len() < zoo_contract::RarityPool::LEN) {
+                    instructions.push(client::migrate_rarity_pool(&signer, rarity));
+                }
+            }
+            instructions
+        }
+        MigrateArg::Templates => stale_accounts(rpc, &CardTemplate::DISCRIMINATOR, CardTemplate::LEN)?
+            .iter()
+            .map(|data| client::migrate_card_template(&signer, u32::from_le_bytes(data[8..12].try_into().unwrap())))
+            .collect(),
+        MigrateArg::Config => {
+            let address = client::get_game_config_pda(&zoo_contract::ID).0;
+            let account = rpc.get_account_with_commitment(&address, rpc.commitment())?.value;
+            if account.is_some_and(|account| account.data.len() < zoo_contract::GameConfig::LEN) {
+                vec![client::migrate_game_config(&signer)]
+            } else {
+                Vec::new()
+            }
+        }
+    };
+
+    for batch in instructions.chunks(MIGRATIONS_PER_TX) {
+        let signature = send(rpc, authority, batch.to_vec())?;
+        println!("Migrated {} accounts ({})", batch.len(), signature);
+    }
+    println!("{} accounts migrated", instructions.len());
+    Ok(())
+}
+
+/// Data of the program accounts with `discriminator` that are still shorter than `len`
+fn stale_accounts(rpc: &RpcClient, discriminator: &[u8], len: usize) -> Result<Vec<Vec<u8>>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, discriminator.to_vec()))]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    Ok(rpc
+        .get_program_accounts_with_config(&zoo_contract::ID, config)?
+        .into_iter()
+        .map(|(_, account)| account.data)
+        .filter(|data| data.len() < len)
+        .collect())
+}
+
+fn now() -> Result<i64> {
+    Ok(std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?.as_secs() as i64)
+}
+
+/// Set 0 is the standard pool, anything else a card set's own pool
+fn update_pool(signer: &Pubkey, set_id: u16, rarity: Rarity, entries: Vec<PoolEntry>) -> Instruction {
+    if set_id == 0 {
+        client::update_rarity_pool(signer, rarity, entries)
+    } else {
+        client::update_set_pool(signer, set_id, rarity, entries)
+    }
+}
+
+fn pool_name(set_id: u16, rarity: Rarity) -> String {
+    if set_id == 0 {
+        format!("{:?} pool", rarity)
+    } else {
+        format!("set {} {:?} pool", set_id, rarity)
+    }
+}
+
+fn load_keypair(path: Option<PathBuf>) -> Result<Keypair> {
+    let path = match path {
+        Some(path) => path,
+        None => {
+            let home = std::env::var("HOME").context("HOME not set; pass --keypair")?;
+            PathBuf::from(home).join(".config/solana/id.json")
+        }
+    };
+    read_keypair_file(&path).map_err(|err| anyhow::anyhow!("reading keypair {}: {}", path.display(), err))
+}
+
+fn send(rpc: &RpcClient, payer: &Keypair, instructions: Vec<Instruction>) -> Result<Signature> {
+    let blockhash = rpc.get_latest_blockhash()?;
+    let tx = Transaction::new_signed_with_payer(&instructions, Some(&payer.pubkey()), &[payer], blockhash);
+    Ok(rpc.send_and_confirm_transaction(&tx)?)
+}
+
+/// Pool entries per transaction: each one adds its template account, and a legacy
+/// transaction only fits about 24 of them
+const POOL_ENTRIES_PER_TX: usize = 20;
+
+fn import_templates(rpc: &RpcClient, authority: &Keypair, path: &std::path::Path) -> Result<()> {
+    let signer = authority.pubkey();
+    let mut manifest = manifest::load(path)?;
+
+    let ids: Vec<u32> = manifest.templates.iter().map(|t| t.card_type_id).collect();
+    let mut existing: Vec<CardTemplate> = Vec::new();
+    // Templates still in the layout from before migrate_card_template can't be read
+    let mut stale: Vec<u32> = Vec::new();
+    for chunk in ids.chunks(100) {
+        let addresses: Vec<Pubkey> = chunk
+            .iter()
+            .map(|id| client::get_card_template_pda(*id, &zoo_contract::ID).0)
+            .collect();
+        for (id, account) in chunk.iter().zip(rpc.get_multiple_accounts(&addresses)?) {
+            match account {
+                Some(account) if account.data.len() < CardTemplate::LEN => stale.push(*id),
+                Some(account) => existing.push(client::deserialize_account::<CardTemplate>(&account.data)?),
+                None => {}
+            }
+        }
+    }
+    manifest.templates.retain(|t| !stale.contains(&t.card_type_id));
+
+    // The standard pools plus the pools of every set the manifest or the chain mentions
+    let mut set_ids: Vec<u16> = vec![0];
+    let on_chain_sets = existing.iter().map(|t| t.set_id);
+    for set_id in manifest.templates.iter().map(|t| t.set_id).chain(on_chain_sets) {
+        if !set_ids.contains(&set_id) {
+            set_ids.push(set_id);
+        }
+    }
+    let now = now()?;
+    let mut pools = Vec::new();
+    let mut released_sets = Vec::new();
+    for set_id in set_ids {
+        if set_id != 0 {
+            let address = client::get_card_set_pda(set_id, &zoo_contract::ID).0;
+            let card_set: Option<zoo_contract::CardSet> = fetch_optional(rpc, &address)?;
+            if card_set.is_some_and(|set| zoo_contract::is_card_set_available(&set, now)) {
+                released_sets.push(set_id);
+            }
+        }
+        for rarity in import::RARITIES {
+            let address = if set_id == 0 {
+                client::get_rarity_pool_pda(rarity, &zoo_contract::ID).0
+            } else {
+                client::get_set_pool_pda(set_id, rarity, &zoo_contract::ID).0
+            };
+            let pool: Option<zoo_contract::RarityPool> = fetch_optional(rpc, &address)?;
+            pools.push((set_id, rarity, pool.map(|p| p.entries).unwrap_or_default()));
+        }
+    }
+
+    let plan = import::plan(
+        &manifest,
+        |id| existing.iter().find(|t| t.card_type_id == id).cloned(),
+        |set_id, rarity| {
+            pools
+                .iter()
+                .find(|(s, r, _)| *s == set_id && *r == rarity)
+                .map(|(_, _, entries)| entries.clone())
+                .unwrap_or_default()
+        },
+        |set_id| released_sets.contains(&set_id),
+    );
+
+    for template in &plan.create {
+        let ix = client::create_card_template(
+            &signer,
+            template.card_type_id,
+            template.name.clone(),
+            template.trait_type.into(),
+            template.rarity.into(),
+            template.min_attack,
+            template.max_attack,
+            template.min_health,
+            template.max_health,
+            template.description.clone(),
+            template.image_uri.clone(),
+            template.set_id,
+            template.ability.into(),
+        );
+        let signature = send(rpc, authority, vec![ix])
+            .with_context(|| format!("creating card template {}", template.card_type_id))?;
+        println!("Created card template {} ({})", template.card_type_id, signature);
+    }
+    for (set_id, rarity, entries) in plan.pool_additions {
+        for batch in entries.chunks(POOL_ENTRIES_PER_TX) {
+            let signature = send(rpc, authority, vec![update_pool(&signer, set_id, rarity, batch.to_vec())])
+                .with_context(|| format!("updating the {}", pool_name(set_id, rarity)))?;
+            println!("Updated {} cards in the {} ({})", batch.len(), pool_name(set_id, rarity), signature);
+        }
+    }
+
+    println!(
+        "{} created, {} already up to date, {} differ on chain",
+        plan.create.len(),
+        plan.unchanged.len(),
+        plan.conflicting.len()
+    );
+    for card_type_id in &plan.conflicting {
+        println!("  card template {} differs from the manifest and was left unchanged", card_type_id);
+    }
+    if !plan.unreleased.is_empty() {
+        println!(
+            "{} kept out of the standard pools until their set is released: {:?}",
+            plan.unreleased.len(),
+            plan.unreleased
+        );
+        println!("  import again after the release to add them");
+    }
+    if !stale.is_empty() {
+        println!("{} skipped in the pre-migration layout: {:?}", stale.len(), stale);
+        println!("  run `migrate templates` first, then import again");
+    }
+    Ok(())
+}
+
+/// Fetch an account that may not exist yet
+fn fetch_optional<T: AccountDeserialize>(rpc: &RpcClient, address: &Pubkey) -> Result<Option<T>> {
+    match rpc.get_account_with_commitment(address, rpc.commitment())?.value {
+        Some(account) => Ok(Some(client::deserialize_account(&account.data)?)),
+        None => Ok(None),
+    }
+}
+
+fn dump_config(rpc: &RpcClient) -> Result<()> {
+    let config = client::fetch_game_config(rpc)?;
+    println!("GameConfig {}", client::get_game_config_pda(&zoo_contract::ID).0);
+    println!("  authority:          {}", config.authority);
+    println!("  card_creators:      {}", config.card_creators.len());
+    for creator in &config.card_creators {
+        println!("    - {}", creator);
+    }
+    println!("  normal_pack_price:  {}", config.normal_pack_price);
+    println!("  sol_to_bug_rate:    {}", config.sol_to_bug_rate);
+    println!("  ticket_price:       {}", config.ticket_price);
+    println!("  current_season_id:  {}", config.current_season_id);
+    println!("  season_floor:       {}", config.season_trophy_floor);
+
+    match client::fetch_economy_config(rpc) {
+        Ok(economy) => {
+            println!("EconomyConfig");
+            println!("  base_trophy_gain:   {}", economy.base_trophy_gain);
+            println!("  trophy_loss:        {}", economy.trophy_loss);
+            println!("  max_streak_bonus:   {}", economy.max_streak_bonus);
+            println!("  win_reward:         {}", economy.win_reward);
+            println!("  loss_reward:        {}", economy.loss_reward);
+            println!("  daily_reward_cap:   {}", economy.daily_reward_cap);
+            println!("  draw_trophy_delta:  {}", economy.draw_trophy_delta);
+        }
+        Err(_) => {
+            let defaults = EconomyConfig::DEFAULTS;
+            println!(
+                "EconomyConfig not created (defaults apply: +{}/-{} trophies, {} BUG per win)",
+                defaults.base_trophy_gain, defaults.trophy_loss, defaults.win_reward
+            );
+        }
+    }
+
+    match client::fetch_tier_config(rpc) {
+        Ok(tier_config) => {
+            println!("TierConfig");
+            for (index, tier) in tier_config.tiers.iter().enumerate() {
+                let label = format!("{:?}:", ArenaTier::from_index(index));
+                println!("  {:<20}{}+{}", label, tier.min_trophies, if tier.has_floor { " (floor)" } else { "" });
+            }
+        }
+        Err(_) => println!("TierConfig not created (everyone is Bronze)"),
+    }
+
+    match client::fetch_leaderboard(rpc) {
+        Ok(leaderboard) => println!("Leaderboard: {} entries", leaderboard.entries.len()),
+        Err(_) => println!("Leaderboard not created (matches don't rank players)"),
+    }
+
+    match client::fetch_reroll_config(rpc) {
+        Ok(reroll) => {
+            println!("RerollConfig");
+            println!("  price:              {} {:?}", reroll.price, reroll.currency);
+            println!("  keep_better:        {}", reroll.keep_better);
+        }
+        Err(_) => println!("RerollConfig not created (rerolls disabled)"),
+    }
+
+    match client::fetch_progression_config(rpc) {
+        Ok(progression) => {
+            let thresholds: Vec<String> = progression.level_thresholds.iter().map(u32::to_string).collect();
+            println!("ProgressionConfig");
+            println!("  xp win / loss:      {} / {}", progression.xp_per_win, progression.xp_per_loss);
+            println!("  level thresholds:   {}", thresholds.join(" "));
+            println!(
+                "  per level:          +{} ATK, +{} HP (cap +{} bps)",
+                progression.attack_per_level, progression.health_per_level, progression.max_bonus_bps
+            );
+        }
+        Err(_) => println!("ProgressionConfig not created (no card XP)"),
+    }
+
+    match client::fetch_synergy_config(rpc) {
+        Ok(synergy) => {
+            println!("SynergyConfig");
+            for tier in &synergy.tiers {
+                let label = format!("{:?} x{}:", tier.trait_type, tier.min_units);
+                println!("  {:<20}+{} bps ATK, +{} bps HP", label, tier.attack_bonus_bps, tier.health_bonus_bps);
+            }
+        }
+        Err(_) => println!("SynergyConfig not created (no trait synergies)"),
+    }
+
+    for rarity in [Rarity::Common, Rarity::Rare, Rarity::Legendary] {
+        match client::fetch_rarity_pool(rpc, rarity) {
+            Ok(pool) => {
+                let entries: Vec<String> =
+                    pool.entries.iter().map(|e| format!("{}:{}", e.card_type_id, e.weight)).collect();
+                println!("{:?} pool (id:weight): {}", rarity, entries.join(" "));
+            }
+            Err(_) => println!("{:?} pool: not created", rarity),
+        }
+    }
+
+    Ok(())
+}
+
+fn dump_player(rpc: &RpcClient, wallet: &Pubkey) -> Result<()> {
+    let profile = client::fetch_player_profile(rpc, wallet)?;
+    println!("PlayerProfile {}", client::get_player_profile_pda(wallet, &zoo_contract::ID).0);
+    println!("  wallet:             {}", profile.wallet);
+    println!("  username:           {}", profile.username);
+    println!("  starter claimed:    {}", profile.has_claimed_starter_pack);
+    println!("  gacha_tickets:      {}", profile.gacha_tickets);
+    println!("  bug_balance:        {}", profile.bug_balance);
+    println!("  crafting_dust:      {}", profile.crafting_dust);
+    println!("  trophies:           {}", profile.trophies);
+    println!("  tier:               {:?}", profile.tier);
+    println!("  rating:             {}", profile.rating);
+    println!(
+        "  record:             {}W / {}L / {}D (streak {})",
+        profile.total_wins, profile.total_losses, profile.total_draws, profile.win_streak
+    );
+    println!("  season:             {} (best {})", profile.season_id, profile.season_best_trophies);
+    if profile.collection_pages > 0 {
+        let card_mints = client::fetch_collection(rpc, wallet)?;
+        println!("  collection:         {} cards in {} pages", card_mints.len(), profile.collection_pages);
+    }
+    Ok(())
+}
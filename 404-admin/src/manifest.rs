@@ -0,0 +1,286 @@
+//! Card template manifests, in TOML or JSON.
+//!
+//! ```toml
+//! [[templates]]
+//! card_type_id = 404
+//! name = "404 Deer"
+//! trait_type = "warrior"
+//! rarity = "common"
+//! min_attack = 8
+//! max_attack = 12
+//! min_health = 20
+//! max_health = 30
+//! description = "It was here a moment ago."
+//! image_uri = "ipfs://.../404_Deer.png"
+//! weight = 100                # optional drop weight inside the rarity pool
+//! set_id = 1                  # optional card set (default 0, the base game)
+//! ability = { kind = "shield", amount = 20 }  # optional (default none)
+//! ```
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::path::Path;
+use zoo_contract::{Ability, EntryCurrency, PoolEntry, Rarity, SynergyTier, TierThreshold, TraitType};
+
+#[derive(Deserialize)]
+pub struct Manifest {
+    pub templates: Vec<TemplateEntry>,
+}
+
+#[derive(Deserialize)]
+pub struct TemplateEntry {
+    pub card_type_id: u32,
+    pub name: String,
+    pub trait_type: TraitArg,
+    pub rarity: RarityArg,
+    pub min_attack: u16,
+    pub max_attack: u16,
+    pub min_health: u16,
+    pub max_health: u16,
+    pub description: String,
+    pub image_uri: String,
+    #[serde(default = "default_weight")]
+    pub weight: u16,
+    #[serde(default)]
+    pub set_id: u16,
+    #[serde(default)]
+    pub ability: AbilityArg,
+}
+
+fn default_weight() -> u16 {
+    PoolEntry::DEFAULT_WEIGHT
+}
+
+#[derive(Deserialize, Clone, Copy, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum TraitArg {
+    Warrior,
+    Archer,
+    Assassin,
+    Tank,
+    Mage,
+    Support,
+}
+
+impl From<TraitArg> for TraitType {
+    fn from(arg: TraitArg) -> Self {
+        match arg {
+            TraitArg::Warrior => TraitType::Warrior,
+            TraitArg::Archer => TraitType::Archer,
+            TraitArg::Assassin => TraitType::Assassin,
+            TraitArg::Tank => TraitType::Tank,
+            TraitArg::Mage => TraitType::Mage,
+            TraitArg::Support => TraitType::Support,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AbilityArg {
+    #[default]
+    None,
+    Lifesteal { percent: u8 },
+    Splash { percent: u8 },
+    Shield { amount: u16 },
+    DoubleAttack { percent: u8 },
+}
+
+impl From<AbilityArg> for Ability {
+    fn from(arg: AbilityArg) -> Self {
+        match arg {
+            AbilityArg::None => Ability::None,
+            AbilityArg::Lifesteal { percent } => Ability::Lifesteal { percent },
+            AbilityArg::Splash { percent } => Ability::Splash { percent },
+            AbilityArg::Shield { amount } => Ability::Shield { amount },
+            AbilityArg::DoubleAttack { percent } => Ability::DoubleAttack { percent },
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum RarityArg {
+    Common,
+    Rare,
+    Legendary,
+}
+
+impl From<RarityArg> for Rarity {
+    fn from(arg: RarityArg) -> Self {
+        match arg {
+            RarityArg::Common => Rarity::Common,
+            RarityArg::Rare => Rarity::Rare,
+            RarityArg::Legendary => Rarity::Legendary,
+        }
+    }
+}
+
+/// How banner pulls are paid
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum CurrencyArg {
+    Bug,
+    Tickets,
+}
+
+impl From<CurrencyArg> for EntryCurrency {
+    fn from(arg: CurrencyArg) -> Self {
+        match arg {
+            CurrencyArg::Bug => EntryCurrency::Bug,
+            CurrencyArg::Tickets => EntryCurrency::Tickets,
+        }
+    }
+}
+
+/// Parse a `ID[:WEIGHT]` command line pool entry
+pub fn parse_pool_entry(arg: &str) -> std::result::Result<PoolEntry, String> {
+    let (id, weight) = match arg.split_once(':') {
+        Some((id, weight)) => (id, weight.parse().map_err(|_| format!("invalid weight in {}", arg))?),
+        None => (arg, PoolEntry::DEFAULT_WEIGHT),
+    };
+    let card_type_id = id.parse().map_err(|_| format!("invalid card id in {}", arg))?;
+    if weight == 0 {
+        return Err(format!("{}: weight must be greater than 0", arg));
+    }
+    Ok(PoolEntry { card_type_id, weight })
+}
+
+/// Parse a `TRAIT:MIN_UNITS:ATTACK_BPS:HEALTH_BPS` command line synergy tier
+pub fn parse_synergy_tier(arg: &str) -> std::result::Result<SynergyTier, String> {
+    let parts: Vec<&str> = arg.split(':').collect();
+    let [trait_name, min_units, attack, health] = parts[..] else {
+        return Err(format!("{}: expected TRAIT:MIN_UNITS:ATTACK_BPS:HEALTH_BPS", arg));
+    };
+    let trait_type = <TraitArg as clap::ValueEnum>::from_str(trait_name, true)
+        .map_err(|_| format!("invalid trait in {}", arg))?;
+    let bps = |value: &str| value.parse::<u16>().map_err(|_| format!("invalid bonus in {}", arg));
+    Ok(SynergyTier {
+        trait_type: trait_type.into(),
+        min_units: min_units.parse().map_err(|_| format!("invalid unit count in {}", arg))?,
+        attack_bonus_bps: bps(attack)?,
+        health_bonus_bps: bps(health)?,
+    })
+}
+
+/// Parse a `MIN_TROPHIES[:floor]` command line arena tier threshold
+pub fn parse_tier_threshold(arg: &str) -> std::result::Result<TierThreshold, String> {
+    let (min_trophies, has_floor) = match arg.split_once(':') {
+        Some((min_trophies, "floor")) => (min_trophies, true),
+        Some(_) => return Err(format!("{}: expected MIN_TROPHIES or MIN_TROPHIES:floor", arg)),
+        None => (arg, false),
+    };
+    Ok(TierThreshold {
+        min_trophies: min_trophies.parse().map_err(|_| format!("invalid trophies in {}", arg))?,
+        has_floor,
+    })
+}
+
+/// Parse a manifest; the format is picked from the extension (.toml or .json)
+pub fn load(path: &Path) -> Result<Manifest> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    let manifest = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => parse_toml(&text)?,
+        Some("json") => parse_json(&text)?,
+        _ => bail!("{}: manifest must be .toml or .json", path.display()),
+    };
+    validate(&manifest)?;
+    Ok(manifest)
+}
+
+fn parse_toml(text: &str) -> Result<Manifest> {
+    Ok(toml::from_str(text)?)
+}
+
+fn parse_json(text: &str) -> Result<Manifest> {
+    Ok(serde_json::from_str(text)?)
+}
+
+/// Catch mistakes before sending anything: duplicate ids, inverted stat ranges, zero weights
+fn validate(manifest: &Manifest) -> Result<()> {
+    let mut seen = std::collections::HashSet::new();
+    for template in &manifest.templates {
+        if !seen.insert(template.card_type_id) {
+            bail!("duplicate card_type_id {}", template.card_type_id);
+        }
+        if template.min_attack > template.max_attack || template.min_health > template.max_health {
+            bail!("card {}: min stat greater than max", template.card_type_id);
+        }
+        if template.weight == 0 {
+            bail!("card {}: weight must be greater than 0", template.card_type_id);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOML: &str = r#"
+        [[templates]]
+        card_type_id = 404
+        name = "404 Deer"
+        trait_type = "warrior"
+        rarity = "common"
+        min_attack = 8
+        max_attack = 12
+        min_health = 20
+        max_health = 30
+        description = "It was here a moment ago."
+        image_uri = "ipfs://deer"
+    "#;
+
+    #[test]
+    fn test_toml_and_json_manifests_parse_the_same() {
+        let from_toml = parse_toml(TOML).unwrap();
+        let json = r#"{ "templates": [{
+            "card_type_id": 404, "name": "404 Deer", "trait_type": "warrior", "rarity": "common",
+            "min_attack": 8, "max_attack": 12, "min_health": 20, "max_health": 30,
+            "description": "It was here a moment ago.", "image_uri": "ipfs://deer"
+        }] }"#;
+        let from_json = parse_json(json).unwrap();
+
+        for manifest in [from_toml, from_json] {
+            let template = &manifest.templates[0];
+            assert_eq!(template.card_type_id, 404);
+            assert_eq!(Rarity::from(template.rarity), Rarity::Common);
+            assert_eq!(TraitType::from(template.trait_type), TraitType::Warrior);
+            assert_eq!(template.weight, PoolEntry::DEFAULT_WEIGHT);
+            assert_eq!(template.set_id, 0);
+            assert_eq!(Ability::from(template.ability), Ability::None);
+            assert!(validate(&manifest).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_parse_pool_entry() {
+        assert_eq!(parse_pool_entry("404").unwrap(), PoolEntry { card_type_id: 404, weight: 100 });
+        assert_eq!(parse_pool_entry("451:300").unwrap(), PoolEntry { card_type_id: 451, weight: 300 });
+        assert!(parse_pool_entry("451:0").is_err());
+        assert!(parse_pool_entry("deer").is_err());
+    }
+
+    #[test]
+    fn test_parse_synergy_tier() {
+        let tier = parse_synergy_tier("tank:2:1000:1500").unwrap();
+        assert_eq!(tier.trait_type, TraitType::Tank);
+        assert_eq!((tier.min_units, tier.attack_bonus_bps, tier.health_bonus_bps), (2, 1000, 1500));
+        assert!(parse_synergy_tier("tank:2:1000").is_err());
+        assert!(parse_synergy_tier("dragon:2:1000:1000").is_err());
+    }
+
+    #[test]
+    fn test_parse_tier_threshold() {
+        let tier = parse_tier_threshold("800:floor").unwrap();
+        assert_eq!((tier.min_trophies, tier.has_floor), (800, true));
+        assert!(!parse_tier_threshold("400").unwrap().has_floor);
+        assert!(parse_tier_threshold("400:flor").is_err());
+        assert!(parse_tier_threshold("gold").is_err());
+    }
+
+    #[test]
+    fn test_validate_rejects_duplicates() {
+        let twice = format!("{}{}", TOML, TOML);
+        assert!(validate(&parse_toml(&twice).unwrap()).is_err());
+    }
+}
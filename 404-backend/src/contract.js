@@ -51,6 +51,30 @@ function getGameConfigPDA() {
   )
 }
 
+// Get EconomyConfig PDA
+function getEconomyConfigPDA() {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from('economy_config')],
+    PROGRAM_ID
+  )
+}
+
+// Get Leaderboard PDA
+function getLeaderboardPDA() {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from('leaderboard')],
+    PROGRAM_ID
+  )
+}
+
+// Get TierConfig PDA
+function getTierConfigPDA() {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from('tier_config')],
+    PROGRAM_ID
+  )
+}
+
 // Get PlayerProfile PDA
 function getPlayerProfilePDA(playerPubkey) {
   return PublicKey.findProgramAddressSync(
@@ -59,12 +83,40 @@ function getPlayerProfilePDA(playerPubkey) {
   )
 }
 
+/**
+ * Keys of the optional match config accounts (economy_config, leaderboard, tier_config)
+ * The program id stands in for those the admin hasn't created yet, so matches
+ * are recorded with the defaults (as client.rs fetch_match_configs does)
+ */
+async function getMatchConfigKeys() {
+  const [economyConfigPDA] = getEconomyConfigPDA()
+  const [leaderboardPDA] = getLeaderboardPDA()
+  const [tierConfigPDA] = getTierConfigPDA()
+  const [economyConfig, leaderboard, tierConfig] = await connection.getMultipleAccountsInfo([
+    economyConfigPDA,
+    leaderboardPDA,
+    tierConfigPDA,
+  ])
+  
+  return [
+    { pubkey: economyConfig ? economyConfigPDA : PROGRAM_ID, isSigner: false, isWritable: false },
+    { pubkey: leaderboard ? leaderboardPDA : PROGRAM_ID, isSigner: false, isWritable: !!leaderboard },
+    { pubkey: tierConfig ? tierConfigPDA : PROGRAM_ID, isSigner: false, isWritable: false },
+  ]
+}
+
 // Anchor instruction discriminator for "record_match_result"
 // sha256("global:record_match_result")[0..8]
 function getRecordMatchResultDiscriminator() {
   return Buffer.from([37, 251, 4, 178, 56, 184, 50, 210])
 }
 
+// Anchor instruction discriminator for "record_match_draw"
+// sha256("global:record_match_draw")[0..8]
+function getRecordMatchDrawDiscriminator() {
+  return Buffer.from([1, 116, 9, 18, 33, 188, 32, 226])
+}
+
 /**
  * Record match result on-chain
  * @param {string} winnerWallet - Winner's wallet address (base58)
@@ -84,6 +136,7 @@ export async function recordMatchResult(winnerWallet, loserWallet) {
     const [winnerProfilePDA] = getPlayerProfilePDA(winnerPubkey)
     const [loserProfilePDA] = getPlayerProfilePDA(loserPubkey)
     const [gameConfigPDA] = getGameConfigPDA()
+    const matchConfigKeys = await getMatchConfigKeys()
     
     const instruction = new TransactionInstruction({
       keys: [
@@ -91,6 +144,10 @@ export async function recordMatchResult(winnerWallet, loserWallet) {
         { pubkey: loserProfilePDA, isSigner: false, isWritable: true },
         { pubkey: gameConfigPDA, isSigner: false, isWritable: false },
         { pubkey: authority.publicKey, isSigner: true, isWritable: false },
+        ...matchConfigKeys,
+        // progression_config (optional, only needed with decks for card XP):
+        // the program id stands in for an account that isn't passed
+        { pubkey: PROGRAM_ID, isSigner: false, isWritable: false },
       ],
       programId: PROGRAM_ID,
       data: getRecordMatchResultDiscriminator(),
@@ -114,4 +171,52 @@ export async function recordMatchResult(winnerWallet, loserWallet) {
   }
 }
 
+/**
+ * Record a drawn match on-chain
+ * @param {string} playerAWallet - First player's wallet address (base58)
+ * @param {string} playerBWallet - Second player's wallet address (base58)
+ * @returns {Promise<{success: boolean, txId?: string, error?: string}>}
+ */
+export async function recordMatchDraw(playerAWallet, playerBWallet) {
+  const authority = getAuthorityKeypair()
+  if (!authority) {
+    return { success: false, error: 'Authority not configured' }
+  }
+  
+  try {
+    const [playerAProfilePDA] = getPlayerProfilePDA(new PublicKey(playerAWallet))
+    const [playerBProfilePDA] = getPlayerProfilePDA(new PublicKey(playerBWallet))
+    const [gameConfigPDA] = getGameConfigPDA()
+    const matchConfigKeys = await getMatchConfigKeys()
+    
+    const instruction = new TransactionInstruction({
+      keys: [
+        { pubkey: playerAProfilePDA, isSigner: false, isWritable: true },
+        { pubkey: playerBProfilePDA, isSigner: false, isWritable: true },
+        { pubkey: gameConfigPDA, isSigner: false, isWritable: false },
+        { pubkey: authority.publicKey, isSigner: true, isWritable: false },
+        ...matchConfigKeys,
+      ],
+      programId: PROGRAM_ID,
+      data: getRecordMatchDrawDiscriminator(),
+    })
+    
+    const transaction = new Transaction().add(instruction)
+    transaction.feePayer = authority.publicKey
+    transaction.recentBlockhash = (await connection.getLatestBlockhash()).blockhash
+    
+    transaction.sign(authority)
+    
+    const txId = await connection.sendRawTransaction(transaction.serialize())
+    await connection.confirmTransaction(txId, 'confirmed')
+    
+    console.log(`✅ Match draw recorded: ${playerAWallet.slice(0, 8)}... vs ${playerBWallet.slice(0, 8)}..., tx=${txId}`)
+    
+    return { success: true, txId }
+  } catch (error) {
+    console.error('❌ Failed to record match draw:', error.message)
+    return { success: false, error: error.message }
+  }
+}
+
 export { getAuthorityKeypair, connection }
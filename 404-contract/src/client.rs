@@ -0,0 +1,1658 @@
+//! Off-chain client helpers (enabled with the `client` feature)
+//!
+//! PDA derivation for every seed, one instruction builder per program
+//! instruction, and account fetch/deserialise helpers, so Rust services and
+//! tests can drive the program without hand-building account lists.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::sysvar;
+use anchor_lang::{system_program, AccountDeserialize, InstructionData};
+use anchor_spl::associated_token::{self, get_associated_token_address};
+use anchor_spl::token;
+use solana_client::rpc_client::RpcClient;
+
+use crate::{accounts, instruction};
+use crate::{
+    Ability, Banner, BannerPulls, CardInstance, CardSet, Collection, CardTemplate, CardTemplateValues, EconomyConfig,
+    EntryCurrency, GameConfig, Leaderboard, Listing, PackType, PendingPull, PlayerDeck, PlayerProfile,
+    PlayerSeasonRecord, PoolEntry, ProgressionConfig, Rarity, RarityPool, RerollConfig, Season,
+    SeasonRewardTier, SynergyConfig, SynergyTier, TierConfig, TierThreshold, TraitType, Tournament, TournamentVault,
+};
+
+pub use crate::get_card_template_pda;
+
+// ============================================================================
+// PDA Helpers
+// ============================================================================
+
+/// Derive the PDA for the global game config
+pub fn get_game_config_pda(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"game_config"], program_id)
+}
+
+/// Derive the PDA for the economy config
+pub fn get_economy_config_pda(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"economy_config"], program_id)
+}
+
+/// Derive the PDA for the arena tier config
+pub fn get_tier_config_pda(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"tier_config"], program_id)
+}
+
+/// Derive the PDA for card XP and levelling
+pub fn get_progression_config_pda(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"progression_config"], program_id)
+}
+
+/// Derive the PDA for the trait synergy bonuses
+pub fn get_synergy_config_pda(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"synergy_config"], program_id)
+}
+
+/// Derive the PDA for the stat reroll rules
+pub fn get_reroll_config_pda(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"reroll_config"], program_id)
+}
+
+/// Derive the PDA for a rarity pool
+pub fn get_rarity_pool_pda(rarity: Rarity, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"rarity_pool", &[rarity.to_discriminant()]], program_id)
+}
+
+/// Derive the PDA for a card set
+pub fn get_card_set_pda(set_id: u16, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"card_set", set_id.to_le_bytes().as_ref()], program_id)
+}
+
+/// Derive the PDA for a card set's own rarity pool
+pub fn get_set_pool_pda(set_id: u16, rarity: Rarity, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"set_pool", set_id.to_le_bytes().as_ref(), &[rarity.to_discriminant()]],
+        program_id,
+    )
+}
+
+/// Derive the PDA for a pack type
+pub fn get_pack_type_pda(pack_type: u8, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"pack_type", &[pack_type]], program_id)
+}
+
+/// Derive the PDA for a player's profile
+pub fn get_player_profile_pda(wallet: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"player_profile", wallet.as_ref()], program_id)
+}
+
+/// Derive the PDA holding a card's rolled stats
+pub fn get_card_instance_pda(card_mint: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"card_instance", card_mint.as_ref()], program_id)
+}
+
+/// Derive the PDA for a card's marketplace listing
+pub fn get_listing_pda(card_mint: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"listing", card_mint.as_ref()], program_id)
+}
+
+/// Derive the escrow token account holding a listed card
+pub fn get_escrow_pda(card_mint: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"escrow", card_mint.as_ref()], program_id)
+}
+
+/// Derive the PDA for one of a player's deck slots (0-4)
+pub fn get_player_deck_pda(owner: &Pubkey, deck_index: u8, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"player_deck", owner.as_ref(), &[deck_index]], program_id)
+}
+
+/// Derive the PDA for one page of a player's card collection index
+pub fn get_collection_pda(owner: &Pubkey, page: u8, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"collection", owner.as_ref(), &[page]], program_id)
+}
+
+/// Derive the PDA for the top-100 leaderboard
+pub fn get_leaderboard_pda(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"leaderboard"], program_id)
+}
+
+/// Derive the PDA for a tournament
+pub fn get_tournament_pda(tournament_id: u64, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"tournament", tournament_id.to_le_bytes().as_ref()], program_id)
+}
+
+/// Derive the prize vault of a tournament (seeded by the tournament address)
+pub fn get_tournament_vault_pda(tournament: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"tournament_vault", tournament.as_ref()], program_id)
+}
+
+/// Derive the PDA for a season
+pub fn get_season_pda(season_id: u32, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"season", season_id.to_le_bytes().as_ref()], program_id)
+}
+
+/// Derive the PDA recording a player's claimed season rewards
+pub fn get_player_season_pda(wallet: &Pubkey, season_id: u32, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"player_season", wallet.as_ref(), season_id.to_le_bytes().as_ref()],
+        program_id,
+    )
+}
+
+/// Derive the PDA for a gacha banner
+pub fn get_banner_pda(banner_id: u32, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"banner", banner_id.to_le_bytes().as_ref()], program_id)
+}
+
+/// Derive the PDA counting a player's pulls on a banner (seeded by the banner address)
+pub fn get_banner_pulls_pda(banner: &Pubkey, wallet: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"banner_pulls", banner.as_ref(), wallet.as_ref()], program_id)
+}
+
+/// Derive the PDA holding a player's paid, not yet claimed pull
+pub fn get_pending_pull_pda(wallet: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"pending_pull", wallet.as_ref()], program_id)
+}
+
+fn game_config() -> Pubkey {
+    get_game_config_pda(&crate::ID).0
+}
+
+fn player_profile(wallet: &Pubkey) -> Pubkey {
+    get_player_profile_pda(wallet, &crate::ID).0
+}
+
+fn rarity_pool(rarity: Rarity) -> Pubkey {
+    get_rarity_pool_pda(rarity, &crate::ID).0
+}
+
+/// The standard pool (set 0) or a card set's pool
+fn pool_of_set(set_id: u16, rarity: Rarity) -> Pubkey {
+    if set_id == 0 {
+        rarity_pool(rarity)
+    } else {
+        get_set_pool_pda(set_id, rarity, &crate::ID).0
+    }
+}
+
+fn card_set(set_id: u16) -> Option<Pubkey> {
+    (set_id != 0).then(|| get_card_set_pda(set_id, &crate::ID).0)
+}
+
+/// The collection page to keep up to date, if the caller passes one
+fn collection(owner: &Pubkey, page: Option<u8>) -> Option<Pubkey> {
+    page.map(|page| get_collection_pda(owner, page, &crate::ID).0)
+}
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+/// Minted cards passed in remaining_accounts as (template, mint, token account, card instance)
+fn with_cards(mut ix: Instruction, player: &Pubkey, cards: &[(u32, Pubkey)]) -> Instruction {
+    for (card_type_id, card_mint) in cards {
+        ix.accounts.push(AccountMeta::new_readonly(get_card_template_pda(*card_type_id, &crate::ID).0, false));
+        ix.accounts.push(AccountMeta::new(*card_mint, false));
+        ix.accounts.push(AccountMeta::new(get_associated_token_address(player, card_mint), false));
+        ix.accounts.push(AccountMeta::new(get_card_instance_pda(card_mint, &crate::ID).0, false));
+    }
+    ix
+}
+
+/// Profiles passed in remaining_accounts (read-only unless `writable`)
+fn with_profiles(mut ix: Instruction, wallets: &[Pubkey], writable: bool) -> Instruction {
+    ix.accounts.extend(wallets.iter().map(|wallet| {
+        let profile = player_profile(wallet);
+        if writable {
+            AccountMeta::new(profile, false)
+        } else {
+            AccountMeta::new_readonly(profile, false)
+        }
+    }));
+    ix
+}
+
+/// Card templates passed read-only in remaining_accounts, in order
+fn with_templates(mut ix: Instruction, card_type_ids: impl IntoIterator<Item = u32>) -> Instruction {
+    ix.accounts.extend(
+        card_type_ids
+            .into_iter()
+            .map(|card_type_id| AccountMeta::new_readonly(get_card_template_pda(card_type_id, &crate::ID).0, false)),
+    );
+    ix
+}
+
+// ============================================================================
+// Admin Instructions
+// ============================================================================
+
+pub fn initialize(
+    authority: &Pubkey,
+    normal_pack_price: u64,
+    sol_to_bug_rate: u64,
+    ticket_price: u64,
+) -> Instruction {
+    build(
+        accounts::Initialize {
+            game_config: game_config(),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::Initialize { normal_pack_price, sol_to_bug_rate, ticket_price },
+    )
+}
+
+pub fn add_card_creator(authority: &Pubkey, new_creator: &Pubkey) -> Instruction {
+    build(
+        accounts::AddCardCreator { game_config: game_config(), authority: *authority },
+        instruction::AddCardCreator { new_creator: *new_creator },
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_economy_config(
+    authority: &Pubkey,
+    base_trophy_gain: u32,
+    trophy_loss: u32,
+    max_streak_bonus: u32,
+    win_reward: u64,
+    loss_reward: u64,
+    daily_reward_cap: u64,
+    draw_trophy_delta: u32,
+) -> Instruction {
+    build(
+        accounts::UpdateEconomyConfig {
+            economy_config: get_economy_config_pda(&crate::ID).0,
+            game_config: game_config(),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::UpdateEconomyConfig {
+            base_trophy_gain,
+            trophy_loss,
+            max_streak_bonus,
+            win_reward,
+            loss_reward,
+            daily_reward_cap,
+            draw_trophy_delta,
+        },
+    )
+}
+
+pub fn update_tier_config(authority: &Pubkey, tiers: Vec<TierThreshold>) -> Instruction {
+    build(
+        accounts::UpdateTierConfig {
+            tier_config: get_tier_config_pda(&crate::ID).0,
+            game_config: game_config(),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::UpdateTierConfig { tiers },
+    )
+}
+
+pub fn update_progression_config(
+    authority: &Pubkey,
+    xp_per_win: u32,
+    xp_per_loss: u32,
+    level_thresholds: Vec<u32>,
+    attack_per_level: u16,
+    health_per_level: u16,
+    max_bonus_bps: u16,
+) -> Instruction {
+    build(
+        accounts::UpdateProgressionConfig {
+            progression_config: get_progression_config_pda(&crate::ID).0,
+            game_config: game_config(),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::UpdateProgressionConfig {
+            xp_per_win,
+            xp_per_loss,
+            level_thresholds,
+            attack_per_level,
+            health_per_level,
+            max_bonus_bps,
+        },
+    )
+}
+
+pub fn update_synergy_config(authority: &Pubkey, tiers: Vec<SynergyTier>) -> Instruction {
+    build(
+        accounts::UpdateSynergyConfig {
+            synergy_config: get_synergy_config_pda(&crate::ID).0,
+            game_config: game_config(),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::UpdateSynergyConfig { tiers },
+    )
+}
+
+pub fn update_reroll_config(authority: &Pubkey, currency: EntryCurrency, price: u64, keep_better: bool) -> Instruction {
+    build(
+        accounts::UpdateRerollConfig {
+            reroll_config: get_reroll_config_pda(&crate::ID).0,
+            game_config: game_config(),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::UpdateRerollConfig { currency, price, keep_better },
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn create_card_template(
+    creator: &Pubkey,
+    card_type_id: u32,
+    name: String,
+    trait_type: TraitType,
+    rarity: Rarity,
+    min_attack: u16,
+    max_attack: u16,
+    min_health: u16,
+    max_health: u16,
+    description: String,
+    image_uri: String,
+    set_id: u16,
+    ability: Ability,
+) -> Instruction {
+    build(
+        accounts::CreateCardTemplate {
+            card_template: get_card_template_pda(card_type_id, &crate::ID).0,
+            game_config: game_config(),
+            card_set: card_set(set_id),
+            creator: *creator,
+            system_program: system_program::ID,
+        },
+        instruction::CreateCardTemplate {
+            card_type_id,
+            name,
+            trait_type,
+            rarity,
+            min_attack,
+            max_attack,
+            min_health,
+            max_health,
+            description,
+            image_uri,
+            set_id,
+            ability,
+        },
+    )
+}
+
+/// Full replacement of the template's editable values (see CardTemplateValues)
+pub fn update_card_template(editor: &Pubkey, card_type_id: u32, values: CardTemplateValues) -> Instruction {
+    build(
+        accounts::UpdateCardTemplate {
+            card_template: get_card_template_pda(card_type_id, &crate::ID).0,
+            game_config: game_config(),
+            editor: *editor,
+        },
+        instruction::UpdateCardTemplate {
+            _card_type_id: card_type_id,
+            min_attack: values.min_attack,
+            max_attack: values.max_attack,
+            min_health: values.min_health,
+            max_health: values.max_health,
+            description: values.description,
+            image_uri: values.image_uri,
+            ability: values.ability,
+        },
+    )
+}
+
+/// `card_type_ids` are the cards a trait-filtered pack may contain
+/// (their templates go in remaining_accounts), empty for unrestricted packs
+#[allow(clippy::too_many_arguments)]
+pub fn update_pack_type(
+    authority: &Pubkey,
+    pack_type: u8,
+    name: String,
+    price: u64,
+    card_count: u8,
+    guaranteed_rarity: Rarity,
+    trait_filter: Option<TraitType>,
+    is_active: bool,
+    set_id: u16,
+    card_type_ids: &[u32],
+) -> Instruction {
+    let ix = build(
+        accounts::UpdatePackType {
+            pack: get_pack_type_pda(pack_type, &crate::ID).0,
+            game_config: game_config(),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::UpdatePackType {
+            pack_type,
+            name,
+            price,
+            card_count,
+            guaranteed_rarity,
+            trait_filter,
+            is_active,
+            set_id,
+        },
+    );
+    with_templates(ix, card_type_ids.iter().copied())
+}
+
+pub fn update_card_set(authority: &Pubkey, set_id: u16, name: String, release_time: i64, is_active: bool) -> Instruction {
+    build(
+        accounts::UpdateCardSet {
+            card_set: get_card_set_pda(set_id, &crate::ID).0,
+            game_config: game_config(),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::UpdateCardSet { set_id, name, release_time, is_active },
+    )
+}
+
+/// Passes the template of every entry in remaining_accounts, as the program requires
+pub fn update_rarity_pool(authority: &Pubkey, rarity: Rarity, entries: Vec<PoolEntry>) -> Instruction {
+    let ix = build(
+        accounts::UpdateRarityPool {
+            rarity_pool: rarity_pool(rarity),
+            game_config: game_config(),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::UpdateRarityPool { rarity_discriminant: rarity.to_discriminant(), entries: entries.clone() },
+    );
+    with_templates(ix, entries.iter().map(|entry| entry.card_type_id))
+}
+
+/// Passes the template of every entry in remaining_accounts, as the program requires
+pub fn update_set_pool(authority: &Pubkey, set_id: u16, rarity: Rarity, entries: Vec<PoolEntry>) -> Instruction {
+    let ix = build(
+        accounts::UpdateSetPool {
+            set_pool: get_set_pool_pda(set_id, rarity, &crate::ID).0,
+            card_set: get_card_set_pda(set_id, &crate::ID).0,
+            game_config: game_config(),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::UpdateSetPool {
+            set_id,
+            rarity_discriminant: rarity.to_discriminant(),
+            entries: entries.clone(),
+        },
+    );
+    with_templates(ix, entries.iter().map(|entry| entry.card_type_id))
+}
+
+/// `set_id` picks the pool: 0 for the standard pool, otherwise the card set's pool
+pub fn remove_from_rarity_pool(authority: &Pubkey, set_id: u16, rarity: Rarity, card_type_ids: Vec<u32>) -> Instruction {
+    build(
+        accounts::RemoveFromRarityPool {
+            rarity_pool: pool_of_set(set_id, rarity),
+            game_config: game_config(),
+            authority: *authority,
+        },
+        instruction::RemoveFromRarityPool { _rarity_discriminant: rarity.to_discriminant(), card_type_ids },
+    )
+}
+
+/// `rarity` is the template's rarity, whose pool it is removed from when retiring;
+/// `set_id` is the template's card set when that set has a pool of this rarity
+pub fn set_card_template_retired(
+    authority: &Pubkey,
+    card_type_id: u32,
+    rarity: Rarity,
+    set_id: Option<u16>,
+    retired: bool,
+) -> Instruction {
+    build(
+        accounts::SetCardTemplateRetired {
+            card_template: get_card_template_pda(card_type_id, &crate::ID).0,
+            rarity_pool: rarity_pool(rarity),
+            set_pool: set_id.map(|set_id| get_set_pool_pda(set_id, rarity, &crate::ID).0),
+            game_config: game_config(),
+            authority: *authority,
+        },
+        instruction::SetCardTemplateRetired { _card_type_id: card_type_id, retired },
+    )
+}
+
+pub fn add_gacha_tickets(authority: &Pubkey, wallet: &Pubkey, amount: u64) -> Instruction {
+    build(
+        accounts::AddGachaTickets {
+            player_profile: player_profile(wallet),
+            game_config: game_config(),
+            authority: *authority,
+        },
+        instruction::AddGachaTickets { amount },
+    )
+}
+
+/// The optional accounts the match instructions read, by whether they exist on
+/// chain; missing configs fall back to the program's defaults (see fetch_match_configs)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MatchConfigs {
+    pub economy_config: bool,
+    pub tier_config: bool,
+    pub leaderboard: bool,
+}
+
+impl MatchConfigs {
+    /// Everything created, as after a full zoo-admin setup
+    pub const ALL: MatchConfigs = MatchConfigs { economy_config: true, tier_config: true, leaderboard: true };
+
+    fn economy_config(&self) -> Option<Pubkey> {
+        self.economy_config.then(|| get_economy_config_pda(&crate::ID).0)
+    }
+
+    fn tier_config(&self) -> Option<Pubkey> {
+        self.tier_config.then(|| get_tier_config_pda(&crate::ID).0)
+    }
+
+    fn leaderboard(&self) -> Option<Pubkey> {
+        self.leaderboard.then(|| get_leaderboard_pda(&crate::ID).0)
+    }
+}
+
+pub fn record_match_result(authority: &Pubkey, winner: &Pubkey, loser: &Pubkey, configs: MatchConfigs) -> Instruction {
+    build(
+        accounts::RecordMatchResult {
+            winner_profile: player_profile(winner),
+            loser_profile: player_profile(loser),
+            game_config: game_config(),
+            authority: *authority,
+            economy_config: configs.economy_config(),
+            leaderboard: configs.leaderboard(),
+            tier_config: configs.tier_config(),
+            progression_config: None,
+        },
+        instruction::RecordMatchResult {},
+    )
+}
+
+/// A deck played in a match, with the cards that gain XP as (card mint, card_type_id)
+pub struct MatchDeck {
+    pub owner: Pubkey,
+    pub deck_index: u8,
+    pub cards: Vec<(Pubkey, u32)>,
+}
+
+/// record_match_result with card XP for the decks played (needs the ProgressionConfig)
+/// Two full decks take 42 extra accounts, so send it with an address lookup table.
+pub fn record_match_result_with_decks(
+    authority: &Pubkey,
+    winner: &Pubkey,
+    loser: &Pubkey,
+    configs: MatchConfigs,
+    decks: &[MatchDeck],
+) -> Instruction {
+    let mut ix = build(
+        accounts::RecordMatchResult {
+            winner_profile: player_profile(winner),
+            loser_profile: player_profile(loser),
+            game_config: game_config(),
+            authority: *authority,
+            economy_config: configs.economy_config(),
+            leaderboard: configs.leaderboard(),
+            tier_config: configs.tier_config(),
+            progression_config: Some(get_progression_config_pda(&crate::ID).0),
+        },
+        instruction::RecordMatchResult {},
+    );
+    for deck in decks {
+        let deck_address = get_player_deck_pda(&deck.owner, deck.deck_index, &crate::ID).0;
+        ix.accounts.push(AccountMeta::new_readonly(deck_address, false));
+        for (card_mint, card_type_id) in &deck.cards {
+            ix.accounts.push(AccountMeta::new(get_card_instance_pda(card_mint, &crate::ID).0, false));
+            ix.accounts.push(AccountMeta::new_readonly(get_card_template_pda(*card_type_id, &crate::ID).0, false));
+        }
+    }
+    ix
+}
+
+pub fn record_match_draw(authority: &Pubkey, player_a: &Pubkey, player_b: &Pubkey, configs: MatchConfigs) -> Instruction {
+    build(
+        accounts::RecordMatchDraw {
+            player_a_profile: player_profile(player_a),
+            player_b_profile: player_profile(player_b),
+            game_config: game_config(),
+            authority: *authority,
+            economy_config: configs.economy_config(),
+            leaderboard: configs.leaderboard(),
+            tier_config: configs.tier_config(),
+        },
+        instruction::RecordMatchDraw {},
+    )
+}
+
+pub fn initialize_leaderboard(authority: &Pubkey) -> Instruction {
+    build(
+        accounts::InitializeLeaderboard {
+            leaderboard: get_leaderboard_pda(&crate::ID).0,
+            game_config: game_config(),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeLeaderboard {},
+    )
+}
+
+/// Permissionless; refreshes the leaderboard entries of `wallets`
+pub fn rerank_leaderboard(wallets: &[Pubkey]) -> Instruction {
+    let ix = build(
+        accounts::RerankLeaderboard {
+            leaderboard: get_leaderboard_pda(&crate::ID).0,
+            game_config: game_config(),
+        },
+        instruction::RerankLeaderboard {},
+    );
+    with_profiles(ix, wallets, false)
+}
+
+pub fn create_tournament(
+    authority: &Pubkey,
+    tournament_id: u64,
+    name: String,
+    entry_currency: EntryCurrency,
+    entry_fee: u64,
+    max_entrants: u8,
+    payout_bps: Vec<u16>,
+) -> Instruction {
+    let tournament = get_tournament_pda(tournament_id, &crate::ID).0;
+    build(
+        accounts::CreateTournament {
+            tournament,
+            tournament_vault: get_tournament_vault_pda(&tournament, &crate::ID).0,
+            game_config: game_config(),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::CreateTournament {
+            tournament_id,
+            name,
+            entry_currency,
+            entry_fee,
+            max_entrants,
+            payout_bps,
+        },
+    )
+}
+
+pub fn start_tournament(authority: &Pubkey, tournament_id: u64) -> Instruction {
+    build(
+        accounts::StartTournament {
+            tournament: get_tournament_pda(tournament_id, &crate::ID).0,
+            game_config: game_config(),
+            authority: *authority,
+        },
+        instruction::StartTournament {},
+    )
+}
+
+/// `reporter` is the authority, or one of the players with the other as `co_signer`
+pub fn report_tournament_match(
+    reporter: &Pubkey,
+    co_signer: Option<&Pubkey>,
+    tournament_id: u64,
+    match_index: u8,
+    winner: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::ReportTournamentMatch {
+            tournament: get_tournament_pda(tournament_id, &crate::ID).0,
+            game_config: game_config(),
+            reporter: *reporter,
+            co_signer: co_signer.copied(),
+        },
+        instruction::ReportTournamentMatch { match_index, winner: *winner },
+    )
+}
+
+/// Permissionless; `placements` are the paid wallets in order (1st, 2nd, ...)
+pub fn distribute_tournament_prizes(tournament_id: u64, placements: &[Pubkey]) -> Instruction {
+    let tournament = get_tournament_pda(tournament_id, &crate::ID).0;
+    let ix = build(
+        accounts::DistributeTournamentPrizes {
+            tournament,
+            tournament_vault: get_tournament_vault_pda(&tournament, &crate::ID).0,
+        },
+        instruction::DistributeTournamentPrizes {},
+    );
+    with_profiles(ix, placements, true)
+}
+
+/// `entrants` are the tournament's entrants in registration order, all refunded
+pub fn cancel_tournament(authority: &Pubkey, tournament_id: u64, entrants: &[Pubkey]) -> Instruction {
+    let tournament = get_tournament_pda(tournament_id, &crate::ID).0;
+    let ix = build(
+        accounts::CancelTournament {
+            tournament,
+            tournament_vault: get_tournament_vault_pda(&tournament, &crate::ID).0,
+            game_config: game_config(),
+            authority: *authority,
+        },
+        instruction::CancelTournament {},
+    );
+    with_profiles(ix, entrants, true)
+}
+
+/// `previous_season_id` is None while still in the pre-season
+pub fn roll_over_season(
+    authority: &Pubkey,
+    season_id: u32,
+    previous_season_id: Option<u32>,
+    end_time: i64,
+    trophy_floor: u32,
+    reward_tiers: Vec<SeasonRewardTier>,
+) -> Instruction {
+    build(
+        accounts::RollOverSeason {
+            season: get_season_pda(season_id, &crate::ID).0,
+            previous_season: previous_season_id.map(|id| get_season_pda(id, &crate::ID).0),
+            game_config: game_config(),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::RollOverSeason { season_id, end_time, trophy_floor, reward_tiers },
+    )
+}
+
+/// Passes the template of every entry in remaining_accounts, as the program requires
+#[allow(clippy::too_many_arguments)]
+pub fn create_banner(
+    authority: &Pubkey,
+    banner_id: u32,
+    name: String,
+    start_time: i64,
+    end_time: i64,
+    entries: Vec<PoolEntry>,
+    currency: EntryCurrency,
+    price: u64,
+    pull_limit: u32,
+    set_id: u16,
+) -> Instruction {
+    let ix = build(
+        accounts::CreateBanner {
+            banner: get_banner_pda(banner_id, &crate::ID).0,
+            game_config: game_config(),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::CreateBanner {
+            banner_id,
+            name,
+            start_time,
+            end_time,
+            entries: entries.clone(),
+            currency,
+            price,
+            pull_limit,
+            set_id,
+        },
+    );
+    with_templates(ix, entries.iter().map(|entry| entry.card_type_id))
+}
+
+pub fn end_banner(authority: &Pubkey, banner_id: u32) -> Instruction {
+    build(
+        accounts::EndBanner {
+            banner: get_banner_pda(banner_id, &crate::ID).0,
+            game_config: game_config(),
+            authority: *authority,
+        },
+        instruction::EndBanner {},
+    )
+}
+
+// ============================================================================
+// Player Instructions
+// ============================================================================
+
+pub fn register_player(player: &Pubkey, username: String) -> Instruction {
+    build(
+        accounts::RegisterPlayer {
+            player_profile: player_profile(player),
+            game_config: game_config(),
+            player: *player,
+            system_program: system_program::ID,
+        },
+        instruction::RegisterPlayer { username },
+    )
+}
+
+pub fn claim_starter_tickets(player: &Pubkey) -> Instruction {
+    build(
+        accounts::ClaimStarterTickets { player_profile: player_profile(player), player: *player },
+        instruction::ClaimStarterTickets {},
+    )
+}
+
+/// Pays `count` tickets; reveal the cards with `reveal_gacha_pull` from the next slot
+pub fn gacha_draw(player: &Pubkey, count: u8) -> Instruction {
+    build(
+        accounts::GachaDraw {
+            player_profile: player_profile(player),
+            pending_pull: get_pending_pull_pda(player, &crate::ID).0,
+            player: *player,
+            system_program: system_program::ID,
+        },
+        instruction::GachaDraw { count },
+    )
+}
+
+/// Simulate this to learn the card ids of the player's pending gacha draw
+/// (any signer may send it; the player's `claim_pulled_cards` can follow in the same transaction)
+pub fn reveal_gacha_pull(player: &Pubkey) -> Instruction {
+    build(
+        accounts::RevealGachaPull {
+            pending_pull: get_pending_pull_pda(player, &crate::ID).0,
+            rarity_pool_common: rarity_pool(Rarity::Common),
+            rarity_pool_rare: rarity_pool(Rarity::Rare),
+            rarity_pool_legendary: rarity_pool(Rarity::Legendary),
+            slot_hashes: sysvar::slot_hashes::ID,
+        },
+        instruction::RevealGachaPull {},
+    )
+}
+
+/// `cards` are the next revealed (card_type_id, fresh mint) pairs of the pending pull, in
+/// order; every mint must already exist with the game config PDA as mint authority.
+/// Each card adds four accounts, so pulls of more than a few cards take several claims.
+/// `collection_page` is the player's collection page to index the cards in (None to skip)
+pub fn claim_pulled_cards(player: &Pubkey, cards: &[(u32, Pubkey)], collection_page: Option<u8>) -> Instruction {
+    let ix = build(
+        accounts::ClaimPulledCards {
+            pending_pull: get_pending_pull_pda(player, &crate::ID).0,
+            game_config: game_config(),
+            player: *player,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            collection: collection(player, collection_page),
+        },
+        instruction::ClaimPulledCards {},
+    );
+    with_cards(ix, player, cards)
+}
+
+pub fn buy_bug_tokens(player: &Pubkey, treasury: &Pubkey, sol_amount: u64) -> Instruction {
+    build(
+        accounts::BuyBugTokens {
+            game_config: game_config(),
+            player_profile: player_profile(player),
+            treasury: *treasury,
+            player: *player,
+            system_program: system_program::ID,
+        },
+        instruction::BuyBugTokens { sol_amount },
+    )
+}
+
+pub fn buy_gacha_tickets(player: &Pubkey, ticket_count: u64) -> Instruction {
+    build(
+        accounts::BuyGachaTickets {
+            game_config: game_config(),
+            player_profile: player_profile(player),
+            player: *player,
+        },
+        instruction::BuyGachaTickets { ticket_count },
+    )
+}
+
+/// Pays for one pull; reveal the card with `reveal_banner_pull` from the next slot
+pub fn banner_draw(player: &Pubkey, banner_id: u32) -> Instruction {
+    let banner = get_banner_pda(banner_id, &crate::ID).0;
+    build(
+        accounts::BannerDraw {
+            banner,
+            banner_pulls: get_banner_pulls_pda(&banner, player, &crate::ID).0,
+            player_profile: player_profile(player),
+            pending_pull: get_pending_pull_pda(player, &crate::ID).0,
+            player: *player,
+            system_program: system_program::ID,
+        },
+        instruction::BannerDraw {},
+    )
+}
+
+/// Simulate this to learn the card of the player's banner pull (see `reveal_gacha_pull`)
+/// `card_type_ids` are the banner's entries, in order (see `fetch_banner`)
+pub fn reveal_banner_pull(player: &Pubkey, banner_id: u32, card_type_ids: &[u32]) -> Instruction {
+    let mut ix = build(
+        accounts::RevealBannerPull {
+            pending_pull: get_pending_pull_pda(player, &crate::ID).0,
+            banner: get_banner_pda(banner_id, &crate::ID).0,
+            slot_hashes: sysvar::slot_hashes::ID,
+        },
+        instruction::RevealBannerPull {},
+    );
+    ix.accounts.extend(card_type_ids.iter().map(|card_type_id| {
+        AccountMeta::new_readonly(get_card_template_pda(*card_type_id, &crate::ID).0, false)
+    }));
+    ix
+}
+
+/// `set_id` is the pack type's card set (0 for standard packs); reveal the cards with
+/// `reveal_pack` from the next slot, then mint them with `claim_pulled_cards`
+pub fn purchase_pack(player: &Pubkey, pack_type: u8, set_id: u16) -> Instruction {
+    build(
+        accounts::PurchasePack {
+            player_profile: player_profile(player),
+            pack: get_pack_type_pda(pack_type, &crate::ID).0,
+            card_set: card_set(set_id),
+            pending_pull: get_pending_pull_pda(player, &crate::ID).0,
+            player: *player,
+            system_program: system_program::ID,
+        },
+        instruction::PurchasePack { pack_type },
+    )
+}
+
+/// Simulate this to learn the card ids of the player's bought pack (see `reveal_gacha_pull`)
+pub fn reveal_pack(player: &Pubkey, pack_type: u8, set_id: u16) -> Instruction {
+    build(
+        accounts::RevealPack {
+            pending_pull: get_pending_pull_pda(player, &crate::ID).0,
+            pack: get_pack_type_pda(pack_type, &crate::ID).0,
+            rarity_pool_common: pool_of_set(set_id, Rarity::Common),
+            rarity_pool_rare: pool_of_set(set_id, Rarity::Rare),
+            rarity_pool_legendary: pool_of_set(set_id, Rarity::Legendary),
+            slot_hashes: sysvar::slot_hashes::ID,
+        },
+        instruction::RevealPack {},
+    )
+}
+
+pub fn burn_card(player: &Pubkey, card_type_id: u32, card_mint: &Pubkey, collection_page: Option<u8>) -> Instruction {
+    build(
+        accounts::BurnCard {
+            player_profile: player_profile(player),
+            card_mint: *card_mint,
+            player_card_token_account: get_associated_token_address(player, card_mint),
+            card_instance: get_card_instance_pda(card_mint, &crate::ID).0,
+            card_template: get_card_template_pda(card_type_id, &crate::ID).0,
+            player: *player,
+            token_program: token::ID,
+            collection: collection(player, collection_page),
+        },
+        instruction::BurnCard {},
+    )
+}
+
+/// `set_id` is the template's card set (0 for base game cards); reveal the stats with
+/// `reveal_crafted_card` from the next slot, then mint the card with `claim_pulled_cards`
+pub fn craft_card(player: &Pubkey, card_type_id: u32, set_id: u16) -> Instruction {
+    build(
+        accounts::CraftCard {
+            player_profile: player_profile(player),
+            card_template: get_card_template_pda(card_type_id, &crate::ID).0,
+            card_set: card_set(set_id),
+            pending_pull: get_pending_pull_pda(player, &crate::ID).0,
+            player: *player,
+            system_program: system_program::ID,
+        },
+        instruction::CraftCard { card_type_id },
+    )
+}
+
+/// Any signer may send this; the player's `claim_pulled_cards` can follow in the same transaction
+pub fn reveal_crafted_card(player: &Pubkey) -> Instruction {
+    build(
+        accounts::RevealCraftedCard {
+            pending_pull: get_pending_pull_pda(player, &crate::ID).0,
+            slot_hashes: sysvar::slot_hashes::ID,
+        },
+        instruction::RevealCraftedCard {},
+    )
+}
+
+/// Reveal the stats with `reveal_card_reroll` from the next slot
+pub fn reroll_card_stats(player: &Pubkey, card_mint: &Pubkey) -> Instruction {
+    build(
+        accounts::RerollCardStats {
+            player_profile: player_profile(player),
+            reroll_config: get_reroll_config_pda(&crate::ID).0,
+            card_mint: *card_mint,
+            player_card_token_account: get_associated_token_address(player, card_mint),
+            card_instance: get_card_instance_pda(card_mint, &crate::ID).0,
+            pending_pull: get_pending_pull_pda(player, &crate::ID).0,
+            player: *player,
+            system_program: system_program::ID,
+        },
+        instruction::RerollCardStats {},
+    )
+}
+
+/// Any signer may send this. `levelled`: the card has gained a level, so its level-ups
+/// are applied to the new roll (passes the ProgressionConfig)
+pub fn reveal_card_reroll(player: &Pubkey, card_type_id: u32, card_mint: &Pubkey, levelled: bool) -> Instruction {
+    build(
+        accounts::RevealCardReroll {
+            pending_pull: get_pending_pull_pda(player, &crate::ID).0,
+            player: *player,
+            card_instance: get_card_instance_pda(card_mint, &crate::ID).0,
+            card_template: Some(get_card_template_pda(card_type_id, &crate::ID).0),
+            progression_config: levelled.then(|| get_progression_config_pda(&crate::ID).0),
+            slot_hashes: sysvar::slot_hashes::ID,
+        },
+        instruction::RevealCardReroll {},
+    )
+}
+
+pub fn save_deck(player: &Pubkey, deck_index: u8, deck_name: String, card_mints: Vec<Pubkey>) -> Instruction {
+    build(
+        accounts::SaveDeck {
+            player_deck: get_player_deck_pda(player, deck_index, &crate::ID).0,
+            player: *player,
+            system_program: system_program::ID,
+            synergy_config: None,
+        },
+        instruction::SaveDeck { deck_index, deck_name, card_mints },
+    )
+}
+
+/// save_deck with the cards' accounts, as (card mint, card_type_id) in deck order,
+/// so the program checks ownership and reports trait synergies (needs the SynergyConfig).
+/// The mints are taken from the card instances, which keeps a full deck in one transaction.
+pub fn save_deck_with_cards(player: &Pubkey, deck_index: u8, deck_name: String, cards: &[(Pubkey, u32)]) -> Instruction {
+    let mut ix = build(
+        accounts::SaveDeck {
+            player_deck: get_player_deck_pda(player, deck_index, &crate::ID).0,
+            player: *player,
+            system_program: system_program::ID,
+            synergy_config: Some(get_synergy_config_pda(&crate::ID).0),
+        },
+        instruction::SaveDeck { deck_index, deck_name, card_mints: Vec::new() },
+    );
+    for (card_mint, card_type_id) in cards {
+        ix.accounts.push(AccountMeta::new_readonly(get_card_instance_pda(card_mint, &crate::ID).0, false));
+        ix.accounts.push(AccountMeta::new_readonly(get_card_template_pda(*card_type_id, &crate::ID).0, false));
+    }
+    ix
+}
+
+pub fn delete_deck(player: &Pubkey, deck_index: u8) -> Instruction {
+    build(
+        accounts::DeleteDeck {
+            player_deck: get_player_deck_pda(player, deck_index, &crate::ID).0,
+            player: *player,
+        },
+        instruction::DeleteDeck { _deck_index: deck_index },
+    )
+}
+
+/// `page` must be the player's current PlayerProfile::collection_pages
+pub fn create_collection_page(player: &Pubkey, page: u8) -> Instruction {
+    build(
+        accounts::CreateCollectionPage {
+            collection: get_collection_pda(player, page, &crate::ID).0,
+            player_profile: player_profile(player),
+            player: *player,
+            system_program: system_program::ID,
+        },
+        instruction::CreateCollectionPage {},
+    )
+}
+
+/// Add the listed cards the player owns to a collection page and drop those they don't;
+/// `collection_pages` is the profile's page count (every other page is passed as well)
+pub fn sync_collection(player: &Pubkey, page: u8, collection_pages: u8, card_mints: Vec<Pubkey>) -> Instruction {
+    let mut ix = build(
+        accounts::SyncCollection {
+            collection: get_collection_pda(player, page, &crate::ID).0,
+            player_profile: player_profile(player),
+            player: *player,
+        },
+        instruction::SyncCollection { card_mints: card_mints.clone() },
+    );
+    ix.accounts.extend(
+        card_mints
+            .iter()
+            .map(|card_mint| AccountMeta::new_readonly(get_card_instance_pda(card_mint, &crate::ID).0, false)),
+    );
+    ix.accounts.extend(
+        (0..collection_pages)
+            .filter(|other| *other != page)
+            .map(|other| AccountMeta::new_readonly(get_collection_pda(player, other, &crate::ID).0, false)),
+    );
+    ix
+}
+
+/// Drop cards `owner` no longer has from one of their collection pages; anyone may send it
+pub fn prune_collection(owner: &Pubkey, page: u8, card_mints: Vec<Pubkey>) -> Instruction {
+    let mut ix = build(
+        accounts::PruneCollection {
+            collection: get_collection_pda(owner, page, &crate::ID).0,
+        },
+        instruction::PruneCollection { card_mints: card_mints.clone() },
+    );
+    ix.accounts.extend(
+        card_mints
+            .iter()
+            .map(|card_mint| AccountMeta::new_readonly(get_card_instance_pda(card_mint, &crate::ID).0, false)),
+    );
+    ix
+}
+
+pub fn join_tournament(player: &Pubkey, tournament_id: u64) -> Instruction {
+    let tournament = get_tournament_pda(tournament_id, &crate::ID).0;
+    build(
+        accounts::JoinTournament {
+            tournament,
+            tournament_vault: get_tournament_vault_pda(&tournament, &crate::ID).0,
+            player_profile: player_profile(player),
+            player: *player,
+        },
+        instruction::JoinTournament {},
+    )
+}
+
+pub fn claim_season_rewards(player: &Pubkey, season_id: u32) -> Instruction {
+    build(
+        accounts::ClaimSeasonRewards {
+            player_profile: player_profile(player),
+            season: get_season_pda(season_id, &crate::ID).0,
+            season_record: get_player_season_pda(player, season_id, &crate::ID).0,
+            game_config: game_config(),
+            player: *player,
+            system_program: system_program::ID,
+        },
+        instruction::ClaimSeasonRewards { season_id },
+    )
+}
+
+// ============================================================================
+// Marketplace Instructions
+// ============================================================================
+
+/// Lists from the seller's associated token account
+pub fn list_card(seller: &Pubkey, card_mint: &Pubkey, price: u64) -> Instruction {
+    build(
+        accounts::ListCard {
+            listing: get_listing_pda(card_mint, &crate::ID).0,
+            card_mint: *card_mint,
+            seller_token_account: get_associated_token_address(seller, card_mint),
+            escrow_token_account: get_escrow_pda(card_mint, &crate::ID).0,
+            seller: *seller,
+            token_program: token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::ListCard { price },
+    )
+}
+
+pub fn cancel_listing(seller: &Pubkey, card_mint: &Pubkey) -> Instruction {
+    build(
+        accounts::CancelListing {
+            listing: get_listing_pda(card_mint, &crate::ID).0,
+            card_mint: *card_mint,
+            seller_token_account: get_associated_token_address(seller, card_mint),
+            escrow_token_account: get_escrow_pda(card_mint, &crate::ID).0,
+            seller: *seller,
+            token_program: token::ID,
+        },
+        instruction::CancelListing {},
+    )
+}
+
+/// `seller` must be the listing's seller (see `fetch_listing`)
+/// `buyer_page` / `seller_page`: the collection pages to move the card between (None to skip)
+pub fn buy_card(
+    buyer: &Pubkey,
+    seller: &Pubkey,
+    card_mint: &Pubkey,
+    buyer_page: Option<u8>,
+    seller_page: Option<u8>,
+) -> Instruction {
+    build(
+        accounts::BuyCard {
+            listing: get_listing_pda(card_mint, &crate::ID).0,
+            seller: *seller,
+            buyer_profile: player_profile(buyer),
+            seller_profile: player_profile(seller),
+            card_mint: *card_mint,
+            card_instance: get_card_instance_pda(card_mint, &crate::ID).0,
+            escrow_token_account: get_escrow_pda(card_mint, &crate::ID).0,
+            buyer_token_account: get_associated_token_address(buyer, card_mint),
+            buyer: *buyer,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+            buyer_collection: collection(buyer, buyer_page),
+            seller_collection: collection(seller, seller_page),
+        },
+        instruction::BuyCard {},
+    )
+}
+
+// ============================================================================
+// Migrations
+// ============================================================================
+
+pub fn migrate_card_instance(payer: &Pubkey, card_mint: &Pubkey) -> Instruction {
+    build(
+        accounts::MigrateCardInstance {
+            card_instance: get_card_instance_pda(card_mint, &crate::ID).0,
+            payer: *payer,
+            system_program: system_program::ID,
+        },
+        instruction::MigrateCardInstance {},
+    )
+}
+
+pub fn migrate_rarity_pool(authority: &Pubkey, rarity: Rarity) -> Instruction {
+    build(
+        accounts::MigrateRarityPool {
+            rarity_pool: rarity_pool(rarity),
+            game_config: game_config(),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::MigrateRarityPool { _rarity_discriminant: rarity.to_discriminant() },
+    )
+}
+
+pub fn migrate_game_config(authority: &Pubkey) -> Instruction {
+    build(
+        accounts::MigrateGameConfig {
+            game_config: game_config(),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::MigrateGameConfig {},
+    )
+}
+
+pub fn migrate_card_template(payer: &Pubkey, card_type_id: u32) -> Instruction {
+    build(
+        accounts::MigrateCardTemplate {
+            card_template: get_card_template_pda(card_type_id, &crate::ID).0,
+            game_config: game_config(),
+            payer: *payer,
+            system_program: system_program::ID,
+        },
+        instruction::MigrateCardTemplate {},
+    )
+}
+
+pub fn migrate_player_profile(payer: &Pubkey, wallet: &Pubkey) -> Instruction {
+    build(
+        accounts::MigratePlayerProfile {
+            player_profile: player_profile(wallet),
+            game_config: game_config(),
+            payer: *payer,
+            system_program: system_program::ID,
+        },
+        instruction::MigratePlayerProfile {},
+    )
+}
+
+// ============================================================================
+// Account Fetching
+// ============================================================================
+
+#[derive(Debug)]
+pub enum ClientError {
+    Rpc(Box<solana_client::client_error::ClientError>),
+    Deserialize(anchor_lang::error::Error),
+}
+
+impl std::fmt::Display for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientError::Rpc(err) => write!(f, "RPC error: {}", err),
+            ClientError::Deserialize(err) => write!(f, "Failed to deserialize account: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+/// Deserialise raw account data, checking the Anchor discriminator
+pub fn deserialize_account<T: AccountDeserialize>(data: &[u8]) -> std::result::Result<T, ClientError> {
+    let mut data = data;
+    T::try_deserialize(&mut data).map_err(ClientError::Deserialize)
+}
+
+/// Fetch and deserialise any program account
+pub fn fetch_account<T: AccountDeserialize>(
+    rpc: &RpcClient,
+    address: &Pubkey,
+) -> std::result::Result<T, ClientError> {
+    let data = rpc.get_account_data(address).map_err(|err| ClientError::Rpc(Box::new(err)))?;
+    deserialize_account(&data)
+}
+
+pub fn fetch_game_config(rpc: &RpcClient) -> std::result::Result<GameConfig, ClientError> {
+    fetch_account(rpc, &game_config())
+}
+
+/// Which of the optional match configs the authority has created
+pub fn fetch_match_configs(rpc: &RpcClient) -> std::result::Result<MatchConfigs, ClientError> {
+    let addresses = [
+        get_economy_config_pda(&crate::ID).0,
+        get_tier_config_pda(&crate::ID).0,
+        get_leaderboard_pda(&crate::ID).0,
+    ];
+    let accounts = rpc.get_multiple_accounts(&addresses).map_err(|err| ClientError::Rpc(Box::new(err)))?;
+    Ok(MatchConfigs {
+        economy_config: accounts[0].is_some(),
+        tier_config: accounts[1].is_some(),
+        leaderboard: accounts[2].is_some(),
+    })
+}
+
+pub fn fetch_economy_config(rpc: &RpcClient) -> std::result::Result<EconomyConfig, ClientError> {
+    fetch_account(rpc, &get_economy_config_pda(&crate::ID).0)
+}
+
+pub fn fetch_tier_config(rpc: &RpcClient) -> std::result::Result<TierConfig, ClientError> {
+    fetch_account(rpc, &get_tier_config_pda(&crate::ID).0)
+}
+
+pub fn fetch_progression_config(rpc: &RpcClient) -> std::result::Result<ProgressionConfig, ClientError> {
+    fetch_account(rpc, &get_progression_config_pda(&crate::ID).0)
+}
+
+pub fn fetch_synergy_config(rpc: &RpcClient) -> std::result::Result<SynergyConfig, ClientError> {
+    fetch_account(rpc, &get_synergy_config_pda(&crate::ID).0)
+}
+
+pub fn fetch_reroll_config(rpc: &RpcClient) -> std::result::Result<RerollConfig, ClientError> {
+    fetch_account(rpc, &get_reroll_config_pda(&crate::ID).0)
+}
+
+pub fn fetch_card_template(rpc: &RpcClient, card_type_id: u32) -> std::result::Result<CardTemplate, ClientError> {
+    fetch_account(rpc, &get_card_template_pda(card_type_id, &crate::ID).0)
+}
+
+pub fn fetch_rarity_pool(rpc: &RpcClient, rarity: Rarity) -> std::result::Result<RarityPool, ClientError> {
+    fetch_account(rpc, &rarity_pool(rarity))
+}
+
+pub fn fetch_card_set(rpc: &RpcClient, set_id: u16) -> std::result::Result<CardSet, ClientError> {
+    fetch_account(rpc, &get_card_set_pda(set_id, &crate::ID).0)
+}
+
+pub fn fetch_set_pool(rpc: &RpcClient, set_id: u16, rarity: Rarity) -> std::result::Result<RarityPool, ClientError> {
+    fetch_account(rpc, &get_set_pool_pda(set_id, rarity, &crate::ID).0)
+}
+
+pub fn fetch_pack_type(rpc: &RpcClient, pack_type: u8) -> std::result::Result<PackType, ClientError> {
+    fetch_account(rpc, &get_pack_type_pda(pack_type, &crate::ID).0)
+}
+
+pub fn fetch_player_profile(rpc: &RpcClient, wallet: &Pubkey) -> std::result::Result<PlayerProfile, ClientError> {
+    fetch_account(rpc, &player_profile(wallet))
+}
+
+pub fn fetch_card_instance(rpc: &RpcClient, card_mint: &Pubkey) -> std::result::Result<CardInstance, ClientError> {
+    fetch_account(rpc, &get_card_instance_pda(card_mint, &crate::ID).0)
+}
+
+pub fn fetch_player_deck(
+    rpc: &RpcClient,
+    owner: &Pubkey,
+    deck_index: u8,
+) -> std::result::Result<PlayerDeck, ClientError> {
+    fetch_account(rpc, &get_player_deck_pda(owner, deck_index, &crate::ID).0)
+}
+
+/// All the card mints of a player's collection, in page order: the profile
+/// gives the page count, then every page is loaded in one request
+pub fn fetch_collection(rpc: &RpcClient, owner: &Pubkey) -> std::result::Result<Vec<Pubkey>, ClientError> {
+    let profile = fetch_player_profile(rpc, owner)?;
+    let pages: Vec<Pubkey> = (0..profile.collection_pages)
+        .map(|page| get_collection_pda(owner, page, &crate::ID).0)
+        .collect();
+    let mut card_mints = Vec::new();
+    for chunk in pages.chunks(100) {
+        for account in rpc.get_multiple_accounts(chunk).map_err(|err| ClientError::Rpc(Box::new(err)))?.into_iter().flatten() {
+            let collection: Collection = deserialize_account(&account.data)?;
+            card_mints.extend(collection.card_mints);
+        }
+    }
+    Ok(card_mints)
+}
+
+pub fn fetch_listing(rpc: &RpcClient, card_mint: &Pubkey) -> std::result::Result<Listing, ClientError> {
+    fetch_account(rpc, &get_listing_pda(card_mint, &crate::ID).0)
+}
+
+pub fn fetch_leaderboard(rpc: &RpcClient) -> std::result::Result<Leaderboard, ClientError> {
+    fetch_account(rpc, &get_leaderboard_pda(&crate::ID).0)
+}
+
+pub fn fetch_tournament(rpc: &RpcClient, tournament_id: u64) -> std::result::Result<Tournament, ClientError> {
+    fetch_account(rpc, &get_tournament_pda(tournament_id, &crate::ID).0)
+}
+
+pub fn fetch_tournament_vault(
+    rpc: &RpcClient,
+    tournament_id: u64,
+) -> std::result::Result<TournamentVault, ClientError> {
+    let tournament = get_tournament_pda(tournament_id, &crate::ID).0;
+    fetch_account(rpc, &get_tournament_vault_pda(&tournament, &crate::ID).0)
+}
+
+pub fn fetch_season(rpc: &RpcClient, season_id: u32) -> std::result::Result<Season, ClientError> {
+    fetch_account(rpc, &get_season_pda(season_id, &crate::ID).0)
+}
+
+pub fn fetch_player_season_record(
+    rpc: &RpcClient,
+    wallet: &Pubkey,
+    season_id: u32,
+) -> std::result::Result<PlayerSeasonRecord, ClientError> {
+    fetch_account(rpc, &get_player_season_pda(wallet, season_id, &crate::ID).0)
+}
+
+pub fn fetch_banner(rpc: &RpcClient, banner_id: u32) -> std::result::Result<Banner, ClientError> {
+    fetch_account(rpc, &get_banner_pda(banner_id, &crate::ID).0)
+}
+
+pub fn fetch_banner_pulls(
+    rpc: &RpcClient,
+    banner_id: u32,
+    wallet: &Pubkey,
+) -> std::result::Result<BannerPulls, ClientError> {
+    let banner = get_banner_pda(banner_id, &crate::ID).0;
+    fetch_account(rpc, &get_banner_pulls_pda(&banner, wallet, &crate::ID).0)
+}
+
+/// The player's paid pull; its `card_type_ids` are the cards to claim once revealed
+pub fn fetch_pending_pull(rpc: &RpcClient, wallet: &Pubkey) -> std::result::Result<PendingPull, ClientError> {
+    fetch_account(rpc, &get_pending_pull_pda(wallet, &crate::ID).0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::{AccountSerialize, Discriminator};
+
+    #[test]
+    fn test_builders_use_the_seeds_the_program_checks() {
+        let player = Pubkey::new_unique();
+        let card_mint = Pubkey::new_unique();
+
+        let ix = buy_card(&player, &Pubkey::new_unique(), &card_mint, None, None);
+        assert_eq!(ix.program_id, crate::ID);
+        assert_eq!(ix.accounts[0].pubkey, get_listing_pda(&card_mint, &crate::ID).0);
+        assert_eq!(ix.accounts[2].pubkey, get_player_profile_pda(&player, &crate::ID).0);
+        assert_eq!(ix.accounts[5].pubkey, get_card_instance_pda(&card_mint, &crate::ID).0);
+        assert_eq!(ix.accounts[6].pubkey, get_escrow_pda(&card_mint, &crate::ID).0);
+        assert!(ix.accounts[8].is_signer && ix.accounts[8].pubkey == player);
+
+        let ix = save_deck(&player, 3, "Main".to_string(), vec![card_mint]);
+        assert_eq!(ix.accounts[0].pubkey, get_player_deck_pda(&player, 3, &crate::ID).0);
+        assert_eq!(&ix.data[..8], &instruction::SaveDeck::DISCRIMINATOR);
+
+        let entries = vec![
+            PoolEntry { card_type_id: 1, weight: PoolEntry::DEFAULT_WEIGHT },
+            PoolEntry { card_type_id: 2, weight: 300 },
+        ];
+        let ix = update_rarity_pool(&player, Rarity::Legendary, entries);
+        assert_eq!(
+            ix.accounts[0].pubkey,
+            Pubkey::find_program_address(&[b"rarity_pool", &[2]], &crate::ID).0
+        );
+        assert_eq!(ix.accounts.len(), 6);
+        assert_eq!(ix.accounts[5].pubkey, get_card_template_pda(2, &crate::ID).0);
+
+        let banner = get_banner_pda(5, &crate::ID).0;
+        let ix = banner_draw(&player, 5);
+        assert_eq!(ix.accounts[0].pubkey, banner);
+        assert_eq!(
+            ix.accounts[1].pubkey,
+            Pubkey::find_program_address(&[b"banner_pulls", banner.as_ref(), player.as_ref()], &crate::ID).0
+        );
+        assert_eq!(ix.accounts[3].pubkey, get_pending_pull_pda(&player, &crate::ID).0);
+        let ix = reveal_banner_pull(&player, 5, &[500, 503]);
+        assert_eq!(ix.accounts[1].pubkey, banner);
+        assert_eq!(ix.accounts[2].pubkey, sysvar::slot_hashes::ID);
+        assert!(!ix.accounts[4].is_writable && ix.accounts[4].pubkey == get_card_template_pda(503, &crate::ID).0);
+        assert_eq!(ix.accounts.len(), 5);
+
+        // Gacha draws are paid, revealed from the slot hash, then claimed
+        let pending_pull = Pubkey::find_program_address(&[b"pending_pull", player.as_ref()], &crate::ID).0;
+        let ix = gacha_draw(&player, 3);
+        assert!(ix.accounts[1].is_writable && ix.accounts[1].pubkey == pending_pull);
+        let ix = reveal_gacha_pull(&player);
+        assert_eq!(ix.accounts[0].pubkey, pending_pull);
+        assert_eq!(ix.accounts[4].pubkey, sysvar::slot_hashes::ID);
+        assert!(ix.accounts.iter().all(|account| !account.is_signer));
+        let ix = claim_pulled_cards(&player, &[(404, card_mint)], Some(1));
+        assert_eq!(ix.accounts[0].pubkey, pending_pull);
+        assert!(!ix.accounts[7].is_writable && ix.accounts[7].pubkey == get_card_template_pda(404, &crate::ID).0);
+        assert_eq!(ix.accounts[9].pubkey, get_associated_token_address(&player, &card_mint));
+        assert_eq!(ix.accounts[10].pubkey, get_card_instance_pda(&card_mint, &crate::ID).0);
+
+        // Crafted cards go through the same pending pull
+        let ix = craft_card(&player, 503, 7);
+        assert_eq!(ix.accounts[2].pubkey, get_card_set_pda(7, &crate::ID).0);
+        assert!(ix.accounts[3].is_writable && ix.accounts[3].pubkey == pending_pull);
+        let ix = reveal_crafted_card(&player);
+        assert_eq!(ix.accounts[0].pubkey, pending_pull);
+        assert_eq!(ix.accounts[1].pubkey, sysvar::slot_hashes::ID);
+
+        // So do rerolls, applied to the card on reveal
+        let ix = reroll_card_stats(&player, &card_mint);
+        assert!(ix.accounts[5].is_writable && ix.accounts[5].pubkey == pending_pull);
+        let ix = reveal_card_reroll(&player, 404, &card_mint, false);
+        assert!(ix.accounts[1].is_writable && !ix.accounts[1].is_signer && ix.accounts[1].pubkey == player);
+        assert!(ix.accounts[2].is_writable && ix.accounts[2].pubkey == get_card_instance_pda(&card_mint, &crate::ID).0);
+        assert_eq!(ix.accounts[4].pubkey, crate::ID);
+
+        // Trait-filtered packs are configured from the templates of their cards
+        let ix = update_pack_type(&player, 2, "Tanks".into(), 500, 5, Rarity::Rare, Some(TraitType::Tank), true, 0, &[404, 418]);
+        assert_eq!(ix.accounts[0].pubkey, Pubkey::find_program_address(&[b"pack_type", &[2]], &crate::ID).0);
+        assert_eq!(ix.accounts.len(), 6);
+        assert_eq!(ix.accounts[4].pubkey, get_card_template_pda(404, &crate::ID).0);
+        assert!(!ix.accounts[5].is_writable);
+
+        // Packs are bought, then revealed from the standard pools or the set's pools
+        let ix = purchase_pack(&player, 2, 0);
+        assert_eq!(ix.accounts[1].pubkey, Pubkey::find_program_address(&[b"pack_type", &[2]], &crate::ID).0);
+        assert_eq!(ix.accounts[2].pubkey, crate::ID);
+        assert_eq!(ix.accounts[3].pubkey, pending_pull);
+        let ix = purchase_pack(&player, 3, 7);
+        assert_eq!(ix.accounts[2].pubkey, get_card_set_pda(7, &crate::ID).0);
+        let ix = reveal_pack(&player, 2, 0);
+        assert_eq!(ix.accounts[2].pubkey, get_rarity_pool_pda(Rarity::Common, &crate::ID).0);
+        assert_eq!(ix.accounts[5].pubkey, sysvar::slot_hashes::ID);
+        let ix = reveal_pack(&player, 3, 7);
+        assert_eq!(
+            ix.accounts[4].pubkey,
+            Pubkey::find_program_address(&[b"set_pool", &7u16.to_le_bytes(), &[2]], &crate::ID).0
+        );
+
+        // Each deck is followed by (card instance, template) pairs, the instances writable
+        let deck = MatchDeck { owner: player, deck_index: 1, cards: vec![(card_mint, 404)] };
+        let ix = record_match_result_with_decks(&player, &player, &Pubkey::new_unique(), MatchConfigs::ALL, &[deck]);
+        assert_eq!(ix.accounts[7].pubkey, get_progression_config_pda(&crate::ID).0);
+        assert_eq!(ix.accounts[8].pubkey, get_player_deck_pda(&player, 1, &crate::ID).0);
+        assert!(ix.accounts[9].is_writable && ix.accounts[9].pubkey == get_card_instance_pda(&card_mint, &crate::ID).0);
+        assert!(!ix.accounts[10].is_writable);
+        assert_eq!(ix.accounts.len(), 11);
+
+        // Configs that don't exist yet are passed as the program id (program defaults apply)
+        let configs = MatchConfigs { economy_config: false, ..MatchConfigs::ALL };
+        let ix = record_match_draw(&player, &player, &Pubkey::new_unique(), configs);
+        assert_eq!(ix.accounts[4].pubkey, crate::ID);
+        assert_eq!(ix.accounts[5].pubkey, get_leaderboard_pda(&crate::ID).0);
+
+        let ix = save_deck_with_cards(&player, 2, "Tanks".to_string(), &[(card_mint, 404)]);
+        assert_eq!(ix.accounts[3].pubkey, get_synergy_config_pda(&crate::ID).0);
+        assert_eq!(ix.accounts[4].pubkey, get_card_instance_pda(&card_mint, &crate::ID).0);
+        assert_eq!(ix.accounts[5].pubkey, get_card_template_pda(404, &crate::ID).0);
+        assert_eq!(ix.accounts.len(), 6);
+
+        // Collection pages are optional on every card-moving instruction
+        let seller = Pubkey::new_unique();
+        let ix = buy_card(&player, &seller, &card_mint, Some(0), Some(2));
+        assert_eq!(ix.accounts[13].pubkey, get_collection_pda(&player, 0, &crate::ID).0);
+        assert_eq!(ix.accounts[14].pubkey, get_collection_pda(&seller, 2, &crate::ID).0);
+        assert!(ix.accounts[14].is_writable);
+        let ix = claim_pulled_cards(&player, &[(404, card_mint)], None);
+        assert_eq!(ix.accounts[6].pubkey, crate::ID);
+        assert_eq!(ix.accounts.len(), 11);
+
+        let ix = sync_collection(&player, 1, 3, vec![card_mint]);
+        assert_eq!(ix.accounts[0].pubkey, get_collection_pda(&player, 1, &crate::ID).0);
+        assert_eq!(ix.accounts[3].pubkey, get_card_instance_pda(&card_mint, &crate::ID).0);
+        assert_eq!(ix.accounts[4].pubkey, get_collection_pda(&player, 0, &crate::ID).0);
+        assert_eq!(ix.accounts[5].pubkey, get_collection_pda(&player, 2, &crate::ID).0);
+        assert_eq!(ix.accounts.len(), 6);
+
+        let ix = prune_collection(&player, 2, vec![card_mint]);
+        assert!(ix.accounts[0].is_writable && ix.accounts[0].pubkey == get_collection_pda(&player, 2, &crate::ID).0);
+        assert_eq!(ix.accounts[1].pubkey, get_card_instance_pda(&card_mint, &crate::ID).0);
+        assert_eq!(ix.accounts.len(), 2);
+    }
+
+    #[test]
+    fn test_remaining_accounts_are_profiles_in_order() {
+        let first = Pubkey::new_unique();
+        let second = Pubkey::new_unique();
+
+        let ix = distribute_tournament_prizes(7, &[first, second]);
+        assert_eq!(ix.accounts.len(), 4);
+        assert_eq!(ix.accounts[2].pubkey, get_player_profile_pda(&first, &crate::ID).0);
+        assert_eq!(ix.accounts[3].pubkey, get_player_profile_pda(&second, &crate::ID).0);
+        assert!(ix.accounts[2].is_writable);
+
+        let ix = cancel_tournament(&Pubkey::new_unique(), 7, &[first, second]);
+        assert_eq!(ix.accounts[4].pubkey, get_player_profile_pda(&first, &crate::ID).0);
+        assert_eq!(ix.accounts[5].pubkey, get_player_profile_pda(&second, &crate::ID).0);
+
+        let ix = rerank_leaderboard(&[first]);
+        assert!(!ix.accounts[2].is_writable);
+    }
+
+    #[test]
+    fn test_deserialize_account_checks_discriminator() {
+        let card = CardInstance {
+            mint: Pubkey::new_unique(),
+            card_type_id: 7,
+            attack: 5,
+            health: 9,
+            owner: Pubkey::new_unique(),
+            bump: 254,
+            xp: 0,
+            level: 0,
+        };
+        let mut data = Vec::new();
+        card.try_serialize(&mut data).unwrap();
+
+        let decoded: CardInstance = deserialize_account(&data).unwrap();
+        assert_eq!(decoded.mint, card.mint);
+        assert_eq!(decoded.health, 9);
+
+        assert!(deserialize_account::<Listing>(&data).is_err());
+    }
+}
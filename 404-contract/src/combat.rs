@@ -0,0 +1,443 @@
+//! Auto-battle rules (enabled with the `combat` feature)
+//!
+//! The board, targeting by trait and the effect of every `Ability`, so the game
+//! server, bots and tests resolve a fight between two boards the same way.
+//! Positions 0-2 are the front row and 3-5 the back row; each turn the units
+//! attack in position order, the first side's unit before the second's.
+//! Trait synergies (`SynergyConfig`) are applied to each board before the fight.
+
+use anchor_lang::prelude::*;
+
+use crate::{active_synergies, apply_synergy, Ability, CardInstance, CardTemplate, GameError, SynergyTier, TraitType};
+
+pub const BOARD_SLOTS: u8 = 6;
+pub const FRONT_ROW_SLOTS: u8 = 3;
+/// Turns before a fight is decided on surviving units
+pub const MAX_TURNS: u32 = 100;
+
+/// A card on the board
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Unit {
+    pub card_type_id: u32,
+    pub trait_type: TraitType,
+    pub ability: Ability,
+    pub position: u8,
+    pub attack: u16,
+    pub health: u16,
+    pub max_health: u16,
+    /// Damage still absorbed by an unbroken shield
+    pub shield: u16,
+}
+
+impl Unit {
+    /// Fails unless `position` is on the board (below BOARD_SLOTS)
+    pub fn new(card_template: &CardTemplate, attack: u16, health: u16, position: u8) -> Result<Self> {
+        require!(position < BOARD_SLOTS, GameError::InvalidBoardPosition);
+        let shield = match card_template.ability {
+            Ability::Shield { amount } => amount,
+            _ => 0,
+        };
+        Ok(Unit {
+            card_type_id: card_template.card_type_id,
+            trait_type: card_template.trait_type,
+            ability: card_template.ability,
+            position,
+            attack,
+            health,
+            max_health: health,
+            shield,
+        })
+    }
+
+    /// A minted card with its rolled (and levelled) stats
+    pub fn from_card(card_template: &CardTemplate, card: &CardInstance, position: u8) -> Result<Self> {
+        Unit::new(card_template, card.attack, card.health, position)
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.health > 0
+    }
+
+    pub fn is_front_row(&self) -> bool {
+        self.position < FRONT_ROW_SLOTS
+    }
+
+    /// Apply incoming damage; the shield takes (part of) the first hit and breaks.
+    /// Returns the health actually lost.
+    pub fn take_damage(&mut self, damage: u16) -> u16 {
+        let absorbed = self.shield.min(damage);
+        self.shield = 0;
+        let lost = (damage - absorbed).min(self.health);
+        self.health -= lost;
+        lost
+    }
+
+    fn heal(&mut self, amount: u16) -> u16 {
+        let healed = amount.min(self.max_health.saturating_sub(self.health));
+        self.health += healed;
+        healed
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    First,
+    Second,
+}
+
+/// One hit of the fight, in order
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hit {
+    pub side: Side,
+    pub attacker: u8,
+    pub target: u8,
+    pub damage: u16,
+    /// Health actually removed (after shields)
+    pub health_lost: u16,
+    pub healed: u16,
+    pub is_splash: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    FirstWins,
+    SecondWins,
+    Draw,
+}
+
+#[derive(Clone, Debug)]
+pub struct BattleResult {
+    pub outcome: Outcome,
+    pub turns: u32,
+    pub hits: Vec<Hit>,
+    pub first: Vec<Unit>,
+    pub second: Vec<Unit>,
+}
+
+fn lowest_position<'a>(units: impl Iterator<Item = (usize, &'a Unit)>) -> Option<usize> {
+    units.min_by_key(|(_, unit)| unit.position).map(|(index, _)| index)
+}
+
+/// Pick the enemy a unit attacks (index into `enemies`), by its trait:
+/// - Warrior, Tank, Support: the opposite front-row unit, else the lowest front-row position, then the back row
+/// - Archer, Mage: the opposite back-row unit, else the lowest back-row position, then the front row
+/// - Assassin: the enemy with the least health
+pub fn select_target(attacker: &Unit, enemies: &[Unit]) -> Option<usize> {
+    let alive = || enemies.iter().enumerate().filter(|(_, enemy)| enemy.is_alive());
+    let front = || alive().filter(|(_, enemy)| enemy.is_front_row());
+    let back = || alive().filter(|(_, enemy)| !enemy.is_front_row());
+    let at = |position: u8| alive().find(|(_, enemy)| enemy.position == position).map(|(index, _)| index);
+    // Same column as the attacker, whichever row it stands in
+    let front_opposite = attacker.position % FRONT_ROW_SLOTS;
+    let back_opposite = front_opposite + FRONT_ROW_SLOTS;
+
+    match attacker.trait_type {
+        TraitType::Warrior | TraitType::Tank | TraitType::Support => {
+            if front().next().is_some() {
+                at(front_opposite).or_else(|| lowest_position(front()))
+            } else {
+                at(back_opposite).or_else(|| lowest_position(back()))
+            }
+        }
+        TraitType::Archer | TraitType::Mage => {
+            if back().next().is_some() {
+                at(back_opposite).or_else(|| lowest_position(back()))
+            } else {
+                at(front_opposite).or_else(|| lowest_position(front()))
+            }
+        }
+        TraitType::Assassin => alive().min_by_key(|(_, enemy)| enemy.health).map(|(index, _)| index),
+    }
+}
+
+/// Units next to `position` in the same row
+fn row_neighbours(position: u8) -> impl Iterator<Item = u8> {
+    let row = position / FRONT_ROW_SLOTS;
+    [position.checked_sub(1), position.checked_add(1)]
+        .into_iter()
+        .flatten()
+        .filter(move |neighbour| neighbour / FRONT_ROW_SLOTS == row)
+}
+
+fn percent_of(value: u16, percent: u8) -> u16 {
+    (value as u32 * percent as u32 / 100) as u16
+}
+
+/// One strike at the attacker's target, with splash and lifesteal
+fn strike(attacker: &mut Unit, enemies: &mut [Unit], side: Side, damage: u16, hits: &mut Vec<Hit>) {
+    let Some(target) = select_target(attacker, enemies) else {
+        return;
+    };
+    let target_position = enemies[target].position;
+    let mut health_lost = enemies[target].take_damage(damage);
+    let mut struck = vec![(target_position, damage, health_lost, false)];
+
+    if let Ability::Splash { percent } = attacker.ability {
+        let splash = percent_of(damage, percent);
+        for neighbour in row_neighbours(target_position) {
+            if let Some(enemy) = enemies.iter_mut().find(|enemy| enemy.position == neighbour && enemy.is_alive()) {
+                let lost = enemy.take_damage(splash);
+                health_lost += lost;
+                struck.push((neighbour, splash, lost, true));
+            }
+        }
+    }
+
+    let healed = match attacker.ability {
+        Ability::Lifesteal { percent } => attacker.heal(percent_of(health_lost, percent)),
+        _ => 0,
+    };
+
+    for (index, (position, damage, lost, is_splash)) in struck.into_iter().enumerate() {
+        hits.push(Hit {
+            side,
+            attacker: attacker.position,
+            target: position,
+            damage,
+            health_lost: lost,
+            healed: if index == 0 { healed } else { 0 },
+            is_splash,
+        });
+    }
+}
+
+/// A unit's attacks for one turn
+pub fn attack(attacker: &mut Unit, enemies: &mut [Unit], side: Side, hits: &mut Vec<Hit>) {
+    strike(attacker, enemies, side, attacker.attack, hits);
+    if let Ability::DoubleAttack { percent } = attacker.ability {
+        strike(attacker, enemies, side, percent_of(attacker.attack, percent), hits);
+    }
+}
+
+/// Raise the stats of a board's units by the synergies its traits reach
+pub fn apply_synergies(units: &mut [Unit], synergy_tiers: &[SynergyTier]) {
+    let traits: Vec<TraitType> = units.iter().map(|unit| unit.trait_type).collect();
+    let synergies = active_synergies(synergy_tiers, &traits);
+    for unit in units.iter_mut() {
+        let (attack, health) = apply_synergy(unit.trait_type, unit.attack, unit.health, &synergies);
+        unit.attack = attack;
+        unit.health = health;
+        unit.max_health = health;
+    }
+}
+
+fn any_alive(units: &[Unit]) -> bool {
+    units.iter().any(Unit::is_alive)
+}
+
+/// Every unit on the board, and no two on the same position
+pub fn validate_board(units: &[Unit]) -> Result<()> {
+    for (i, unit) in units.iter().enumerate() {
+        require!(unit.position < BOARD_SLOTS, GameError::InvalidBoardPosition);
+        require!(
+            !units[..i].iter().any(|other| other.position == unit.position),
+            GameError::InvalidBoardPosition
+        );
+    }
+    Ok(())
+}
+
+/// Fight two boards to the end (or MAX_TURNS, then the side with more units left wins),
+/// after applying each board's trait synergies (pass no tiers to fight on raw stats).
+/// Fails if either board has a unit off the board or two units on one position.
+pub fn simulate_battle(mut first: Vec<Unit>, mut second: Vec<Unit>, synergy_tiers: &[SynergyTier]) -> Result<BattleResult> {
+    validate_board(&first)?;
+    validate_board(&second)?;
+    apply_synergies(&mut first, synergy_tiers);
+    apply_synergies(&mut second, synergy_tiers);
+    let mut hits = Vec::new();
+    let mut turns = 0;
+
+    while turns < MAX_TURNS && any_alive(&first) && any_alive(&second) {
+        turns += 1;
+        for position in 0..BOARD_SLOTS {
+            if let Some(unit) = first.iter_mut().find(|unit| unit.position == position && unit.is_alive()) {
+                attack(unit, &mut second, Side::First, &mut hits);
+            }
+            if !any_alive(&second) {
+                break;
+            }
+            if let Some(unit) = second.iter_mut().find(|unit| unit.position == position && unit.is_alive()) {
+                attack(unit, &mut first, Side::Second, &mut hits);
+            }
+            if !any_alive(&first) {
+                break;
+            }
+        }
+    }
+
+    let first_alive = first.iter().filter(|unit| unit.is_alive()).count();
+    let second_alive = second.iter().filter(|unit| unit.is_alive()).count();
+    let outcome = match first_alive.cmp(&second_alive) {
+        std::cmp::Ordering::Greater => Outcome::FirstWins,
+        std::cmp::Ordering::Less => Outcome::SecondWins,
+        std::cmp::Ordering::Equal => Outcome::Draw,
+    };
+
+    Ok(BattleResult { outcome, turns, hits, first, second })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::new_test_template;
+
+    fn unit(trait_type: TraitType, ability: Ability, position: u8, attack: u16, health: u16) -> Unit {
+        let card_template = CardTemplate {
+            name: String::from("404 Deer"),
+            trait_type,
+            min_attack: attack,
+            max_attack: attack,
+            min_health: health,
+            max_health: health,
+            ability,
+            ..new_test_template(404)
+        };
+        Unit::new(&card_template, attack, health, position).unwrap()
+    }
+
+    #[test]
+    fn test_select_target_by_trait() {
+        let enemies = vec![
+            unit(TraitType::Warrior, Ability::None, 0, 1, 30),
+            unit(TraitType::Warrior, Ability::None, 2, 1, 10),
+            unit(TraitType::Warrior, Ability::None, 4, 1, 20),
+        ];
+
+        // Warriors hit the opposite front-row unit, else the lowest front position
+        assert_eq!(select_target(&unit(TraitType::Warrior, Ability::None, 2, 1, 1), &enemies), Some(1));
+        assert_eq!(select_target(&unit(TraitType::Warrior, Ability::None, 1, 1, 1), &enemies), Some(0));
+        // Back-row units face the same column
+        assert_eq!(select_target(&unit(TraitType::Warrior, Ability::None, 5, 1, 1), &enemies), Some(1));
+        assert_eq!(select_target(&unit(TraitType::Archer, Ability::None, 4, 1, 1), &enemies), Some(2));
+        // Archers hit the back row first
+        assert_eq!(select_target(&unit(TraitType::Archer, Ability::None, 0, 1, 1), &enemies), Some(2));
+        // Assassins hit the weakest
+        assert_eq!(select_target(&unit(TraitType::Assassin, Ability::None, 5, 1, 1), &enemies), Some(1));
+
+        // With the front row down, warriors move on to the back row
+        let mut enemies = enemies;
+        enemies[0].health = 0;
+        enemies[1].health = 0;
+        assert_eq!(select_target(&unit(TraitType::Warrior, Ability::None, 0, 1, 1), &enemies), Some(2));
+        enemies[2].health = 0;
+        assert_eq!(select_target(&unit(TraitType::Warrior, Ability::None, 0, 1, 1), &enemies), None);
+    }
+
+    #[test]
+    fn test_abilities() {
+        let mut hits = Vec::new();
+
+        // Shield absorbs only the first hit
+        let mut enemies = vec![unit(TraitType::Warrior, Ability::Shield { amount: 8 }, 0, 1, 20)];
+        let mut attacker = unit(TraitType::Warrior, Ability::None, 0, 10, 10);
+        attack(&mut attacker, &mut enemies, Side::First, &mut hits);
+        assert_eq!(enemies[0].health, 18);
+        attack(&mut attacker, &mut enemies, Side::First, &mut hits);
+        assert_eq!(enemies[0].health, 8);
+
+        // Lifesteal heals from damage dealt, up to max health
+        let mut enemies = vec![unit(TraitType::Warrior, Ability::None, 0, 1, 50)];
+        let mut attacker = unit(TraitType::Warrior, Ability::Lifesteal { percent: 50 }, 0, 10, 30);
+        attacker.health = 20;
+        attack(&mut attacker, &mut enemies, Side::First, &mut hits);
+        assert_eq!(attacker.health, 25);
+        attack(&mut attacker, &mut enemies, Side::First, &mut hits);
+        attack(&mut attacker, &mut enemies, Side::First, &mut hits);
+        assert_eq!(attacker.health, 30);
+
+        // Splash hits the target's row neighbours, not the other row
+        let mut enemies = vec![
+            unit(TraitType::Warrior, Ability::None, 0, 1, 50),
+            unit(TraitType::Warrior, Ability::None, 1, 1, 50),
+            unit(TraitType::Warrior, Ability::None, 2, 1, 50),
+            unit(TraitType::Warrior, Ability::None, 4, 1, 50),
+        ];
+        let mut attacker = unit(TraitType::Warrior, Ability::Splash { percent: 50 }, 1, 20, 10);
+        attack(&mut attacker, &mut enemies, Side::First, &mut hits);
+        let health: Vec<u16> = enemies.iter().map(|enemy| enemy.health).collect();
+        assert_eq!(health, vec![40, 30, 40, 50]);
+
+        // Double attack strikes again at a share of attack
+        let mut enemies = vec![unit(TraitType::Warrior, Ability::None, 0, 1, 50)];
+        let mut attacker = unit(TraitType::Warrior, Ability::DoubleAttack { percent: 50 }, 0, 10, 10);
+        hits.clear();
+        attack(&mut attacker, &mut enemies, Side::First, &mut hits);
+        assert_eq!(enemies[0].health, 35);
+        assert_eq!(hits.len(), 2);
+    }
+
+    #[test]
+    fn test_simulate_battle() {
+        let strong = vec![unit(TraitType::Warrior, Ability::None, 0, 10, 50)];
+        let weak = vec![unit(TraitType::Warrior, Ability::None, 0, 5, 20)];
+        let result = simulate_battle(strong.clone(), weak.clone(), &[]).unwrap();
+        assert_eq!(result.outcome, Outcome::FirstWins);
+        assert_eq!(result.turns, 2);
+        assert!(!result.second[0].is_alive());
+        assert_eq!(simulate_battle(weak, strong, &[]).unwrap().outcome, Outcome::SecondWins);
+
+        // Nobody can hurt anybody: decided on survivors after MAX_TURNS
+        let wall = vec![unit(TraitType::Warrior, Ability::None, 0, 0, 10)];
+        let result = simulate_battle(wall.clone(), wall, &[]).unwrap();
+        assert_eq!(result.turns, MAX_TURNS);
+        assert_eq!(result.outcome, Outcome::Draw);
+    }
+
+    #[test]
+    fn test_board_positions() {
+        let card_template = new_test_template(404);
+        assert!(Unit::new(&card_template, 1, 1, BOARD_SLOTS - 1).is_ok());
+        assert!(Unit::new(&card_template, 1, 1, BOARD_SLOTS).is_err());
+        assert!(Unit::new(&card_template, 1, 1, u8::MAX).is_err());
+        assert_eq!(row_neighbours(u8::MAX).count(), 0);
+        assert_eq!(row_neighbours(4).collect::<Vec<u8>>(), vec![3, 5]);
+
+        // Units moved off the board or onto a taken position are rejected
+        let board = vec![unit(TraitType::Warrior, Ability::None, 0, 1, 10)];
+        let mut off_board = board.clone();
+        off_board[0].position = BOARD_SLOTS;
+        assert!(simulate_battle(board.clone(), off_board, &[]).is_err());
+        let stacked = vec![
+            unit(TraitType::Warrior, Ability::None, 2, 1, 10),
+            unit(TraitType::Archer, Ability::None, 2, 1, 10),
+        ];
+        assert!(simulate_battle(stacked, board, &[]).is_err());
+
+        // Healing a unit set above its max health heals nothing
+        let mut overhealed = unit(TraitType::Warrior, Ability::None, 0, 1, 10);
+        overhealed.health = 15;
+        assert_eq!(overhealed.heal(5), 0);
+        assert_eq!(overhealed.health, 15);
+    }
+
+    #[test]
+    fn test_synergies_change_the_fight() {
+        let pair = vec![
+            unit(TraitType::Tank, Ability::None, 0, 5, 20),
+            unit(TraitType::Tank, Ability::None, 1, 5, 20),
+        ];
+        let mut boosted = pair.clone();
+        let tiers = [SynergyTier {
+            trait_type: TraitType::Tank,
+            min_units: 2,
+            attack_bonus_bps: 10_000,
+            health_bonus_bps: 5_000,
+        }];
+        apply_synergies(&mut boosted, &tiers);
+        assert_eq!((boosted[0].attack, boosted[0].health, boosted[0].max_health), (10, 30, 30));
+
+        // One tank alone doesn't reach the tier
+        let mut single = vec![pair[0].clone()];
+        apply_synergies(&mut single, &tiers);
+        assert_eq!((single[0].attack, single[0].health), (5, 20));
+
+        // Mirror boards go to the side striking first; a synergy outweighs that
+        let mixed = vec![
+            unit(TraitType::Tank, Ability::None, 0, 5, 20),
+            unit(TraitType::Mage, Ability::None, 1, 5, 20),
+        ];
+        assert_eq!(simulate_battle(pair.clone(), pair.clone(), &[]).unwrap().outcome, Outcome::FirstWins);
+        assert_eq!(simulate_battle(mixed, pair, &tiers).unwrap().outcome, Outcome::SecondWins);
+    }
+}
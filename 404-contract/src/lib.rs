@@ -1,10 +1,20 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::solana_program::sysvar::slot_hashes;
 use anchor_spl::token::{self, Token, TokenAccount, Mint, MintTo, Transfer};
-use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::associated_token::{self, AssociatedToken};
+
+#[cfg(feature = "client")]
+pub mod client;
+
+#[cfg(feature = "combat")]
+pub mod combat;
 
 // change
-declare_id!("F27HZp9MUiCx3oXz53kA6A5VsKQTVsiRcpBtADJrgapB "); 
+declare_id!("F27HZp9MUiCx3oXz53kA6A5VsKQTVsiRcpBtADJrgapB"); 
 
+// Instruction arguments are flat parameters; #[program] expands them into wrappers too
+#[allow(clippy::too_many_arguments)]
 #[program]
 pub mod zoo_contract {
     use super::*;
@@ -24,12 +34,19 @@ pub mod zoo_contract {
         game_config.sol_to_bug_rate = sol_to_bug_rate;
         game_config.ticket_price = ticket_price;
         game_config.bump = ctx.bumps.game_config;
+        game_config.current_season_id = 0;
+        game_config.season_trophy_floor = 0;
         
         msg!("Game initialized with authority: {}", game_config.authority);
         msg!("Normal pack price: {}", normal_pack_price);
         msg!("SOL to BUG rate: {} BUG per SOL", sol_to_bug_rate);
         msg!("Ticket price: {} BUG", ticket_price);
         
+        emit!(ConfigUpdated {
+            authority: game_config.authority,
+            kind: ConfigKind::Game,
+        });
+        
         Ok(())
     }
     
@@ -49,6 +66,262 @@ pub mod zoo_contract {
         msg!("Added card creator: {}", new_creator);
         msg!("Total card creators: {}", game_config.card_creators.len());
         
+        emit!(ConfigUpdated {
+            authority: game_config.authority,
+            kind: ConfigKind::CardCreators,
+        });
+        
+        Ok(())
+    }
+    
+    /// Create or update the trophy / reward economy (admin function)
+    /// max_streak_bonus and daily_reward_cap use 0 for "no limit"
+    /// draw_trophy_delta is added to both players on a draw (0 = unchanged)
+    pub fn update_economy_config(
+        ctx: Context<UpdateEconomyConfig>,
+        base_trophy_gain: u32,
+        trophy_loss: u32,
+        max_streak_bonus: u32,
+        win_reward: u64,
+        loss_reward: u64,
+        daily_reward_cap: u64,
+        draw_trophy_delta: u32,
+    ) -> Result<()> {
+        let economy_config = &mut ctx.accounts.economy_config;
+        
+        economy_config.base_trophy_gain = base_trophy_gain;
+        economy_config.trophy_loss = trophy_loss;
+        economy_config.max_streak_bonus = max_streak_bonus;
+        economy_config.win_reward = win_reward;
+        economy_config.loss_reward = loss_reward;
+        economy_config.daily_reward_cap = daily_reward_cap;
+        economy_config.bump = ctx.bumps.economy_config;
+        economy_config.draw_trophy_delta = draw_trophy_delta;
+        
+        msg!("Economy updated: +{} (streak cap {}) / -{} trophies", 
+            base_trophy_gain, max_streak_bonus, trophy_loss);
+        msg!("Rewards: win {} BUG, loss {} BUG, daily cap {}", 
+            win_reward, loss_reward, daily_reward_cap);
+        msg!("Draw trophy delta: +{}", draw_trophy_delta);
+        
+        emit!(ConfigUpdated {
+            authority: ctx.accounts.authority.key(),
+            kind: ConfigKind::Economy,
+        });
+        
+        Ok(())
+    }
+    
+    /// Create or update arena tier thresholds (admin function)
+    /// One entry per ArenaTier (Bronze -> Legend), ascending, Bronze starting at 0.
+    /// Tiers with has_floor set can't be dropped out of once reached in a season.
+    pub fn update_tier_config(
+        ctx: Context<UpdateTierConfig>,
+        tiers: Vec<TierThreshold>,
+    ) -> Result<()> {
+        validate_tier_thresholds(&tiers)?;
+        
+        let tier_config = &mut ctx.accounts.tier_config;
+        tier_config.tiers = tiers;
+        tier_config.bump = ctx.bumps.tier_config;
+        
+        for (index, tier) in tier_config.tiers.iter().enumerate() {
+            msg!("{:?}: {}+ trophies{}", ArenaTier::from_index(index), tier.min_trophies,
+                if tier.has_floor { " (floor)" } else { "" });
+        }
+        
+        emit!(ConfigUpdated {
+            authority: ctx.accounts.authority.key(),
+            kind: ConfigKind::Tiers,
+        });
+        
+        Ok(())
+    }
+    
+    /// Create or update card XP and levelling (admin function)
+    /// level_thresholds: total XP for each level above the minted card, ascending (max 20).
+    /// Every level adds attack_per_level / health_per_level, never beyond the
+    /// template's max stat plus max_bonus_bps of it.
+    pub fn update_progression_config(
+        ctx: Context<UpdateProgressionConfig>,
+        xp_per_win: u32,
+        xp_per_loss: u32,
+        level_thresholds: Vec<u32>,
+        attack_per_level: u16,
+        health_per_level: u16,
+        max_bonus_bps: u16,
+    ) -> Result<()> {
+        validate_level_thresholds(&level_thresholds)?;
+        
+        let progression_config = &mut ctx.accounts.progression_config;
+        progression_config.xp_per_win = xp_per_win;
+        progression_config.xp_per_loss = xp_per_loss;
+        progression_config.level_thresholds = level_thresholds;
+        progression_config.attack_per_level = attack_per_level;
+        progression_config.health_per_level = health_per_level;
+        progression_config.max_bonus_bps = max_bonus_bps;
+        progression_config.bump = ctx.bumps.progression_config;
+        
+        msg!("Card XP: +{} per win, +{} per loss, {} levels", 
+            xp_per_win, xp_per_loss, progression_config.level_thresholds.len());
+        msg!("Per level: +{} ATK, +{} HP (cap +{} bps over template max)", 
+            attack_per_level, health_per_level, max_bonus_bps);
+        
+        emit!(ConfigUpdated {
+            authority: ctx.accounts.authority.key(),
+            kind: ConfigKind::Progression,
+        });
+        
+        Ok(())
+    }
+    
+    /// Create or update the rules of reroll_card_stats (admin function)
+    /// Each reroll costs `price` BUG or tickets; with keep_better set, each stat
+    /// keeps the higher of its old and new roll.
+    pub fn update_reroll_config(
+        ctx: Context<UpdateRerollConfig>,
+        currency: EntryCurrency,
+        price: u64,
+        keep_better: bool,
+    ) -> Result<()> {
+        require!(price > 0, GameError::InvalidPrice);
+        
+        let reroll_config = &mut ctx.accounts.reroll_config;
+        reroll_config.currency = currency;
+        reroll_config.price = price;
+        reroll_config.keep_better = keep_better;
+        reroll_config.bump = ctx.bumps.reroll_config;
+        
+        msg!("Reroll: {} {:?}, keep better: {}", price, currency, keep_better);
+        
+        emit!(ConfigUpdated {
+            authority: ctx.accounts.authority.key(),
+            kind: ConfigKind::Reroll,
+        });
+        
+        Ok(())
+    }
+    
+    /// Create or update the trait synergy bonuses (admin function)
+    /// A deck with at least min_units cards of a trait gives those cards the tier's
+    /// attack/health bonus; tiers of the same trait don't stack, the highest reached applies.
+    pub fn update_synergy_config(
+        ctx: Context<UpdateSynergyConfig>,
+        tiers: Vec<SynergyTier>,
+    ) -> Result<()> {
+        validate_synergy_tiers(&tiers)?;
+        
+        let synergy_config = &mut ctx.accounts.synergy_config;
+        synergy_config.tiers = tiers;
+        synergy_config.bump = ctx.bumps.synergy_config;
+        
+        msg!("Synergy tiers updated: {} tiers", synergy_config.tiers.len());
+        
+        emit!(ConfigUpdated {
+            authority: ctx.accounts.authority.key(),
+            kind: ConfigKind::Synergy,
+        });
+        
+        Ok(())
+    }
+    
+    /// Create or update a purchasable pack type (admin function)
+    /// card_count: 1-10 cards; the last card is at least guaranteed_rarity.
+    /// trait_filter restricts the pack to cards of one trait (None = any card);
+    /// pass the CardTemplate of every card of that trait the pack may contain in
+    /// remaining_accounts, their ids are stored on the pack type.
+    /// set_id draws from that card set's pools instead of the standard ones (0 = standard).
+    /// Deactivated pack types can no longer be bought.
+    pub fn update_pack_type<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UpdatePackType<'info>>,
+        pack_type: u8,
+        name: String,
+        price: u64,
+        card_count: u8,
+        guaranteed_rarity: Rarity,
+        trait_filter: Option<TraitType>,
+        is_active: bool,
+        set_id: u16,
+    ) -> Result<()> {
+        validate_non_empty_string(&name)?;
+        validate_string_length(&name, PackType::MAX_NAME_LEN)?;
+        require!(price > 0, GameError::InvalidPrice);
+        require!(
+            card_count >= 1 && card_count as usize <= PackType::MAX_CARDS,
+            GameError::InvalidDrawCount
+        );
+        
+        // Cards a trait-filtered pack may contain, read from their templates
+        let mut card_type_ids = Vec::new();
+        if let Some(trait_type) = trait_filter {
+            require!(!ctx.remaining_accounts.is_empty(), GameError::EmptyTraitFilter);
+            require!(
+                ctx.remaining_accounts.len() <= PackType::MAX_FILTERED_CARDS,
+                GameError::TooManyFilteredCards
+            );
+            for account_info in ctx.remaining_accounts.iter() {
+                let template = Account::<CardTemplate>::try_from(account_info)?;
+                require!(template.trait_type == trait_type, GameError::TraitMismatch);
+                if !card_type_ids.contains(&template.card_type_id) {
+                    card_type_ids.push(template.card_type_id);
+                }
+            }
+        }
+        
+        let pack = &mut ctx.accounts.pack;
+        pack.pack_type = pack_type;
+        pack.name = name;
+        pack.price = price;
+        pack.card_count = card_count;
+        pack.guaranteed_rarity = guaranteed_rarity;
+        pack.trait_filter = trait_filter;
+        pack.is_active = is_active;
+        pack.bump = ctx.bumps.pack;
+        pack.set_id = set_id;
+        pack.card_type_ids = card_type_ids;
+        
+        msg!("Pack type {} ({}): {} cards for {} BUG, last card {:?}+", 
+            pack_type, pack.name, card_count, price, guaranteed_rarity);
+        msg!("Trait filter: {:?}, set: {}, active: {}", trait_filter, set_id, is_active);
+        
+        emit!(ConfigUpdated {
+            authority: ctx.accounts.authority.key(),
+            kind: ConfigKind::PackType,
+        });
+        
+        Ok(())
+    }
+    
+    /// Create or update a card set / expansion (admin function)
+    /// set_id 0 is reserved for the base game. Set packs can be bought once
+    /// release_time has passed, while the set is active.
+    pub fn update_card_set(
+        ctx: Context<UpdateCardSet>,
+        set_id: u16,
+        name: String,
+        release_time: i64,
+        is_active: bool,
+    ) -> Result<()> {
+        require!(set_id != 0, GameError::InvalidCardSet);
+        validate_non_empty_string(&name)?;
+        validate_string_length(&name, CardSet::MAX_NAME_LEN)?;
+        
+        let card_set = &mut ctx.accounts.card_set;
+        card_set.set_id = set_id;
+        card_set.name = name.clone();
+        card_set.release_time = release_time;
+        card_set.is_active = is_active;
+        card_set.bump = ctx.bumps.card_set;
+        
+        msg!("Card set {} ({}): released {}, active: {}", set_id, name, release_time, is_active);
+        
+        emit!(CardSetUpdated {
+            set_id,
+            name,
+            release_time,
+            is_active,
+        });
+        
         Ok(())
     }
     
@@ -66,6 +339,8 @@ pub mod zoo_contract {
         max_health: u16,
         description: String,
         image_uri: String,
+        set_id: u16,
+        ability: Ability,
     ) -> Result<()> {
         let game_config = &ctx.accounts.game_config;
         let creator = &ctx.accounts.creator;
@@ -76,9 +351,16 @@ pub mod zoo_contract {
             GameError::Unauthorized
         );
         
+        // Cards outside the base game (set 0) need their CardSet to exist
+        if set_id != 0 {
+            let card_set = ctx.accounts.card_set.as_ref().ok_or(GameError::InvalidCardSet)?;
+            require!(card_set.set_id == set_id, GameError::InvalidCardSet);
+        }
+        
         // Validate stat ranges
         require!(min_attack <= max_attack, GameError::InvalidStatRange);
         require!(min_health <= max_health, GameError::InvalidStatRange);
+        validate_ability(&ability)?;
         
         // Validate non-empty strings
         validate_non_empty_string(&name)?;
@@ -101,18 +383,88 @@ pub mod zoo_contract {
         card_template.description = description.clone();
         card_template.image_uri = image_uri.clone();
         card_template.bump = ctx.bumps.card_template;
+        card_template.creator = creator.key();
+        card_template.version = 1;
+        card_template.is_retired = false;
+        card_template.set_id = set_id;
+        card_template.ability = ability;
         
         msg!("Created card template: {} (ID: {})", name, card_type_id);
-        msg!("Trait: {:?}, Rarity: {:?}", trait_type, rarity);
+        msg!("Trait: {:?}, Rarity: {:?}, Set: {}", trait_type, rarity, set_id);
+        msg!("Ability: {:?}", ability);
+        msg!("Stats: ATK {}-{}, HP {}-{}", min_attack, max_attack, min_health, max_health);
+        
+        emit!(CardTemplateCreated {
+            card_type_id,
+            creator: creator.key(),
+            name,
+            trait_type,
+            rarity,
+            min_attack,
+            max_attack,
+            min_health,
+            max_health,
+            set_id,
+            ability,
+        });
+        
+        Ok(())
+    }
+    
+    /// Balance patch: adjust a template's stat ranges, description and image.
+    /// Only the authority or the template's original creator may edit it.
+    pub fn update_card_template(
+        ctx: Context<UpdateCardTemplate>,
+        _card_type_id: u32,
+        min_attack: u16,
+        max_attack: u16,
+        min_health: u16,
+        max_health: u16,
+        description: String,
+        image_uri: String,
+        ability: Ability,
+    ) -> Result<()> {
+        let game_config = &ctx.accounts.game_config;
+        let editor = &ctx.accounts.editor;
+        let card_template = &mut ctx.accounts.card_template;
+        
+        require!(
+            editor.key() == game_config.authority || editor.key() == card_template.creator,
+            GameError::Unauthorized
+        );
+        
+        let new_values = CardTemplateValues {
+            min_attack,
+            max_attack,
+            min_health,
+            max_health,
+            description,
+            image_uri,
+            ability,
+        };
+        let old_values = apply_card_template_update(card_template, new_values.clone())?;
+        
+        msg!("Updated card template {} to version {}", card_template.card_type_id, card_template.version);
         msg!("Stats: ATK {}-{}, HP {}-{}", min_attack, max_attack, min_health, max_health);
         
+        emit!(CardTemplateUpdated {
+            card_type_id: card_template.card_type_id,
+            editor: editor.key(),
+            version: card_template.version,
+            old_values,
+            new_values,
+        });
+        
         Ok(())
     }
     
-    pub fn update_rarity_pool(
-        ctx: Context<UpdateRarityPool>,
+    /// Add cards to a rarity pool, or change the drop weight of cards already in it
+    /// Pass the CardTemplate account of every entry in remaining_accounts,
+    /// in the same order; each must exist, match the pool's rarity and not be retired.
+    pub fn update_rarity_pool<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UpdateRarityPool<'info>>,
         rarity_discriminant: u8,
-        card_type_ids: Vec<u32>,
+        entries: Vec<PoolEntry>,
     ) -> Result<()> {
         let rarity_pool = &mut ctx.accounts.rarity_pool;
         
@@ -125,20 +477,130 @@ pub mod zoo_contract {
         };
         
         // Initialize if this is the first time
-        if rarity_pool.card_type_ids.is_empty() {
+        if rarity_pool.entries.is_empty() {
             rarity_pool.rarity = rarity;
             rarity_pool.bump = ctx.bumps.rarity_pool;
         }
         
-        // Add new card_type_ids to the pool
-        for card_type_id in card_type_ids.iter() {
-            if !rarity_pool.card_type_ids.contains(card_type_id) {
-                rarity_pool.card_type_ids.push(*card_type_id);
+        // Every id must be backed by a live template of this rarity
+        let templates = load_card_templates(ctx.remaining_accounts, entries.len())?;
+        let card_type_ids: Vec<u32> = entries.iter().map(|entry| entry.card_type_id).collect();
+        validate_pool_templates(rarity, &card_type_ids, &templates)?;
+        
+        // Add new entries, re-weight existing ones
+        add_to_pool(rarity_pool, &entries)?;
+        
+        msg!("Updated rarity pool for {:?}", rarity);
+        msg!("Total cards in pool: {}", rarity_pool.entries.len());
+        
+        emit!(ConfigUpdated {
+            authority: ctx.accounts.authority.key(),
+            kind: ConfigKind::RarityPool,
+        });
+        
+        Ok(())
+    }
+    
+    /// Add cards to a card set's own rarity pool, or change their drop weight.
+    /// Same rules as update_rarity_pool, and every card must belong to the set.
+    /// Set pools stay intact when a set rotates out of the standard pools.
+    pub fn update_set_pool<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UpdateSetPool<'info>>,
+        set_id: u16,
+        rarity_discriminant: u8,
+        entries: Vec<PoolEntry>,
+    ) -> Result<()> {
+        let set_pool = &mut ctx.accounts.set_pool;
+        
+        let rarity = match rarity_discriminant {
+            0 => Rarity::Common,
+            1 => Rarity::Rare,
+            2 => Rarity::Legendary,
+            _ => return Err(GameError::InvalidRarity.into()),
+        };
+        
+        // Initialize if this is the first time
+        if set_pool.entries.is_empty() {
+            set_pool.rarity = rarity;
+            set_pool.set_id = set_id;
+            set_pool.bump = ctx.bumps.set_pool;
+        }
+        
+        let templates = load_card_templates(ctx.remaining_accounts, entries.len())?;
+        let card_type_ids: Vec<u32> = entries.iter().map(|entry| entry.card_type_id).collect();
+        validate_pool_templates(rarity, &card_type_ids, &templates)?;
+        validate_set_templates(set_id, &templates)?;
+        
+        add_to_pool(set_pool, &entries)?;
+        
+        msg!("Updated {:?} pool of card set {}", rarity, set_id);
+        msg!("Total cards in pool: {}", set_pool.entries.len());
+        
+        emit!(ConfigUpdated {
+            authority: ctx.accounts.authority.key(),
+            kind: ConfigKind::RarityPool,
+        });
+        
+        Ok(())
+    }
+    
+    /// Take card ids out of a rarity pool so they stop dropping
+    /// Works on standard and card set pools; rotating a set out of the
+    /// standard drop pool means removing its cards from the standard pools.
+    pub fn remove_from_rarity_pool(
+        ctx: Context<RemoveFromRarityPool>,
+        _rarity_discriminant: u8,
+        card_type_ids: Vec<u32>,
+    ) -> Result<()> {
+        let rarity_pool = &mut ctx.accounts.rarity_pool;
+        
+        let removed = remove_from_pool(rarity_pool, &card_type_ids);
+        
+        msg!("Removed {} cards from rarity pool {:?}", removed, rarity_pool.rarity);
+        msg!("Total cards in pool: {}", rarity_pool.entries.len());
+        
+        emit!(ConfigUpdated {
+            authority: ctx.accounts.authority.key(),
+            kind: ConfigKind::RarityPool,
+        });
+        
+        Ok(())
+    }
+    
+    /// Retire (or un-retire) a card template. Retiring also removes it from its
+    /// rarity pool, and from its card set's pool when that is passed; banners skip
+    /// it from then on (see roll_banner_card) and cards already minted keep working.
+    /// Un-retiring doesn't re-add it to the pools (use update_rarity_pool /
+    /// update_set_pool), but it drops from its banners again.
+    pub fn set_card_template_retired(
+        ctx: Context<SetCardTemplateRetired>,
+        _card_type_id: u32,
+        retired: bool,
+    ) -> Result<()> {
+        let card_template = &mut ctx.accounts.card_template;
+        let rarity_pool = &mut ctx.accounts.rarity_pool;
+        
+        card_template.is_retired = retired;
+        if retired {
+            remove_from_pool(rarity_pool, &[card_template.card_type_id]);
+            if let Some(set_pool) = ctx.accounts.set_pool.as_mut() {
+                require!(
+                    card_template.set_id != 0
+                        && set_pool.set_id == card_template.set_id
+                        && set_pool.rarity == card_template.rarity,
+                    GameError::InvalidRarityPool
+                );
+                remove_from_pool(set_pool, &[card_template.card_type_id]);
             }
         }
         
-        msg!("Updated rarity pool for {:?}", rarity);
-        msg!("Total cards in pool: {}", rarity_pool.card_type_ids.len());
+        msg!("Card template {} retired: {}", card_template.card_type_id, retired);
+        
+        emit!(CardTemplateRetired {
+            card_type_id: card_template.card_type_id,
+            authority: ctx.accounts.authority.key(),
+            retired,
+        });
         
         Ok(())
     }
@@ -162,10 +624,17 @@ pub mod zoo_contract {
         player_profile.total_losses = 0;
         player_profile.win_streak = 0;
         player_profile.bump = ctx.bumps.player_profile;
+        player_profile.rating = PlayerProfile::INITIAL_RATING;
+        player_profile.season_id = ctx.accounts.game_config.current_season_id;
         
         msg!("Player registered: {}", username);
         msg!("Wallet: {}", player_profile.wallet);
         
+        emit!(PlayerRegistered {
+            wallet: player_profile.wallet,
+            username,
+        });
+        
         Ok(())
     }
     
@@ -184,71 +653,213 @@ pub mod zoo_contract {
             PlayerProfile::FREE_STARTER_TICKETS, 
             player_profile.wallet);
         
+        emit!(TicketsClaimed {
+            wallet: player_profile.wallet,
+            amount: PlayerProfile::FREE_STARTER_TICKETS,
+            total_tickets: player_profile.gacha_tickets,
+        });
+        
         Ok(())
     }
     
-    /// Use gacha tickets to draw a single card (1 ticket = 1 draw)
-    /// For multiple draws, call this instruction multiple times with different mints
-    pub fn gacha_draw(ctx: Context<GachaDraw>) -> Result<()> {
+    /// Use gacha tickets to pay for `count` draws (1 ticket = 1 draw, at most
+    /// PendingPull::MAX_CARDS). The cards are rolled from the hash of this slot, which
+    /// nobody knows yet: reveal them with reveal_gacha_pull in a later slot, then mint
+    /// them with claim_pulled_cards. A player has one pending pull at a time.
+    pub fn gacha_draw(ctx: Context<GachaDraw>, count: u8) -> Result<()> {
         let player_profile = &mut ctx.accounts.player_profile;
         let player = &ctx.accounts.player;
-        let card_template = &ctx.accounts.card_template;
         let clock = Clock::get()?;
         
+        require!(
+            count > 0 && count as usize <= PendingPull::MAX_CARDS,
+            GameError::InvalidPullCount
+        );
+        
         // Check player has enough tickets
         require!(
-            player_profile.gacha_tickets >= 1,
+            player_profile.gacha_tickets >= count as u64,
             GameError::InsufficientTickets
         );
         
-        msg!("Player {} drawing 1 card", player.key());
+        msg!("Player {} drawing {} cards", player.key(), count);
         
-        // Deduct 1 ticket
+        // Deduct the tickets
         player_profile.gacha_tickets = player_profile.gacha_tickets
-            .checked_sub(1)
+            .checked_sub(count as u64)
             .ok_or(GameError::NumericalOverflow)?;
         
-        // Generate random value
-        let random_value = generate_random_u64(&clock, &player.key(), clock.slot);
+        ctx.accounts.pending_pull.set_inner(PendingPull::new(
+            player.key(),
+            PullSource::Gacha,
+            clock.slot,
+            count,
+            ctx.bumps.pending_pull,
+        ));
         
-        // Roll stats based on card template
-        let (actual_attack, actual_health) = roll_card_stats(
-            card_template.min_attack,
-            card_template.max_attack,
-            card_template.min_health,
-            card_template.max_health,
-            random_value,
+        msg!("Tickets remaining: {}", player_profile.gacha_tickets);
+        
+        emit!(PullCommitted {
+            wallet: player.key(),
+            source: PullSource::Gacha,
+            card_count: count,
+            commit_slot: clock.slot,
+        });
+        
+        Ok(())
+    }
+    
+    /// Roll the cards of a pending gacha draw from the hash of the slot it was paid in
+    /// Anyone may call this from the next slot on. Returns the card ids, so clients
+    /// simulate it to learn which templates claim_pulled_cards needs.
+    pub fn reveal_gacha_pull(ctx: Context<RevealGachaPull>) -> Result<Vec<u32>> {
+        let pending_pull = &mut ctx.accounts.pending_pull;
+        require!(pending_pull.source == PullSource::Gacha, GameError::InvalidPullSource);
+        
+        let (seed, expired) = pull_seed(
+            pending_pull,
+            &ctx.accounts.slot_hashes.try_borrow_data()?,
+            Clock::get()?.slot,
+        )?;
+        let pools: [&RarityPool; 3] =
+            [&ctx.accounts.rarity_pool_common, &ctx.accounts.rarity_pool_rare, &ctx.accounts.rarity_pool_legendary];
+        let card_type_ids = (0..pending_pull.card_count)
+            .map(|index| roll_gacha_card(pools, pull_card_random(&seed, index, expired)))
+            .collect::<Result<Vec<u32>>>()?;
+        
+        pending_pull.reveal(card_type_ids.clone(), seed, expired);
+        
+        msg!("Revealed gacha pull of {}: {:?}", pending_pull.player, card_type_ids);
+        
+        emit!(PullRevealed {
+            wallet: pending_pull.player,
+            source: PullSource::Gacha,
+            card_type_ids: card_type_ids.clone(),
+        });
+        
+        Ok(card_type_ids)
+    }
+    
+    /// Mint the next cards of a revealed pull. For every card pass its CardTemplate,
+    /// a new mint (initialized by the client with game_config as mint authority), the
+    /// player's associated token account for it and its CardInstance PDA in
+    /// remaining_accounts, in that order, for as many cards as fit in the transaction.
+    /// The pending pull is closed, its rent back to the player, with the last card.
+    // `usize::is_multiple_of` is newer than the Solana platform tools' rustc
+    #[allow(clippy::manual_is_multiple_of)]
+    pub fn claim_pulled_cards<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimPulledCards<'info>>,
+    ) -> Result<()> {
+        let pending_pull = &mut ctx.accounts.pending_pull;
+        let player = &ctx.accounts.player;
+        
+        require!(!pending_pull.card_type_ids.is_empty(), GameError::PullNotRevealed);
+        let first = pending_pull.claimed as usize;
+        let claiming = ctx.remaining_accounts.len() / 4;
+        require!(
+            claiming > 0
+                && ctx.remaining_accounts.len() % 4 == 0
+                && first + claiming <= pending_pull.card_type_ids.len(),
+            GameError::InvalidPackAccounts
         );
         
-        // Mint 1 token to player's token account
-        // The mint should already be initialized by the client before calling this
-        let seeds = &[b"game_config".as_ref(), &[ctx.accounts.game_config.bump]];
+        let game_config = &ctx.accounts.game_config;
+        let seeds = &[b"game_config".as_ref(), &[game_config.bump]];
         let signer_seeds = &[&seeds[..]];
         
-        let mint_ctx = CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            MintTo {
-                mint: ctx.accounts.card_mint.to_account_info(),
-                to: ctx.accounts.player_card_token_account.to_account_info(),
-                authority: ctx.accounts.game_config.to_account_info(),
-            },
-            signer_seeds,
-        );
-        token::mint_to(mint_ctx, 1)?;
+        for (i, accounts) in (first..first + claiming).zip(ctx.remaining_accounts.chunks_exact(4)) {
+            let card_type_id = pending_pull.card_type_ids[i];
+            let (template_info, mint_info, token_info, instance_info) =
+                (&accounts[0], &accounts[1], &accounts[2], &accounts[3]);
+            
+            // A card retired after the reveal is still minted: the pull was paid for
+            let card_template = Account::<CardTemplate>::try_from(template_info)?;
+            require!(card_template.card_type_id == card_type_id, GameError::CardNotRolled);
+            let card_mint = Account::<Mint>::try_from(mint_info)?;
+            require!(
+                card_mint.mint_authority == COption::Some(game_config.key()),
+                GameError::Unauthorized
+            );
+            
+            // Roll stats based on card template
+            let (actual_attack, actual_health) = roll_card_stats(
+                card_template.min_attack,
+                card_template.max_attack,
+                card_template.min_health,
+                card_template.max_health,
+                pull_stats_random(&pending_pull.seed, i as u8),
+            );
+            
+            // Mint 1 token to the player's (possibly new) associated token account
+            associated_token::create_idempotent(CpiContext::new(
+                ctx.accounts.associated_token_program.to_account_info(),
+                associated_token::Create {
+                    payer: player.to_account_info(),
+                    associated_token: token_info.clone(),
+                    authority: player.to_account_info(),
+                    mint: mint_info.clone(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                },
+            ))?;
+            let mint_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: mint_info.clone(),
+                    to: token_info.clone(),
+                    authority: game_config.to_account_info(),
+                },
+                signer_seeds,
+            );
+            token::mint_to(mint_ctx, 1)?;
+            
+            create_card_instance(
+                instance_info,
+                CardInstance {
+                    mint: mint_info.key(),
+                    card_type_id,
+                    attack: actual_attack,
+                    health: actual_health,
+                    owner: player.key(),
+                    bump: 0,
+                    xp: 0,
+                    level: 0,
+                },
+                player,
+                &ctx.accounts.system_program,
+            )?;
+            
+            if let Some(collection) = ctx.accounts.collection.as_mut() {
+                add_to_collection(collection, mint_info.key())?;
+            }
+            
+            msg!("Card {}: ID {}, ATK={}, HP={}", i + 1, card_type_id, actual_attack, actual_health);
+            
+            emit!(CardMinted {
+                owner: player.key(),
+                mint: mint_info.key(),
+                card_type_id,
+                attack: actual_attack,
+                health: actual_health,
+            });
+            
+            if let PullSource::Banner { banner_id, pulls } = pending_pull.source {
+                emit!(BannerPulled {
+                    banner_id,
+                    wallet: player.key(),
+                    card_type_id,
+                    mint: mint_info.key(),
+                    pulls,
+                });
+            }
+        }
         
-        // Store card instance data
-        let card_instance = &mut ctx.accounts.card_instance;
-        card_instance.mint = ctx.accounts.card_mint.key();
-        card_instance.card_type_id = card_template.card_type_id;
-        card_instance.attack = actual_attack;
-        card_instance.health = actual_health;
-        card_instance.owner = player.key();
-        card_instance.bump = ctx.bumps.card_instance;
-        
-        msg!("Minted card: type_id={}, ATK={}, HP={}", 
-            card_template.card_type_id, actual_attack, actual_health);
-        msg!("Mint address: {}", ctx.accounts.card_mint.key());
-        msg!("Tickets remaining: {}", player_profile.gacha_tickets);
+        pending_pull.claimed += claiming as u8;
+        msg!("Claimed {}/{} cards", pending_pull.claimed, pending_pull.card_count);
+        
+        if pending_pull.claimed == pending_pull.card_count {
+            pending_pull.close(player.to_account_info())?;
+        }
         
         Ok(())
     }
@@ -267,6 +878,12 @@ pub mod zoo_contract {
         msg!("Added {} tickets to player {}. Total: {}", 
             amount, player_profile.wallet, player_profile.gacha_tickets);
         
+        emit!(TicketsGranted {
+            wallet: player_profile.wallet,
+            amount,
+            total_tickets: player_profile.gacha_tickets,
+        });
+        
         Ok(())
     }
     
@@ -309,6 +926,13 @@ pub mod zoo_contract {
         
         msg!("Bought {} BUG for {} lamports. Balance: {}", bug_amount, sol_amount, player_profile.bug_balance);
         
+        emit!(BugPurchased {
+            wallet: player_profile.wallet,
+            sol_amount,
+            bug_amount,
+            bug_balance: player_profile.bug_balance,
+        });
+        
         Ok(())
     }
     
@@ -338,47 +962,41 @@ pub mod zoo_contract {
         msg!("Bought {} gacha tickets for {} BUG", ticket_count, total_cost);
         msg!("Tickets: {}, BUG balance: {}", player_profile.gacha_tickets, player_profile.bug_balance);
         
+        emit!(TicketsPurchased {
+            wallet: player_profile.wallet,
+            ticket_count,
+            bug_cost: total_cost,
+            total_tickets: player_profile.gacha_tickets,
+            bug_balance: player_profile.bug_balance,
+        });
+        
         Ok(())
     }
     
-    /// Roll for a random card (view function to help client pick card_type_id)
-    /// Client calls this first, then calls gacha_draw with the selected card template
-    pub fn roll_gacha(ctx: Context<RollGacha>) -> Result<u32> {
+    /// Buy a pack of a registered type (see update_pack_type)
+    /// Like gacha_draw this records a pending pull: reveal_pack rolls its cards in a
+    /// later slot and claim_pulled_cards mints them. Packs with a trait filter draw
+    /// from the rarity pools, restricted to the cards stored on the pack type.
+    pub fn purchase_pack(ctx: Context<PurchasePack>, pack_type: u8) -> Result<()> {
+        let pack = &ctx.accounts.pack;
+        let player_profile = &mut ctx.accounts.player_profile;
         let player = &ctx.accounts.player;
         let clock = Clock::get()?;
         
-        // Generate random value
-        let random_value = generate_random_u64(&clock, &player.key(), clock.slot);
-        
-        // Roll for rarity
-        let rarity = roll_rarity(random_value);
-        
-        // Get the appropriate rarity pool
-        let rarity_pool = match rarity {
-            Rarity::Common => &ctx.accounts.rarity_pool_common,
-            Rarity::Rare => &ctx.accounts.rarity_pool_rare,
-            Rarity::Legendary => &ctx.accounts.rarity_pool_legendary,
-        };
-        
-        // Select random card from pool
-        let card_type_id = select_random_card(rarity_pool, random_value)?;
+        require!(pack.is_active, GameError::InvalidPackType);
         
-        msg!("Rolled: {:?} - Card ID {}", rarity, card_type_id);
-        
-        Ok(card_type_id)
-    }
-    
-    pub fn purchase_pack(
-        ctx: Context<PurchasePack>,
-        _pack_type: u8, // For future expansion
-    ) -> Result<()> {
-        let game_config = &ctx.accounts.game_config;
-        let player_profile = &mut ctx.accounts.player_profile;
-        let player = &ctx.accounts.player;
-        let clock = Clock::get()?;
+        // Set packs need their card set active and released
+        if pack.set_id != 0 {
+            let card_set = ctx.accounts.card_set.as_ref().ok_or(GameError::InvalidCardSet)?;
+            require!(card_set.set_id == pack.set_id, GameError::InvalidCardSet);
+            require!(
+                is_card_set_available(card_set, clock.unix_timestamp),
+                GameError::CardSetNotAvailable
+            );
+        }
         
         // Check player has sufficient BUG balance
-        let pack_price = game_config.normal_pack_price;
+        let pack_price = pack.price;
         require!(player_profile.bug_balance >= pack_price, GameError::InsufficientBalance);
         
         // Deduct BUG from player's balance
@@ -388,88 +1006,528 @@ pub mod zoo_contract {
         
         msg!("Pack purchased for {} BUG. Balance: {}", pack_price, player_profile.bug_balance);
         
-        // Determine number of cards (currently fixed, could vary by pack_type in future)
-        let num_cards = game_config.starter_pack_card_count;
-        
-        // Mint random cards
-        for i in 0..num_cards {
-            // Generate random value for this card
-            let random_value = generate_random_u64(&clock, &player.key(), i as u64);
-            
-            // Roll for rarity
-            let rarity = roll_rarity(random_value);
-            
-            // Get the appropriate rarity pool
-            let rarity_pool = match rarity {
-                Rarity::Common => &ctx.accounts.rarity_pool_common,
-                Rarity::Rare => &ctx.accounts.rarity_pool_rare,
-                Rarity::Legendary => &ctx.accounts.rarity_pool_legendary,
-            };
-            
-            // Select random card from pool
-            let card_type_id = select_random_card(rarity_pool, random_value)?;
-            
-            // Generate another random value for stats rolling
-            let stats_random = generate_random_u64(&clock, &player.key(), i as u64 + 1000);
-            
-            // Note: In production, you would fetch the card template here and roll stats
-            // For now, we log placeholder stats (actual implementation needs remaining accounts)
-            // let (actual_attack, actual_health) = roll_card_stats(
-            //     card_template.min_attack,
-            //     card_template.max_attack,
-            //     card_template.min_health,
-            //     card_template.max_health,
-            //     stats_random,
-            // );
-            
-            msg!("Card {}: ID {} ({:?}), stats_seed: {}", i + 1, card_type_id, rarity, stats_random);
-            
-            // Note: In production, this would mint actual NFTs using Metaplex
-            // with the rolled attack and health values stored in metadata
-        }
+        let source = PullSource::Pack { pack_type, price: pack_price };
+        ctx.accounts.pending_pull.set_inner(PendingPull::new(
+            player.key(),
+            source,
+            clock.slot,
+            pack.card_count,
+            ctx.bumps.pending_pull,
+        ));
         
-        msg!("Pack opened successfully!");
-        msg!("Total cards minted: {}", num_cards);
+        emit!(PullCommitted {
+            wallet: player.key(),
+            source,
+            card_count: pack.card_count,
+            commit_slot: clock.slot,
+        });
         
         Ok(())
     }
     
-    /// Save or update a player's deck (up to 10 cards)
-    /// deck_index: 0-4 (player can have up to 5 decks)
-    pub fn save_deck(
-        ctx: Context<SaveDeck>,
-        deck_index: u8,
-        deck_name: String,
-        card_mints: Vec<Pubkey>,
-    ) -> Result<()> {
-        require!(deck_index < PlayerDeck::MAX_DECKS, GameError::InvalidDeckIndex);
-        require!(card_mints.len() <= PlayerDeck::MAX_CARDS, GameError::TooManyCardsInDeck);
-        validate_string_length(&deck_name, PlayerDeck::MAX_NAME_LEN)?;
+    /// Roll the cards of a purchased pack from the hash of the slot it was bought in
+    /// (see reveal_gacha_pull). A slot whose rarity has no card the pack may contain
+    /// draws from the closest rarity that has one; set packs draw from the set's pools.
+    pub fn reveal_pack(ctx: Context<RevealPack>) -> Result<Vec<u32>> {
+        let pending_pull = &mut ctx.accounts.pending_pull;
+        let pack = &ctx.accounts.pack;
+        let PullSource::Pack { pack_type, price } = pending_pull.source else {
+            return Err(GameError::InvalidPullSource.into());
+        };
+        require!(pack_type == pack.pack_type, GameError::InvalidPackType);
         
-        let player_deck = &mut ctx.accounts.player_deck;
-        player_deck.owner = ctx.accounts.player.key();
-        player_deck.deck_index = deck_index;
-        player_deck.deck_name = deck_name.clone();
-        player_deck.card_mints = card_mints.clone();
-        player_deck.is_active = true;
-        player_deck.bump = ctx.bumps.player_deck;
+        let (seed, expired) = pull_seed(
+            pending_pull,
+            &ctx.accounts.slot_hashes.try_borrow_data()?,
+            Clock::get()?.slot,
+        )?;
+        let card_type_ids = roll_pack_cards(
+            pack,
+            [&ctx.accounts.rarity_pool_common, &ctx.accounts.rarity_pool_rare, &ctx.accounts.rarity_pool_legendary],
+            |slot| pull_card_random(&seed, slot as u8, expired),
+        )?;
         
-        msg!("Saved deck {} for player {}", deck_name, ctx.accounts.player.key());
-        msg!("Cards in deck: {}", card_mints.len());
+        pending_pull.reveal(card_type_ids.clone(), seed, expired);
         
-        Ok(())
+        msg!("Opened pack {} of {}: {:?}", pack_type, pending_pull.player, card_type_ids);
+        
+        emit!(PullRevealed {
+            wallet: pending_pull.player,
+            source: pending_pull.source,
+            card_type_ids: card_type_ids.clone(),
+        });
+        
+        emit!(PackOpened {
+            owner: pending_pull.player,
+            pack_type,
+            price,
+            card_type_ids: card_type_ids.clone(),
+        });
+        
+        Ok(card_type_ids)
     }
     
-    /// Delete a player's deck (set to inactive)
-    pub fn delete_deck(ctx: Context<DeleteDeck>, _deck_index: u8) -> Result<()> {
-        let player_deck = &mut ctx.accounts.player_deck;
-        player_deck.is_active = false;
-        player_deck.card_mints = Vec::new();
-        player_deck.deck_name = String::new();
+    /// Burn an owned card NFT for crafting dust (see calculate_burn_dust)
+    /// Closes the player's token account and the CardInstance, returning their rent.
+    /// Listed cards sit in escrow and have to be delisted first.
+    pub fn burn_card(ctx: Context<BurnCard>) -> Result<()> {
+        let card_instance = &ctx.accounts.card_instance;
+        let card_template = &ctx.accounts.card_template;
+        let player = &ctx.accounts.player;
         
-        msg!("Deleted deck {} for player {}", _deck_index, ctx.accounts.player.key());
+        let dust = calculate_burn_dust(card_template, card_instance.attack, card_instance.health);
         
-        Ok(())
+        let burn_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::Burn {
+                mint: ctx.accounts.card_mint.to_account_info(),
+                from: ctx.accounts.player_card_token_account.to_account_info(),
+                authority: player.to_account_info(),
+            },
+        );
+        token::burn(burn_ctx, 1)?;
+        
+        let close_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::CloseAccount {
+                account: ctx.accounts.player_card_token_account.to_account_info(),
+                destination: player.to_account_info(),
+                authority: player.to_account_info(),
+            },
+        );
+        token::close_account(close_ctx)?;
+        
+        // card_instance is closed by its close constraint
+        
+        if let Some(collection) = ctx.accounts.collection.as_mut() {
+            remove_from_collection(collection, &ctx.accounts.card_mint.key())?;
+        }
+        
+        let player_profile = &mut ctx.accounts.player_profile;
+        player_profile.crafting_dust = player_profile.crafting_dust
+            .checked_add(dust)
+            .ok_or(GameError::NumericalOverflow)?;
+        
+        msg!("Burned card: type_id={}, ATK={}, HP={}", 
+            card_instance.card_type_id, card_instance.attack, card_instance.health);
+        msg!("Dust: +{} (balance {})", dust, player_profile.crafting_dust);
+        
+        emit!(CardBurned {
+            owner: player.key(),
+            mint: ctx.accounts.card_mint.key(),
+            card_type_id: card_instance.card_type_id,
+            dust,
+            crafting_dust: player_profile.crafting_dust,
+        });
+        
+        Ok(())
+    }
+    
+    /// Spend crafting dust on a chosen card (cost by rarity, see CardInstance::CRAFT_DUST)
+    /// Its stats are rolled from the hash of this slot: reveal them with reveal_crafted_card
+    /// in a later slot, then mint the card with claim_pulled_cards. Cards of a card set
+    /// need the set account, and the set released.
+    pub fn craft_card(ctx: Context<CraftCard>, card_type_id: u32) -> Result<()> {
+        let player_profile = &mut ctx.accounts.player_profile;
+        let player = &ctx.accounts.player;
+        let card_template = &ctx.accounts.card_template;
+        let clock = Clock::get()?;
+        
+        require!(!card_template.is_retired, GameError::CardTemplateRetired);
+        
+        if card_template.set_id != 0 {
+            let card_set = ctx.accounts.card_set.as_ref().ok_or(GameError::InvalidCardSet)?;
+            require!(card_set.set_id == card_template.set_id, GameError::InvalidCardSet);
+            require!(
+                is_card_set_available(card_set, clock.unix_timestamp),
+                GameError::CardSetNotAvailable
+            );
+        }
+        
+        let cost = calculate_craft_cost(card_template.rarity);
+        require!(player_profile.crafting_dust >= cost, GameError::InsufficientDust);
+        player_profile.crafting_dust = player_profile.crafting_dust
+            .checked_sub(cost)
+            .ok_or(GameError::NumericalOverflow)?;
+        
+        let source = PullSource::Craft { card_type_id };
+        ctx.accounts.pending_pull.set_inner(PendingPull::new(
+            player.key(),
+            source,
+            clock.slot,
+            1,
+            ctx.bumps.pending_pull,
+        ));
+        
+        msg!("Crafting card: type_id={}", card_type_id);
+        msg!("Dust: -{} (balance {})", cost, player_profile.crafting_dust);
+        
+        emit!(CardCrafted {
+            owner: player.key(),
+            card_type_id,
+            dust_spent: cost,
+            crafting_dust: player_profile.crafting_dust,
+        });
+        emit!(PullCommitted {
+            wallet: player.key(),
+            source,
+            card_count: 1,
+            commit_slot: clock.slot,
+        });
+        
+        Ok(())
+    }
+    
+    /// Seed the stats of a crafted card from the hash of the slot it was paid in
+    /// Anyone may call this from the next slot on; claim_pulled_cards then mints the card.
+    pub fn reveal_crafted_card(ctx: Context<RevealCraftedCard>) -> Result<()> {
+        let pending_pull = &mut ctx.accounts.pending_pull;
+        let PullSource::Craft { card_type_id } = pending_pull.source else {
+            return Err(GameError::InvalidPullSource.into());
+        };
+        
+        let (seed, expired) = pull_seed(
+            pending_pull,
+            &ctx.accounts.slot_hashes.try_borrow_data()?,
+            Clock::get()?.slot,
+        )?;
+        pending_pull.reveal(vec![card_type_id], seed, expired);
+        
+        msg!("Revealed crafted card {} of {}", card_type_id, pending_pull.player);
+        
+        emit!(PullRevealed {
+            wallet: pending_pull.player,
+            source: pending_pull.source,
+            card_type_ids: vec![card_type_id],
+        });
+        
+        Ok(())
+    }
+    
+    /// Pay to re-roll an owned card's attack and health within its template's current range
+    /// Price and keep-the-better-roll rule come from RerollConfig (see update_reroll_config).
+    /// The new stats are rolled from the hash of this slot: apply them with
+    /// reveal_card_reroll in a later slot. The reroll is the player's pending pull until then.
+    pub fn reroll_card_stats(ctx: Context<RerollCardStats>) -> Result<()> {
+        let reroll_config = &ctx.accounts.reroll_config;
+        let player_profile = &mut ctx.accounts.player_profile;
+        let player = &ctx.accounts.player;
+        let clock = Clock::get()?;
+        
+        match reroll_config.currency {
+            EntryCurrency::Bug => {
+                require!(player_profile.bug_balance >= reroll_config.price, GameError::InsufficientBalance);
+                player_profile.bug_balance = player_profile.bug_balance
+                    .checked_sub(reroll_config.price)
+                    .ok_or(GameError::NumericalOverflow)?;
+            }
+            EntryCurrency::Tickets => {
+                require!(player_profile.gacha_tickets >= reroll_config.price, GameError::InsufficientTickets);
+                player_profile.gacha_tickets = player_profile.gacha_tickets
+                    .checked_sub(reroll_config.price)
+                    .ok_or(GameError::NumericalOverflow)?;
+            }
+        }
+        
+        // The rules paid for apply, whatever the config says by the reveal
+        let source = PullSource::Reroll {
+            mint: ctx.accounts.card_mint.key(),
+            currency: reroll_config.currency,
+            price: reroll_config.price,
+            keep_better: reroll_config.keep_better,
+        };
+        ctx.accounts.pending_pull.set_inner(PendingPull::new(
+            player.key(),
+            source,
+            clock.slot,
+            1,
+            ctx.bumps.pending_pull,
+        ));
+        
+        msg!("Paid for a reroll of card {}", ctx.accounts.card_mint.key());
+        
+        emit!(PullCommitted {
+            wallet: player.key(),
+            source,
+            card_count: 1,
+            commit_slot: clock.slot,
+        });
+        
+        Ok(())
+    }
+    
+    /// Roll a paid reroll from the hash of the slot it was paid in and apply it to the
+    /// card; a levelled card keeps its level-ups on top of the new roll. Anyone may call
+    /// this from the next slot on. The pending pull is closed, its rent back to the player.
+    /// A card burned since has nothing left to reroll: the pull is just closed.
+    pub fn reveal_card_reroll(ctx: Context<RevealCardReroll>) -> Result<()> {
+        let pending_pull = &mut ctx.accounts.pending_pull;
+        let PullSource::Reroll { mint, currency, price, keep_better } = pending_pull.source else {
+            return Err(GameError::InvalidPullSource.into());
+        };
+        
+        let (seed, expired) = pull_seed(
+            pending_pull,
+            &ctx.accounts.slot_hashes.try_borrow_data()?,
+            Clock::get()?.slot,
+        )?;
+        
+        let instance_info = ctx.accounts.card_instance.to_account_info();
+        let (card_instance_pda, _) = Pubkey::find_program_address(
+            &[b"card_instance", mint.as_ref()],
+            &crate::ID,
+        );
+        require!(instance_info.key() == card_instance_pda, GameError::InvalidRerollAccounts);
+        if instance_info.owner != &crate::ID || instance_info.data_is_empty() {
+            msg!("Card {} was burned, nothing to reroll", mint);
+            return Ok(());
+        }
+        
+        let mut data = instance_info.try_borrow_mut_data()?;
+        let mut card_instance = CardInstance::try_deserialize(&mut &data[..])?;
+        let card_template = ctx.accounts.card_template.as_ref().ok_or(GameError::InvalidRerollAccounts)?;
+        require!(card_template.card_type_id == card_instance.card_type_id, GameError::InvalidRerollAccounts);
+        
+        pending_pull.reveal(vec![card_instance.card_type_id], seed, expired);
+        let old_stats = (card_instance.attack, card_instance.health);
+        
+        let rolled = roll_card_stats(
+            card_template.min_attack,
+            card_template.max_attack,
+            card_template.min_health,
+            card_template.max_health,
+            pull_stats_random(&pending_pull.seed, 0),
+        );
+        // A levelled card keeps its level-ups on top of the new roll
+        let rolled = if card_instance.level > 0 {
+            let progression_config = ctx.accounts.progression_config
+                .as_ref()
+                .ok_or(GameError::ProgressionNotConfigured)?;
+            level_up_stats(rolled, card_instance.level, card_template, progression_config)
+        } else {
+            rolled
+        };
+        let (attack, health) = apply_reroll(old_stats, rolled, keep_better);
+        card_instance.attack = attack;
+        card_instance.health = health;
+        card_instance.try_serialize(&mut &mut data[..])?;
+        
+        msg!("Rerolled card {}: ATK {} -> {}, HP {} -> {} (rolled {}/{})", 
+            mint, old_stats.0, attack, old_stats.1, health, rolled.0, rolled.1);
+        
+        emit!(PullRevealed {
+            wallet: pending_pull.player,
+            source: pending_pull.source,
+            card_type_ids: pending_pull.card_type_ids.clone(),
+        });
+        
+        emit!(CardStatsRerolled {
+            owner: card_instance.owner,
+            mint,
+            card_type_id: card_instance.card_type_id,
+            old_attack: old_stats.0,
+            old_health: old_stats.1,
+            attack,
+            health,
+            currency,
+            price,
+        });
+        
+        Ok(())
+    }
+    
+    /// Save or update a player's deck (up to 10 cards)
+    /// deck_index: 0-4 (player can have up to 5 decks)
+    /// remaining_accounts: optionally a CardInstance and its CardTemplate for every card,
+    /// in deck order, to check ownership and report the deck's trait synergies. With the
+    /// cards passed, card_mints may be left empty to take them from the card instances
+    /// (a full deck with its mints in the data doesn't fit in one transaction).
+    /// Synergies aren't stored on the deck: combat::simulate_battle applies the tiers
+    /// current when the deck fights.
+    pub fn save_deck<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SaveDeck<'info>>,
+        deck_index: u8,
+        deck_name: String,
+        card_mints: Vec<Pubkey>,
+    ) -> Result<()> {
+        require!(deck_index < PlayerDeck::MAX_DECKS, GameError::InvalidDeckIndex);
+        validate_string_length(&deck_name, PlayerDeck::MAX_NAME_LEN)?;
+        
+        let (card_mints, synergies) = if ctx.remaining_accounts.is_empty() {
+            (card_mints, Vec::new())
+        } else {
+            let synergy_config = ctx.accounts.synergy_config
+                .as_ref()
+                .ok_or(GameError::SynergyNotConfigured)?;
+            let (deck_mints, traits) = deck_cards(ctx.remaining_accounts, ctx.accounts.player.key())?;
+            require!(
+                card_mints.is_empty() || card_mints == deck_mints,
+                GameError::InvalidDeckAccounts
+            );
+            (deck_mints, active_synergies(&synergy_config.tiers, &traits))
+        };
+        require!(card_mints.len() <= PlayerDeck::MAX_CARDS, GameError::TooManyCardsInDeck);
+        
+        let player_deck = &mut ctx.accounts.player_deck;
+        player_deck.owner = ctx.accounts.player.key();
+        player_deck.deck_index = deck_index;
+        player_deck.deck_name = deck_name.clone();
+        player_deck.card_mints = card_mints.clone();
+        player_deck.is_active = true;
+        player_deck.bump = ctx.bumps.player_deck;
+        
+        msg!("Saved deck {} for player {}", deck_name, ctx.accounts.player.key());
+        msg!("Cards in deck: {}", card_mints.len());
+        for synergy in &synergies {
+            msg!("Synergy {:?} x{}: +{} bps ATK, +{} bps HP", 
+                synergy.trait_type, synergy.units, synergy.attack_bonus_bps, synergy.health_bonus_bps);
+        }
+        
+        emit!(DeckSaved {
+            owner: player_deck.owner,
+            deck_index,
+            deck_name,
+            card_mints,
+            synergies,
+        });
+        
+        Ok(())
+    }
+    
+    /// Delete a player's deck (set to inactive)
+    pub fn delete_deck(ctx: Context<DeleteDeck>, _deck_index: u8) -> Result<()> {
+        let player_deck = &mut ctx.accounts.player_deck;
+        player_deck.is_active = false;
+        player_deck.card_mints = Vec::new();
+        player_deck.deck_name = String::new();
+        
+        msg!("Deleted deck {} for player {}", _deck_index, ctx.accounts.player.key());
+        
+        emit!(DeckDeleted {
+            owner: ctx.accounts.player.key(),
+            deck_index: _deck_index,
+        });
+        
+        Ok(())
+    }
+    
+    /// Create the player's next collection page (pages are numbered from 0; the
+    /// profile's collection_pages says how many exist, so all pages load in one fetch)
+    pub fn create_collection_page(ctx: Context<CreateCollectionPage>) -> Result<()> {
+        let player_profile = &mut ctx.accounts.player_profile;
+        let page = player_profile.collection_pages;
+        require!(page < u8::MAX, GameError::TooManyCollectionPages);
+        
+        let collection = &mut ctx.accounts.collection;
+        collection.owner = ctx.accounts.player.key();
+        collection.page = page;
+        collection.card_mints = Vec::new();
+        collection.bump = ctx.bumps.collection;
+        player_profile.collection_pages = page + 1;
+        
+        msg!("Created collection page {} for player {}", page, collection.owner);
+        
+        emit!(CollectionPageCreated {
+            owner: collection.owner,
+            page,
+        });
+        
+        Ok(())
+    }
+    
+    /// Bring a collection page in line with the given cards: remaining_accounts are the
+    /// CardInstance PDAs of `card_mints`, in order, followed by the player's other
+    /// collection pages in page order. Cards the player owns are added unless another
+    /// page already indexes them, cards burned or owned by someone else are removed.
+    /// Used to index cards minted before collections existed, or moved without passing the page.
+    pub fn sync_collection<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SyncCollection<'info>>,
+        card_mints: Vec<Pubkey>,
+    ) -> Result<()> {
+        let player = ctx.accounts.player.key();
+        let collection = &mut ctx.accounts.collection;
+        let other_pages: Vec<u8> = (0..ctx.accounts.player_profile.collection_pages)
+            .filter(|page| *page != collection.page)
+            .collect();
+        require!(
+            ctx.remaining_accounts.len() == card_mints.len() + other_pages.len(),
+            GameError::InvalidCollectionCards
+        );
+        let (card_accounts, page_accounts) = ctx.remaining_accounts.split_at(card_mints.len());
+        for (account_info, page) in page_accounts.iter().zip(other_pages.iter()) {
+            let (expected, _) = Pubkey::find_program_address(
+                &[b"collection", player.as_ref(), &[*page]],
+                &crate::ID,
+            );
+            require!(
+                account_info.key() == expected && account_info.owner == &crate::ID,
+                GameError::InvalidCollectionPage
+            );
+        }
+        let mut added = Vec::new();
+        let mut removed = Vec::new();
+        
+        for (account_info, card_mint) in card_accounts.iter().zip(card_mints.iter()) {
+            let owned = card_owned_by(account_info, card_mint, player)?;
+            let indexed = collection.card_mints.contains(card_mint);
+            if owned && !indexed {
+                for page_account in page_accounts {
+                    require!(
+                        !collection_page_contains(&page_account.try_borrow_data()?, card_mint),
+                        GameError::CardOnAnotherPage
+                    );
+                }
+                add_to_collection(collection, *card_mint)?;
+                added.push(*card_mint);
+            } else if !owned && indexed {
+                remove_from_collection(collection, card_mint)?;
+                removed.push(*card_mint);
+            }
+        }
+        
+        msg!("Collection page {}: +{} -{} cards ({} total)", 
+            collection.page, added.len(), removed.len(), collection.card_mints.len());
+        
+        emit!(CollectionSynced {
+            owner: player,
+            page: collection.page,
+            added,
+            removed,
+        });
+        
+        Ok(())
+    }
+    
+    /// Remove cards the page's owner no longer has (burned or sold without passing the
+    /// page) from any player's collection page. Permissionless, as it only drops stale
+    /// entries; remaining_accounts are the CardInstance PDAs of `card_mints`, in order.
+    pub fn prune_collection<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PruneCollection<'info>>,
+        card_mints: Vec<Pubkey>,
+    ) -> Result<()> {
+        let collection = &mut ctx.accounts.collection;
+        require!(ctx.remaining_accounts.len() == card_mints.len(), GameError::InvalidCollectionCards);
+        let mut removed = Vec::new();
+        
+        for (account_info, card_mint) in ctx.remaining_accounts.iter().zip(card_mints.iter()) {
+            let owned = card_owned_by(account_info, card_mint, collection.owner)?;
+            if !owned && collection.card_mints.contains(card_mint) {
+                remove_from_collection(collection, card_mint)?;
+                removed.push(*card_mint);
+            }
+        }
+        
+        msg!("Collection page {} of {}: -{} stale cards ({} total)", 
+            collection.page, collection.owner, removed.len(), collection.card_mints.len());
+        
+        emit!(CollectionSynced {
+            owner: collection.owner,
+            page: collection.page,
+            added: Vec::new(),
+            removed,
+        });
+        
+        Ok(())
     }
 
     // ========================================================================
@@ -504,6 +1562,12 @@ pub mod zoo_contract {
         
         msg!("Card listed: mint={}, price={} BUG", ctx.accounts.card_mint.key(), price);
         
+        emit!(ListingCreated {
+            seller: ctx.accounts.seller.key(),
+            card_mint: ctx.accounts.card_mint.key(),
+            price,
+        });
+        
         Ok(())
     }
     
@@ -549,6 +1613,11 @@ pub mod zoo_contract {
         
         msg!("Listing cancelled: mint={}", card_mint);
         
+        emit!(ListingCancelled {
+            seller: ctx.accounts.seller.key(),
+            card_mint,
+        });
+        
         Ok(())
     }
     
@@ -616,21 +1685,60 @@ pub mod zoo_contract {
         let card_instance = &mut ctx.accounts.card_instance;
         card_instance.owner = ctx.accounts.buyer.key();
         
+        // Move the card between the players' collection pages
+        if let Some(buyer_collection) = ctx.accounts.buyer_collection.as_mut() {
+            add_to_collection(buyer_collection, card_mint)?;
+        }
+        if let Some(seller_collection) = ctx.accounts.seller_collection.as_mut() {
+            remove_from_collection(seller_collection, &card_mint)?;
+        }
+        
         // listing 账户会被 close 约束自动关闭
         
         msg!("Card sold: mint={}, price={}, fee={}", card_mint, price, fee);
         
+        emit!(ListingFilled {
+            seller: listing.seller,
+            buyer: ctx.accounts.buyer.key(),
+            card_mint,
+            price,
+            fee,
+        });
+        
         Ok(())
     }
 
-    /// Record match result with win streak bonus
-    /// Trophy gain = BASE (30) + win_streak
-    /// Trophy loss = 30 (fixed), win_streak resets to 0
-    /// Winner receives 100 BUG tokens as reward
-    pub fn record_match_result(ctx: Context<RecordMatchResult>) -> Result<()> {
+    /// Record match result with win streak bonus (values from EconomyConfig)
+    /// Trophy gain = base_trophy_gain + win_streak (streak bonus optionally capped)
+    /// Trophy loss = trophy_loss, win_streak resets to 0
+    /// Winner receives win_reward BUG, loser receives loss_reward BUG (subject to daily cap)
+    /// Skill rating is updated with Elo (zero-sum, K = 32)
+    /// Cards of the decks played gain XP (values from ProgressionConfig): pass each
+    /// PlayerDeck in remaining_accounts, followed by a writable CardInstance and its
+    /// CardTemplate for every card of that deck that should gain XP (see award_deck_xp)
+    pub fn record_match_result<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RecordMatchResult<'info>>,
+    ) -> Result<()> {
+        let game_config = &ctx.accounts.game_config;
+        let economy_config = ctx.accounts.economy_config.as_deref().unwrap_or(&EconomyConfig::DEFAULTS);
+        let no_tiers = TierConfig::NONE;
+        let tier_config = ctx.accounts.tier_config.as_deref().unwrap_or(&no_tiers);
+        let clock = Clock::get()?;
         let winner_profile = &mut ctx.accounts.winner_profile;
         let loser_profile = &mut ctx.accounts.loser_profile;
         
+        // Apply pending season rollover (soft reset) before touching trophies
+        sync_player_season(winner_profile, game_config.current_season_id, game_config.season_trophy_floor);
+        sync_player_season(loser_profile, game_config.current_season_id, game_config.season_trophy_floor);
+        note_tier_reached(loser_profile, tier_config);
+        
+        // Update skill ratings before trophies (independent of streak bonus)
+        let rating_change = calculate_elo_change(winner_profile.rating, loser_profile.rating);
+        winner_profile.rating = winner_profile.rating
+            .checked_add(rating_change)
+            .ok_or(GameError::NumericalOverflow)?;
+        loser_profile.rating = loser_profile.rating.saturating_sub(rating_change);
+        
         // Increment winner's win streak first
         winner_profile.win_streak = winner_profile.win_streak
             .checked_add(1)
@@ -638,17 +1746,23 @@ pub mod zoo_contract {
         
         // Calculate trophy gain: base (30) + win_streak bonus
         // e.g., 1st win: 30+1=31, 2nd win: 30+2=32, 3rd win: 30+3=33...
-        let trophy_gain = PlayerProfile::BASE_TROPHY_GAIN
-            .checked_add(winner_profile.win_streak)
-            .ok_or(GameError::NumericalOverflow)?;
+        let trophy_gain = calculate_trophy_gain(economy_config, winner_profile.win_streak)?;
         
         // Increase winner trophies
         winner_profile.trophies = winner_profile.trophies
             .checked_add(trophy_gain)
             .ok_or(GameError::NumericalOverflow)?;
+        winner_profile.season_best_trophies = winner_profile.season_best_trophies
+            .max(winner_profile.trophies);
         
-        // Decrease loser trophies (fixed 30, clamped to 0)
-        loser_profile.trophies = loser_profile.trophies.saturating_sub(PlayerProfile::TROPHY_LOSS);
+        // Decrease loser trophies (clamped to 0, or to the floor of a tier reached this season)
+        let loser_trophies_before = loser_profile.trophies;
+        loser_profile.trophies = trophies_after_loss(
+            loser_trophies_before,
+            economy_config.trophy_loss,
+            tier_trophy_floor(tier_config, loser_profile.season_peak_tier),
+        );
+        let trophy_loss = loser_trophies_before.saturating_sub(loser_profile.trophies);
         
         // Reset loser's win streak
         loser_profile.win_streak = 0;
@@ -666,338 +1780,3593 @@ pub mod zoo_contract {
         msg!("Winner: {} | Trophies: {} (+{}) | Win Streak: {}", 
             winner_profile.wallet, winner_profile.trophies, trophy_gain, winner_profile.win_streak);
         msg!("Loser: {} | Trophies: {} (-{}) | Win Streak Reset", 
-            loser_profile.wallet, loser_profile.trophies, PlayerProfile::TROPHY_LOSS);
+            loser_profile.wallet, loser_profile.trophies, trophy_loss);
+        msg!("Rating: winner {} (+{}), loser {} (-{})", 
+            winner_profile.rating, rating_change, loser_profile.rating, rating_change);
         
-        // Add 100 BUG reward to winner's balance
-        winner_profile.bug_balance = winner_profile.bug_balance
-            .checked_add(PlayerProfile::WIN_REWARD)
-            .ok_or(GameError::NumericalOverflow)?;
+        // Pay match rewards, limited by the daily BUG cap
+        let today = unix_day(clock.unix_timestamp);
+        let winner_reward = grant_match_reward(winner_profile, economy_config.win_reward, economy_config, today)?;
+        let loser_reward = grant_match_reward(loser_profile, economy_config.loss_reward, economy_config, today)?;
+        
+        msg!("Reward: {} BUG. Winner balance: {}", winner_reward, winner_profile.bug_balance);
+        msg!("Reward: {} BUG. Loser balance: {}", loser_reward, loser_profile.bug_balance);
+        
+        for profile in [&mut **winner_profile, &mut **loser_profile] {
+            if let Some(tier_changed) = refresh_player_tier(profile, tier_config) {
+                msg!("Tier changed: {} {:?} -> {:?}", 
+                    profile.wallet, tier_changed.old_tier, tier_changed.new_tier);
+                emit!(tier_changed);
+            }
+        }
+        
+        if let Some(leaderboard) = ctx.accounts.leaderboard.as_mut() {
+            update_leaderboard(leaderboard, winner_profile);
+            update_leaderboard(leaderboard, loser_profile);
+        }
         
-        msg!("Reward: {} BUG. Winner balance: {}", PlayerProfile::WIN_REWARD, winner_profile.bug_balance);
+        emit!(MatchRecorded {
+            winner: winner_profile.wallet,
+            loser: loser_profile.wallet,
+            trophy_gain,
+            trophy_loss,
+            winner_trophies: winner_profile.trophies,
+            loser_trophies: loser_profile.trophies,
+            rating_change,
+            winner_reward,
+            loser_reward,
+        });
+        
+        if !ctx.remaining_accounts.is_empty() {
+            let progression_config = ctx.accounts.progression_config
+                .as_ref()
+                .ok_or(GameError::ProgressionNotConfigured)?;
+            award_deck_xp(
+                ctx.remaining_accounts,
+                progression_config,
+                winner_profile.wallet,
+                loser_profile.wallet,
+            )?;
+        }
         
         Ok(())
     }
-}
-
-// ============================================================================
-// State Structs (Account Structures)
-// ============================================================================
 
-#[account]
-pub struct GameConfig {
-    pub authority: Pubkey,              // Primary admin authority
-    pub card_creators: Vec<Pubkey>,     // Authorized card creators (max 10)
-    pub normal_pack_price: u64,         // Price in BUG tokens
-    pub starter_pack_card_count: u8,    // Fixed at 10
-    pub sol_to_bug_rate: u64,           // How many BUG tokens per 1 SOL (in lamports)
-    pub ticket_price: u64,              // Price of 1 gacha ticket in BUG tokens
-    pub bump: u8,                       // PDA bump seed
-}
+    /// Record a drawn match
+    /// Both players gain draw_trophy_delta trophies (0 = unchanged), win streaks are
+    /// left as they are, and Elo moves towards the expected 50/50 result
+    pub fn record_match_draw(ctx: Context<RecordMatchDraw>) -> Result<()> {
+        let game_config = &ctx.accounts.game_config;
+        let economy_config = ctx.accounts.economy_config.as_deref().unwrap_or(&EconomyConfig::DEFAULTS);
+        let no_tiers = TierConfig::NONE;
+        let tier_config = ctx.accounts.tier_config.as_deref().unwrap_or(&no_tiers);
+        let player_a_profile = &mut ctx.accounts.player_a_profile;
+        let player_b_profile = &mut ctx.accounts.player_b_profile;
+        
+        sync_player_season(player_a_profile, game_config.current_season_id, game_config.season_trophy_floor);
+        sync_player_season(player_b_profile, game_config.current_season_id, game_config.season_trophy_floor);
+        
+        // Positive change means player A was the underdog and gains rating
+        let rating_change = calculate_elo_draw_change(player_a_profile.rating, player_b_profile.rating);
+        player_a_profile.rating = apply_rating_change(player_a_profile.rating, rating_change)?;
+        player_b_profile.rating = apply_rating_change(player_b_profile.rating, -rating_change)?;
+        
+        for profile in [&mut **player_a_profile, &mut **player_b_profile] {
+            profile.trophies = profile.trophies
+                .checked_add(economy_config.draw_trophy_delta)
+                .ok_or(GameError::NumericalOverflow)?;
+            profile.season_best_trophies = profile.season_best_trophies.max(profile.trophies);
+            profile.total_draws = profile.total_draws
+                .checked_add(1)
+                .ok_or(GameError::NumericalOverflow)?;
+            
+            if let Some(tier_changed) = refresh_player_tier(profile, tier_config) {
+                msg!("Tier changed: {} {:?} -> {:?}", 
+                    profile.wallet, tier_changed.old_tier, tier_changed.new_tier);
+                emit!(tier_changed);
+            }
+        }
+        
+        msg!("Match draw recorded:");
+        msg!("Player A: {} | Trophies: {} | Rating: {}", 
+            player_a_profile.wallet, player_a_profile.trophies, player_a_profile.rating);
+        msg!("Player B: {} | Trophies: {} | Rating: {}", 
+            player_b_profile.wallet, player_b_profile.trophies, player_b_profile.rating);
+        
+        if let Some(leaderboard) = ctx.accounts.leaderboard.as_mut() {
+            update_leaderboard(leaderboard, player_a_profile);
+            update_leaderboard(leaderboard, player_b_profile);
+        }
+        
+        emit!(MatchDrawn {
+            player_a: player_a_profile.wallet,
+            player_b: player_b_profile.wallet,
+            trophy_delta: economy_config.draw_trophy_delta,
+            player_a_trophies: player_a_profile.trophies,
+            player_b_trophies: player_b_profile.trophies,
+            rating_change,
+        });
+        
+        Ok(())
+    }
 
-impl GameConfig {
-    pub const MAX_CARD_CREATORS: usize = 10;
+    // ========================================================================
+    // Leaderboard Functions
+    // ========================================================================
     
-    // Calculate space needed for account
-    // 8 (discriminator) + 32 (authority) + 4 + (32 * 10) (card_creators vec) 
-    // + 8 (normal_pack_price) + 1 (starter_pack_card_count) 
-    // + 8 (sol_to_bug_rate) + 8 (ticket_price) + 1 (bump)
-    pub const LEN: usize = 8 + 32 + 4 + (32 * 10) + 8 + 1 + 8 + 8 + 1;
-}
-
-#[account]
-pub struct CardTemplate {
-    pub card_type_id: u32,
-    pub name: String,                   // Max 32 chars
-    pub trait_type: TraitType,
-    pub rarity: Rarity,
-    pub min_attack: u16,                // Minimum attack value
-    pub max_attack: u16,                // Maximum attack value
-    pub min_health: u16,                // Minimum health value
-    pub max_health: u16,                // Maximum health value
-    pub description: String,            // Max 200 chars
-    pub image_uri: String,              // Max 200 chars (IPFS URI)
-    pub bump: u8,
-}
-
-impl CardTemplate {
-    pub const MAX_NAME_LEN: usize = 32;
-    pub const MAX_DESCRIPTION_LEN: usize = 200;
-    pub const MAX_IMAGE_URI_LEN: usize = 200;
+    /// Create the on-chain top-100 leaderboard (admin function)
+    pub fn initialize_leaderboard(ctx: Context<InitializeLeaderboard>) -> Result<()> {
+        let leaderboard = &mut ctx.accounts.leaderboard;
+        leaderboard.entries = Vec::new();
+        leaderboard.bump = ctx.bumps.leaderboard;
+        
+        msg!("Leaderboard initialized (top {})", Leaderboard::MAX_ENTRIES);
+        
+        emit!(ConfigUpdated {
+            authority: ctx.accounts.authority.key(),
+            kind: ConfigKind::Leaderboard,
+        });
+        
+        Ok(())
+    }
     
-    // Calculate space needed for account
-    // 8 (discriminator) + 4 (card_type_id) + 4 + 32 (name) + 1 (trait_type) + 1 (rarity)
-    // + 2 (min_attack) + 2 (max_attack) + 2 (min_health) + 2 (max_health) 
-    // + 4 + 200 (description) + 4 + 200 (image_uri) + 1 (bump)
-    pub const LEN: usize = 8 + 4 + 4 + 32 + 1 + 1 + 2 + 2 + 2 + 2 + 4 + 200 + 4 + 200 + 1;
-}
+    /// Re-rank the leaderboard from fresh PlayerProfile data (permissionless)
+    /// Pass PlayerProfile accounts in remaining_accounts; their entries are
+    /// refreshed (with any pending season soft reset applied) and the list re-sorted.
+    /// Used to repair entries that went stale, e.g. after a season rollover.
+    pub fn rerank_leaderboard<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RerankLeaderboard<'info>>,
+    ) -> Result<()> {
+        let game_config = &ctx.accounts.game_config;
+        let leaderboard = &mut ctx.accounts.leaderboard;
+        
+        for account_info in ctx.remaining_accounts.iter() {
+            let mut profile: PlayerProfile = Account::<PlayerProfile>::try_from(account_info)?
+                .into_inner();
+            sync_player_season(&mut profile, game_config.current_season_id, game_config.season_trophy_floor);
+            update_leaderboard(leaderboard, &profile);
+        }
+        
+        msg!("Leaderboard re-ranked with {} profiles", ctx.remaining_accounts.len());
+        msg!("Entries: {}", leaderboard.entries.len());
+        
+        emit!(LeaderboardReranked {
+            profiles: ctx.remaining_accounts.len() as u32,
+            entries: leaderboard.entries.len() as u32,
+        });
+        
+        Ok(())
+    }
 
-#[account]
+    // ========================================================================
+    // Tournament Functions
+    // ========================================================================
+    
+    /// Create a single-elimination tournament (admin function)
+    /// payout_bps: prize share per placement in basis points (1st, 2nd, ...), must sum to 10000
+    pub fn create_tournament(
+        ctx: Context<CreateTournament>,
+        tournament_id: u64,
+        name: String,
+        entry_currency: EntryCurrency,
+        entry_fee: u64,
+        max_entrants: u8,
+        payout_bps: Vec<u16>,
+    ) -> Result<()> {
+        validate_non_empty_string(&name)?;
+        validate_string_length(&name, Tournament::MAX_NAME_LEN)?;
+        require!(
+            max_entrants >= 2 && max_entrants as usize <= Tournament::MAX_ENTRANTS,
+            GameError::InvalidTournamentSize
+        );
+        validate_payout_bps(&payout_bps)?;
+        
+        let tournament = &mut ctx.accounts.tournament;
+        tournament.tournament_id = tournament_id;
+        tournament.name = name.clone();
+        tournament.entry_currency = entry_currency;
+        tournament.entry_fee = entry_fee;
+        tournament.max_entrants = max_entrants;
+        tournament.payout_bps = payout_bps;
+        tournament.status = TournamentStatus::Registration;
+        tournament.entrants = Vec::new();
+        tournament.round = 0;
+        tournament.current_round = Vec::new();
+        tournament.next_round = Vec::new();
+        tournament.eliminated = Vec::new();
+        tournament.prize_pool = 0;
+        tournament.prizes_paid = false;
+        tournament.created_at = Clock::get()?.unix_timestamp;
+        tournament.bump = ctx.bumps.tournament;
+        
+        let vault = &mut ctx.accounts.tournament_vault;
+        vault.tournament = tournament.key();
+        vault.bug_balance = 0;
+        vault.ticket_balance = 0;
+        vault.bump = ctx.bumps.tournament_vault;
+        
+        msg!("Tournament created: {} (ID: {})", name, tournament_id);
+        msg!("Entry fee: {} {:?}, max entrants: {}", entry_fee, entry_currency, max_entrants);
+        
+        emit!(TournamentCreated {
+            tournament_id,
+            name,
+            entry_currency,
+            entry_fee,
+            max_entrants,
+        });
+        
+        Ok(())
+    }
+    
+    /// Join a tournament during registration; the entry fee is escrowed in the vault
+    pub fn join_tournament(ctx: Context<JoinTournament>) -> Result<()> {
+        let tournament = &mut ctx.accounts.tournament;
+        let vault = &mut ctx.accounts.tournament_vault;
+        let player_profile = &mut ctx.accounts.player_profile;
+        let player = ctx.accounts.player.key();
+        
+        require!(
+            tournament.status == TournamentStatus::Registration,
+            GameError::TournamentNotOpen
+        );
+        require!(
+            tournament.entrants.len() < tournament.max_entrants as usize,
+            GameError::TournamentFull
+        );
+        require!(!tournament.entrants.contains(&player), GameError::AlreadyJoined);
+        
+        let entry_fee = tournament.entry_fee;
+        match tournament.entry_currency {
+            EntryCurrency::Bug => {
+                require!(player_profile.bug_balance >= entry_fee, GameError::InsufficientBalance);
+                player_profile.bug_balance = player_profile.bug_balance
+                    .checked_sub(entry_fee)
+                    .ok_or(GameError::NumericalOverflow)?;
+                vault.bug_balance = vault.bug_balance
+                    .checked_add(entry_fee)
+                    .ok_or(GameError::NumericalOverflow)?;
+            }
+            EntryCurrency::Tickets => {
+                require!(player_profile.gacha_tickets >= entry_fee, GameError::InsufficientTickets);
+                player_profile.gacha_tickets = player_profile.gacha_tickets
+                    .checked_sub(entry_fee)
+                    .ok_or(GameError::NumericalOverflow)?;
+                vault.ticket_balance = vault.ticket_balance
+                    .checked_add(entry_fee)
+                    .ok_or(GameError::NumericalOverflow)?;
+            }
+        }
+        
+        tournament.prize_pool = tournament.prize_pool
+            .checked_add(entry_fee)
+            .ok_or(GameError::NumericalOverflow)?;
+        tournament.entrants.push(player);
+        
+        msg!("Player {} joined tournament {}", player, tournament.tournament_id);
+        msg!("Entrants: {}/{}, prize pool: {}", 
+            tournament.entrants.len(), tournament.max_entrants, tournament.prize_pool);
+        
+        emit!(TournamentJoined {
+            tournament_id: tournament.tournament_id,
+            player,
+            entrants: tournament.entrants.len() as u8,
+            prize_pool: tournament.prize_pool,
+        });
+        
+        Ok(())
+    }
+    
+    /// Close registration and seed the bracket in random order (admin function)
+    pub fn start_tournament(ctx: Context<StartTournament>) -> Result<()> {
+        let tournament = &mut ctx.accounts.tournament;
+        let clock = Clock::get()?;
+        
+        require!(
+            tournament.status == TournamentStatus::Registration,
+            GameError::TournamentNotOpen
+        );
+        require!(tournament.entrants.len() >= 2, GameError::NotEnoughEntrants);
+        
+        let random_value = generate_random_u64(&clock, &tournament.key(), tournament.tournament_id);
+        let seeded = seed_bracket(&tournament.entrants, random_value);
+        
+        tournament.status = TournamentStatus::InProgress;
+        tournament.round = 1;
+        tournament.begin_round(seeded);
+        
+        msg!("Tournament {} started with {} entrants", 
+            tournament.tournament_id, tournament.entrants.len());
+        
+        emit!(TournamentStarted {
+            tournament_id: tournament.tournament_id,
+            bracket: tournament.current_round.clone(),
+        });
+        
+        Ok(())
+    }
+    
+    /// Report the winner of a bracket match
+    /// Must be signed by the authority, or co-signed by both players of the match.
+    /// match_index: 0-based match in the current round (players 2i and 2i+1)
+    pub fn report_tournament_match(
+        ctx: Context<ReportTournamentMatch>,
+        match_index: u8,
+        winner: Pubkey,
+    ) -> Result<()> {
+        let game_config = &ctx.accounts.game_config;
+        let tournament = &mut ctx.accounts.tournament;
+        
+        require!(
+            tournament.status == TournamentStatus::InProgress,
+            GameError::TournamentNotInProgress
+        );
+        
+        let (player_a, player_b) = tournament.match_players(match_index)?;
+        
+        let reporter = ctx.accounts.reporter.key();
+        let co_signer = ctx.accounts.co_signer.as_ref().map(|signer| signer.key());
+        let is_authority = reporter == game_config.authority;
+        let is_co_signed = match co_signer {
+            Some(co_signer) => {
+                (reporter == player_a && co_signer == player_b)
+                    || (reporter == player_b && co_signer == player_a)
+            }
+            None => false,
+        };
+        require!(is_authority || is_co_signed, GameError::Unauthorized);
+        
+        let round = tournament.round;
+        tournament.record_match_winner(match_index, winner)?;
+        let loser = if winner == player_a { player_b } else { player_a };
+        
+        msg!("Tournament {} round {} match {}: winner {}", 
+            tournament.tournament_id, tournament.round, match_index, winner);
+        if tournament.status == TournamentStatus::Completed {
+            msg!("Tournament {} completed. Champion: {}", tournament.tournament_id, winner);
+        }
+        
+        emit!(TournamentMatchReported {
+            tournament_id: tournament.tournament_id,
+            round,
+            match_index,
+            winner,
+            loser,
+            completed: tournament.status == TournamentStatus::Completed,
+        });
+        
+        Ok(())
+    }
+    
+    /// Pay out the prize pool by placement (permissionless once completed)
+    /// Pass the PlayerProfile accounts of the paid placements in remaining_accounts,
+    /// in placement order (1st, 2nd, ...)
+    pub fn distribute_tournament_prizes<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DistributeTournamentPrizes<'info>>,
+    ) -> Result<()> {
+        let tournament = &mut ctx.accounts.tournament;
+        let vault = &mut ctx.accounts.tournament_vault;
+        
+        require!(
+            tournament.status == TournamentStatus::Completed,
+            GameError::TournamentNotCompleted
+        );
+        require!(!tournament.prizes_paid, GameError::PrizesAlreadyPaid);
+        
+        let placements = tournament.placements();
+        let payouts = calculate_tournament_payouts(
+            tournament.prize_pool,
+            &tournament.payout_bps,
+            placements.len(),
+        )?;
+        require!(
+            ctx.remaining_accounts.len() == payouts.len(),
+            GameError::InvalidPlacementAccount
+        );
+        
+        for (place, (account_info, payout)) in ctx.remaining_accounts.iter().zip(payouts.iter()).enumerate() {
+            let mut profile = Account::<PlayerProfile>::try_from(account_info)?;
+            require!(profile.wallet == placements[place], GameError::InvalidPlacementAccount);
+            
+            match tournament.entry_currency {
+                EntryCurrency::Bug => {
+                    vault.bug_balance = vault.bug_balance
+                        .checked_sub(*payout)
+                        .ok_or(GameError::NumericalOverflow)?;
+                    profile.bug_balance = profile.bug_balance
+                        .checked_add(*payout)
+                        .ok_or(GameError::NumericalOverflow)?;
+                }
+                EntryCurrency::Tickets => {
+                    vault.ticket_balance = vault.ticket_balance
+                        .checked_sub(*payout)
+                        .ok_or(GameError::NumericalOverflow)?;
+                    profile.gacha_tickets = profile.gacha_tickets
+                        .checked_add(*payout)
+                        .ok_or(GameError::NumericalOverflow)?;
+                }
+            }
+            profile.exit(ctx.program_id)?;
+            
+            msg!("Place {}: {} receives {} {:?}", place + 1, profile.wallet, payout, tournament.entry_currency);
+            emit!(TournamentPrizePaid {
+                tournament_id: tournament.tournament_id,
+                place: (place + 1) as u8,
+                wallet: profile.wallet,
+                amount: *payout,
+                currency: tournament.entry_currency,
+            });
+        }
+        
+        tournament.prizes_paid = true;
+        
+        Ok(())
+    }
+    
+    /// Cancel a tournament still in registration and refund every entry fee (admin function)
+    /// Pass the entrants' PlayerProfile accounts in remaining_accounts, in registration order
+    pub fn cancel_tournament<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CancelTournament<'info>>,
+    ) -> Result<()> {
+        let tournament = &mut ctx.accounts.tournament;
+        let vault = &mut ctx.accounts.tournament_vault;
+        
+        let entrants = tournament.cancel()?;
+        require!(
+            ctx.remaining_accounts.len() == entrants.len(),
+            GameError::InvalidEntrantAccount
+        );
+        
+        let entry_fee = tournament.entry_fee;
+        for (account_info, entrant) in ctx.remaining_accounts.iter().zip(entrants.iter()) {
+            let mut profile = Account::<PlayerProfile>::try_from(account_info)?;
+            require!(profile.wallet == *entrant, GameError::InvalidEntrantAccount);
+            
+            match tournament.entry_currency {
+                EntryCurrency::Bug => {
+                    vault.bug_balance = vault.bug_balance
+                        .checked_sub(entry_fee)
+                        .ok_or(GameError::NumericalOverflow)?;
+                    profile.bug_balance = profile.bug_balance
+                        .checked_add(entry_fee)
+                        .ok_or(GameError::NumericalOverflow)?;
+                }
+                EntryCurrency::Tickets => {
+                    vault.ticket_balance = vault.ticket_balance
+                        .checked_sub(entry_fee)
+                        .ok_or(GameError::NumericalOverflow)?;
+                    profile.gacha_tickets = profile.gacha_tickets
+                        .checked_add(entry_fee)
+                        .ok_or(GameError::NumericalOverflow)?;
+                }
+            }
+            profile.exit(ctx.program_id)?;
+        }
+        
+        msg!("Tournament {} cancelled, {} entrants refunded {} {:?}", 
+            tournament.tournament_id, entrants.len(), entry_fee, tournament.entry_currency);
+        
+        emit!(TournamentCancelled {
+            tournament_id: tournament.tournament_id,
+            refunded: entrants.len() as u8,
+            entry_fee,
+            currency: tournament.entry_currency,
+        });
+        
+        Ok(())
+    }
+
+    // ========================================================================
+    // Season Functions
+    // ========================================================================
+    
+    /// Roll over to a new season (admin function)
+    /// Ends the current season (early if needed) and starts `season_id` = current + 1.
+    /// Trophies are soft-reset lazily: the next time a player's profile is touched,
+    /// anything above `trophy_floor` is halved.
+    pub fn roll_over_season(
+        ctx: Context<RollOverSeason>,
+        season_id: u32,
+        end_time: i64,
+        trophy_floor: u32,
+        reward_tiers: Vec<SeasonRewardTier>,
+    ) -> Result<()> {
+        let game_config = &mut ctx.accounts.game_config;
+        let clock = Clock::get()?;
+        
+        require!(
+            season_id == game_config.current_season_id.checked_add(1).ok_or(GameError::NumericalOverflow)?,
+            GameError::InvalidSeason
+        );
+        require!(end_time > clock.unix_timestamp, GameError::InvalidSeasonEndTime);
+        validate_season_reward_tiers(&reward_tiers)?;
+        
+        // Close out the previous season (season 0 is the pre-season and has no account)
+        if game_config.current_season_id > 0 {
+            let previous_season = ctx.accounts.previous_season
+                .as_mut()
+                .ok_or(GameError::InvalidSeason)?;
+            require!(
+                previous_season.season_id == game_config.current_season_id,
+                GameError::InvalidSeason
+            );
+            if previous_season.end_time > clock.unix_timestamp {
+                previous_season.end_time = clock.unix_timestamp;
+            }
+        }
+        
+        let season = &mut ctx.accounts.season;
+        season.season_id = season_id;
+        season.start_time = clock.unix_timestamp;
+        season.end_time = end_time;
+        season.trophy_floor = trophy_floor;
+        season.reward_tiers = reward_tiers;
+        season.bump = ctx.bumps.season;
+        
+        game_config.current_season_id = season_id;
+        game_config.season_trophy_floor = trophy_floor;
+        
+        msg!("Season {} started, ends at {}", season_id, end_time);
+        msg!("Trophy floor: {}, reward tiers: {}", trophy_floor, season.reward_tiers.len());
+        
+        emit!(SeasonStarted {
+            season_id,
+            start_time: season.start_time,
+            end_time,
+            trophy_floor,
+        });
+        
+        Ok(())
+    }
+    
+    /// Claim end-of-season rewards based on the player's final trophies
+    /// Creates a PlayerSeasonRecord archiving final/best trophies (one claim per season).
+    /// Results wait on the profile for up to MAX_UNCLAIMED_SEASONS later rollovers.
+    pub fn claim_season_rewards(ctx: Context<ClaimSeasonRewards>, season_id: u32) -> Result<()> {
+        let game_config = &ctx.accounts.game_config;
+        let season = &ctx.accounts.season;
+        let player_profile = &mut ctx.accounts.player_profile;
+        
+        // Season must have been rolled over so final trophies are settled
+        require!(season_id < game_config.current_season_id, GameError::SeasonNotEnded);
+        
+        sync_player_season(player_profile, game_config.current_season_id, game_config.season_trophy_floor);
+        let result = take_season_result(player_profile, season_id)?;
+        
+        let final_trophies = result.final_trophies;
+        let (bug_reward, ticket_reward) = match find_season_reward(&season.reward_tiers, final_trophies) {
+            Some(tier) => (tier.bug_reward, tier.ticket_reward),
+            None => (0, 0),
+        };
+        
+        player_profile.bug_balance = player_profile.bug_balance
+            .checked_add(bug_reward)
+            .ok_or(GameError::NumericalOverflow)?;
+        player_profile.gacha_tickets = player_profile.gacha_tickets
+            .checked_add(ticket_reward)
+            .ok_or(GameError::NumericalOverflow)?;
+        
+        let season_record = &mut ctx.accounts.season_record;
+        season_record.wallet = player_profile.wallet;
+        season_record.season_id = season_id;
+        season_record.final_trophies = final_trophies;
+        season_record.best_trophies = result.best_trophies;
+        season_record.bug_reward = bug_reward;
+        season_record.ticket_reward = ticket_reward;
+        season_record.bump = ctx.bumps.season_record;
+        
+        msg!("Season {} rewards claimed by {}", season_id, player_profile.wallet);
+        msg!("Final trophies: {}, best: {}", final_trophies, season_record.best_trophies);
+        msg!("Reward: {} BUG, {} tickets", bug_reward, ticket_reward);
+        
+        emit!(SeasonRewardsClaimed {
+            wallet: player_profile.wallet,
+            season_id,
+            final_trophies,
+            best_trophies: season_record.best_trophies,
+            bug_reward,
+            ticket_reward,
+        });
+        
+        Ok(())
+    }
+    
+    // ========================================================================
+    // Banner Functions
+    // ========================================================================
+    
+    /// Create a limited-time gacha banner (admin function)
+    /// entries: the banner's own drop table (any rarity, max 20), weighted like rarity pools.
+    /// Pass the CardTemplate account of every entry in remaining_accounts, in the same order.
+    /// pull_limit: max pulls per player, 0 = unlimited
+    /// set_id: when not 0, every card must belong to that card set
+    pub fn create_banner<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CreateBanner<'info>>,
+        banner_id: u32,
+        name: String,
+        start_time: i64,
+        end_time: i64,
+        entries: Vec<PoolEntry>,
+        currency: EntryCurrency,
+        price: u64,
+        pull_limit: u32,
+        set_id: u16,
+    ) -> Result<()> {
+        validate_non_empty_string(&name)?;
+        validate_string_length(&name, Banner::MAX_NAME_LEN)?;
+        require!(end_time > start_time, GameError::InvalidBannerWindow);
+        require!(end_time > Clock::get()?.unix_timestamp, GameError::InvalidBannerWindow);
+        require!(price > 0, GameError::InvalidPrice);
+        
+        let templates = load_card_templates(ctx.remaining_accounts, entries.len())?;
+        validate_banner_entries(&entries, &templates)?;
+        if set_id != 0 {
+            validate_set_templates(set_id, &templates)?;
+        }
+        
+        let banner = &mut ctx.accounts.banner;
+        banner.banner_id = banner_id;
+        banner.name = name.clone();
+        banner.start_time = start_time;
+        banner.end_time = end_time;
+        banner.entries = entries;
+        banner.currency = currency;
+        banner.price = price;
+        banner.pull_limit = pull_limit;
+        banner.total_pulls = 0;
+        banner.bump = ctx.bumps.banner;
+        banner.set_id = set_id;
+        
+        msg!("Banner created: {} (ID: {})", name, banner_id);
+        msg!("Runs {} - {}, {} cards, {} {:?} per pull", 
+            start_time, end_time, banner.entries.len(), price, currency);
+        
+        emit!(BannerCreated {
+            banner_id,
+            name,
+            start_time,
+            end_time,
+            currency,
+            price,
+            pull_limit,
+        });
+        
+        Ok(())
+    }
+    
+    /// End a banner now instead of at its scheduled end time (admin function)
+    pub fn end_banner(ctx: Context<EndBanner>) -> Result<()> {
+        let banner = &mut ctx.accounts.banner;
+        let now = Clock::get()?.unix_timestamp;
+        
+        if banner.end_time > now {
+            banner.end_time = now;
+        }
+        
+        msg!("Banner {} ended at {}", banner.banner_id, banner.end_time);
+        
+        emit!(BannerEnded {
+            banner_id: banner.banner_id,
+            end_time: banner.end_time,
+        });
+        
+        Ok(())
+    }
+    
+    /// Pay for a single pull on an active banner, counted towards the banner's pull
+    /// limit. Like gacha_draw this records a pending pull: reveal_banner_pull rolls the
+    /// card from the banner's drop table in a later slot and claim_pulled_cards mints it.
+    pub fn banner_draw(ctx: Context<BannerDraw>) -> Result<()> {
+        let banner = &mut ctx.accounts.banner;
+        let banner_pulls = &mut ctx.accounts.banner_pulls;
+        let player_profile = &mut ctx.accounts.player_profile;
+        let player = &ctx.accounts.player;
+        let clock = Clock::get()?;
+        
+        require!(is_banner_active(banner, clock.unix_timestamp), GameError::BannerNotActive);
+        
+        // First pull on this banner: set up the player's record
+        if banner_pulls.wallet == Pubkey::default() {
+            banner_pulls.banner = banner.key();
+            banner_pulls.wallet = player.key();
+            banner_pulls.pulls = 0;
+            banner_pulls.bump = ctx.bumps.banner_pulls;
+        }
+        require!(
+            banner.pull_limit == 0 || banner_pulls.pulls < banner.pull_limit,
+            GameError::BannerPullLimitReached
+        );
+        
+        // Pay for the pull
+        match banner.currency {
+            EntryCurrency::Bug => {
+                require!(player_profile.bug_balance >= banner.price, GameError::InsufficientBalance);
+                player_profile.bug_balance = player_profile.bug_balance
+                    .checked_sub(banner.price)
+                    .ok_or(GameError::NumericalOverflow)?;
+            }
+            EntryCurrency::Tickets => {
+                require!(player_profile.gacha_tickets >= banner.price, GameError::InsufficientTickets);
+                player_profile.gacha_tickets = player_profile.gacha_tickets
+                    .checked_sub(banner.price)
+                    .ok_or(GameError::NumericalOverflow)?;
+            }
+        }
+        
+        banner_pulls.pulls = banner_pulls.pulls
+            .checked_add(1)
+            .ok_or(GameError::NumericalOverflow)?;
+        banner.total_pulls = banner.total_pulls
+            .checked_add(1)
+            .ok_or(GameError::NumericalOverflow)?;
+        
+        let source = PullSource::Banner { banner_id: banner.banner_id, pulls: banner_pulls.pulls };
+        ctx.accounts.pending_pull.set_inner(PendingPull::new(
+            player.key(),
+            source,
+            clock.slot,
+            1,
+            ctx.bumps.pending_pull,
+        ));
+        
+        msg!("Banner {} pull {} by {}", banner.banner_id, banner_pulls.pulls, player.key());
+        
+        emit!(PullCommitted {
+            wallet: player.key(),
+            source,
+            card_count: 1,
+            commit_slot: clock.slot,
+        });
+        
+        Ok(())
+    }
+    
+    /// Roll the card of a banner pull from the hash of the slot it was paid in
+    /// (see reveal_gacha_pull). The templates of the banner's entries follow in
+    /// remaining_accounts, in entry order: once that hash has aged out the pull gets
+    /// the most common card of the banner's lowest rarity.
+    pub fn reveal_banner_pull<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RevealBannerPull<'info>>,
+    ) -> Result<u32> {
+        let pending_pull = &mut ctx.accounts.pending_pull;
+        let banner = &ctx.accounts.banner;
+        let PullSource::Banner { banner_id, .. } = pending_pull.source else {
+            return Err(GameError::InvalidPullSource.into());
+        };
+        require!(banner_id == banner.banner_id, GameError::InvalidPullSource);
+        
+        let (seed, expired) = pull_seed(
+            pending_pull,
+            &ctx.accounts.slot_hashes.try_borrow_data()?,
+            Clock::get()?.slot,
+        )?;
+        let templates = load_card_templates(ctx.remaining_accounts, banner.entries.len())?;
+        let card_type_id = roll_banner_card(&banner.entries, &templates, pull_card_random(&seed, 0, false), expired)?;
+        
+        pending_pull.reveal(vec![card_type_id], seed, expired);
+        
+        msg!("Rolled on banner {}: Card ID {}", banner_id, card_type_id);
+        
+        emit!(PullRevealed {
+            wallet: pending_pull.player,
+            source: pending_pull.source,
+            card_type_ids: vec![card_type_id],
+        });
+        
+        Ok(card_type_id)
+    }
+    
+    // ========================================================================
+    // Migration Functions
+    // ========================================================================
+    
+    /// Grow a CardInstance minted before card XP to the current layout, with xp
+    /// and level 0. Anyone may pay the extra rent; current accounts are left as they are.
+    pub fn migrate_card_instance(ctx: Context<MigrateCardInstance>) -> Result<()> {
+        let card_instance = ctx.accounts.card_instance.to_account_info();
+        let grown = grow_account(
+            &card_instance,
+            <CardInstance as anchor_lang::Discriminator>::DISCRIMINATOR,
+            CardInstance::LEN,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+        )?;
+        
+        msg!("Card instance {}: {}", card_instance.key(), if grown { "migrated" } else { "already current" });
+        
+        Ok(())
+    }
+    
+    /// Grow a PlayerProfile registered before ratings, seasons, tiers, crafting and
+    /// collections to the current layout. The new fields start as for a fresh
+    /// registration, with the trophies it has counted towards the current season.
+    pub fn migrate_player_profile(ctx: Context<MigratePlayerProfile>) -> Result<()> {
+        let player_profile = ctx.accounts.player_profile.to_account_info();
+        let grown = grow_account(
+            &player_profile,
+            <PlayerProfile as anchor_lang::Discriminator>::DISCRIMINATOR,
+            PlayerProfile::LEN,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+        )?;
+        
+        if grown {
+            let mut data = player_profile.try_borrow_mut_data()?;
+            let mut profile = PlayerProfile::try_deserialize(&mut &data[..])?;
+            init_migrated_profile(&mut profile, ctx.accounts.game_config.current_season_id);
+            profile.try_serialize(&mut &mut data[..])?;
+        }
+        
+        msg!("Player profile {}: {}", player_profile.key(), if grown { "migrated" } else { "already current" });
+        
+        Ok(())
+    }
+    
+    /// Convert a standard rarity pool from the original layout (a list of card ids,
+    /// all equally likely) to weighted entries at DEFAULT_WEIGHT (admin function)
+    pub fn migrate_rarity_pool(ctx: Context<MigrateRarityPool>, _rarity_discriminant: u8) -> Result<()> {
+        let rarity_pool = ctx.accounts.rarity_pool.to_account_info();
+        let old_len = rarity_pool.data_len();
+        if old_len >= RarityPool::LEN {
+            msg!("Rarity pool {}: already current", rarity_pool.key());
+            return Ok(());
+        }
+        require!(old_len == LegacyRarityPool::LEN, GameError::InvalidMigrationAccount);
+        
+        grow_account(
+            &rarity_pool,
+            <RarityPool as anchor_lang::Discriminator>::DISCRIMINATOR,
+            RarityPool::LEN,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+        )?;
+        
+        let mut data = rarity_pool.try_borrow_mut_data()?;
+        let legacy = LegacyRarityPool::deserialize(&mut &data[8..])?;
+        let card_count = legacy.card_type_ids.len();
+        legacy.into_pool().try_serialize(&mut &mut data[..])?;
+        
+        msg!("Rarity pool {}: migrated {} cards", rarity_pool.key(), card_count);
+        
+        emit!(ConfigUpdated {
+            authority: ctx.accounts.authority.key(),
+            kind: ConfigKind::RarityPool,
+        });
+        
+        Ok(())
+    }
+    
+    /// Grow a GameConfig created before seasons to the current layout, in
+    /// pre-season (season 0, no trophy floor). Run before the other migrations,
+    /// which read the current season from it (admin function).
+    pub fn migrate_game_config(ctx: Context<MigrateGameConfig>) -> Result<()> {
+        let game_config = ctx.accounts.game_config.to_account_info();
+        {
+            // The authority is the first field in every layout
+            let data = game_config.try_borrow_data()?;
+            require!(data.len() >= 40, GameError::InvalidMigrationAccount);
+            require!(data[8..40] == ctx.accounts.authority.key().to_bytes(), GameError::Unauthorized);
+        }
+        
+        let grown = grow_account(
+            &game_config,
+            <GameConfig as anchor_lang::Discriminator>::DISCRIMINATOR,
+            GameConfig::LEN,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+        )?;
+        
+        msg!("Game config: {}", if grown { "migrated" } else { "already current" });
+        
+        if grown {
+            emit!(ConfigUpdated {
+                authority: ctx.accounts.authority.key(),
+                kind: ConfigKind::Game,
+            });
+        }
+        
+        Ok(())
+    }
+    
+    /// Grow a CardTemplate created before creators, versions, retirement, card sets
+    /// and abilities to the current layout: a base game card at version 1, owned by
+    /// the authority, not retired and without an ability. Anyone may pay the extra rent.
+    pub fn migrate_card_template(ctx: Context<MigrateCardTemplate>) -> Result<()> {
+        let card_template = ctx.accounts.card_template.to_account_info();
+        let grown = grow_account(
+            &card_template,
+            <CardTemplate as anchor_lang::Discriminator>::DISCRIMINATOR,
+            CardTemplate::LEN,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+        )?;
+        
+        if grown {
+            let mut data = card_template.try_borrow_mut_data()?;
+            let mut template = CardTemplate::try_deserialize(&mut &data[..])?;
+            init_migrated_template(&mut template, ctx.accounts.game_config.authority);
+            template.try_serialize(&mut &mut data[..])?;
+        }
+        
+        msg!("Card template {}: {}", card_template.key(), if grown { "migrated" } else { "already current" });
+        
+        Ok(())
+    }
+}
+
+// ============================================================================
+// State Structs (Account Structures)
+// ============================================================================
+
+#[account]
+pub struct GameConfig {
+    pub authority: Pubkey,              // Primary admin authority
+    pub card_creators: Vec<Pubkey>,     // Authorized card creators (max 10)
+    pub normal_pack_price: u64,         // Price in BUG tokens
+    pub starter_pack_card_count: u8,    // Fixed at 10
+    pub sol_to_bug_rate: u64,           // How many BUG tokens per 1 SOL (in lamports)
+    pub ticket_price: u64,              // Price of 1 gacha ticket in BUG tokens
+    pub bump: u8,                       // PDA bump seed
+    pub current_season_id: u32,         // 0 = pre-season (no Season account yet)
+    pub season_trophy_floor: u32,       // Soft reset floor of the current season
+}
+
+impl GameConfig {
+    pub const MAX_CARD_CREATORS: usize = 10;
+    
+    // Calculate space needed for account
+    // 8 (discriminator) + 32 (authority) + 4 + (32 * 10) (card_creators vec) 
+    // + 8 (normal_pack_price) + 1 (starter_pack_card_count) 
+    // + 8 (sol_to_bug_rate) + 8 (ticket_price) + 1 (bump)
+    // + 4 (current_season_id) + 4 (season_trophy_floor)
+    pub const LEN: usize = 8 + 32 + 4 + (32 * 10) + 8 + 1 + 8 + 8 + 1 + 4 + 4;
+}
+
+/// Trophy and reward economy, tunable without a redeploy
+#[account]
+pub struct EconomyConfig {
+    pub base_trophy_gain: u32,          // Base trophy gain per win (default 30)
+    pub trophy_loss: u32,               // Trophy loss per loss (default 30)
+    pub max_streak_bonus: u32,          // Cap on win streak bonus trophies (0 = uncapped)
+    pub win_reward: u64,                // BUG reward per win (default 100)
+    pub loss_reward: u64,               // BUG reward per loss (default 0)
+    pub daily_reward_cap: u64,          // Max BUG per player per day from wins and losses (0 = no cap)
+    pub bump: u8,
+    pub draw_trophy_delta: u32,         // Trophies both players gain on a draw (default 0)
+}
+
+impl EconomyConfig {
+    /// Values used until the authority creates the EconomyConfig (the original constants)
+    pub const DEFAULTS: EconomyConfig = EconomyConfig {
+        base_trophy_gain: 30,
+        trophy_loss: 30,
+        max_streak_bonus: 0,
+        win_reward: 100,
+        loss_reward: 0,
+        daily_reward_cap: 0,
+        bump: 0,
+        draw_trophy_delta: 0,
+    };
+    
+    // 8 (discriminator) + 4 (base_trophy_gain) + 4 (trophy_loss) + 4 (max_streak_bonus)
+    // + 8 (win_reward) + 8 (loss_reward) + 8 (daily_reward_cap) + 1 (bump)
+    // + 4 (draw_trophy_delta)
+    pub const LEN: usize = 8 + 4 + 4 + 4 + 8 + 8 + 8 + 1 + 4;
+}
+
+/// Card XP and levelling, for the decks played in record_match_result
+#[account]
+pub struct ProgressionConfig {
+    pub xp_per_win: u32,                // XP for each card of the winner's deck
+    pub xp_per_loss: u32,               // XP for each card of the loser's deck
+    pub level_thresholds: Vec<u32>,     // Total XP for level 1, 2, ... (ascending, max 20)
+    pub attack_per_level: u16,          // Attack gained per level
+    pub health_per_level: u16,          // Health gained per level
+    pub max_bonus_bps: u16,             // Stats never exceed the template max plus this share of it
+    pub bump: u8,
+}
+
+impl ProgressionConfig {
+    pub const MAX_LEVELS: usize = 20;
+    
+    // 8 (discriminator) + 4 (xp_per_win) + 4 (xp_per_loss) + 4 + (4 * 20) (level_thresholds vec)
+    // + 2 (attack_per_level) + 2 (health_per_level) + 2 (max_bonus_bps) + 1 (bump)
+    pub const LEN: usize = 8 + 4 + 4 + 4 + (4 * 20) + 2 + 2 + 2 + 1;
+}
+
+/// Price and rules of reroll_card_stats
+#[account]
+pub struct RerollConfig {
+    pub currency: EntryCurrency,        // BUG or gacha tickets
+    pub price: u64,                     // Cost of one reroll
+    pub keep_better: bool,              // Keep the higher of the old and new roll, per stat
+    pub bump: u8,
+}
+
+impl RerollConfig {
+    // 8 (discriminator) + 1 (currency) + 8 (price) + 1 (keep_better) + 1 (bump)
+    pub const LEN: usize = 8 + 1 + 8 + 1 + 1;
+}
+
+/// Trait synergy bonuses for decks with several cards of the same trait
+#[account]
+pub struct SynergyConfig {
+    pub tiers: Vec<SynergyTier>,        // Max 24; per trait, only the highest tier reached applies
+    pub bump: u8,
+}
+
+impl SynergyConfig {
+    pub const MAX_TIERS: usize = 24;
+    
+    // 8 (discriminator) + 4 + (6 * 24) (tiers vec) + 1 (bump)
+    pub const LEN: usize = 8 + 4 + (SynergyTier::LEN * 24) + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SynergyTier {
+    pub trait_type: TraitType,
+    pub min_units: u8,                  // Cards of the trait needed in the deck
+    pub attack_bonus_bps: u16,          // Attack bonus for those cards (10000 = +100%)
+    pub health_bonus_bps: u16,          // Health bonus for those cards
+}
+
+impl SynergyTier {
+    pub const MAX_BONUS_BPS: u16 = 10_000;
+    
+    // 1 (trait_type) + 1 (min_units) + 2 (attack_bonus_bps) + 2 (health_bonus_bps)
+    pub const LEN: usize = 1 + 1 + 2 + 2;
+}
+
+/// A synergy a deck has reached
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ActiveSynergy {
+    pub trait_type: TraitType,
+    pub units: u8,                      // Cards of the trait in the deck
+    pub attack_bonus_bps: u16,
+    pub health_bonus_bps: u16,
+}
+
+impl ActiveSynergy {
+    // 1 (trait_type) + 1 (units) + 2 (attack_bonus_bps) + 2 (health_bonus_bps)
+    pub const LEN: usize = 1 + 1 + 2 + 2;
+}
+
+/// Arena tier trophy thresholds, indexed by ArenaTier
+#[account]
+pub struct TierConfig {
+    pub tiers: Vec<TierThreshold>,      // One per ArenaTier, ascending
+    pub bump: u8,
+}
+
+impl TierConfig {
+    /// Used until the authority creates the TierConfig: everyone stays Bronze, no floors
+    pub const NONE: TierConfig = TierConfig { tiers: Vec::new(), bump: 0 };
+    
+    // 8 (discriminator) + 4 + (5 * 6) (tiers vec) + 1 (bump)
+    pub const LEN: usize = 8 + 4 + (TierThreshold::LEN * 6) + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TierThreshold {
+    pub min_trophies: u32,              // Trophies needed to enter the tier
+    pub has_floor: bool,                // Once reached this season, can't drop below it
+}
+
+impl TierThreshold {
+    // 4 (min_trophies) + 1 (has_floor)
+    pub const LEN: usize = 4 + 1;
+}
+
+#[account]
+pub struct CardTemplate {
+    pub card_type_id: u32,
+    pub name: String,                   // Max 32 chars
+    pub trait_type: TraitType,
+    pub rarity: Rarity,
+    pub min_attack: u16,                // Minimum attack value
+    pub max_attack: u16,                // Maximum attack value
+    pub min_health: u16,                // Minimum health value
+    pub max_health: u16,                // Maximum health value
+    pub description: String,            // Max 200 chars
+    pub image_uri: String,              // Max 200 chars (IPFS URI)
+    pub bump: u8,
+    pub creator: Pubkey,                // Original creator (may edit alongside the authority)
+    pub version: u32,                   // 1 on creation, bumped by every update
+    pub is_retired: bool,               // Retired cards no longer drop
+    pub set_id: u16,                    // Card set / expansion (0 = base game)
+    pub ability: Ability,               // Special combat effect (see the combat module)
+}
+
+impl CardTemplate {
+    pub const MAX_NAME_LEN: usize = 32;
+    pub const MAX_DESCRIPTION_LEN: usize = 200;
+    pub const MAX_IMAGE_URI_LEN: usize = 200;
+    
+    // Calculate space needed for account
+    // 8 (discriminator) + 4 (card_type_id) + 4 + 32 (name) + 1 (trait_type) + 1 (rarity)
+    // + 2 (min_attack) + 2 (max_attack) + 2 (min_health) + 2 (max_health) 
+    // + 4 + 200 (description) + 4 + 200 (image_uri) + 1 (bump)
+    // + 32 (creator) + 4 (version) + 1 (is_retired) + 2 (set_id) + Ability::LEN (ability)
+    pub const LEN: usize = 8 + 4 + 4 + 32 + 1 + 1 + 2 + 2 + 2 + 2 + 4 + 200 + 4 + 200 + 1 + 32 + 4 + 1 + 2 + Ability::LEN;
+}
+
+/// The editable part of a card template, as carried by CardTemplateUpdated
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
+pub struct CardTemplateValues {
+    pub min_attack: u16,
+    pub max_attack: u16,
+    pub min_health: u16,
+    pub max_health: u16,
+    pub description: String,
+    pub image_uri: String,
+    pub ability: Ability,
+}
+
+#[account]
 pub struct PlayerProfile {
     pub wallet: Pubkey,
-    pub username: String,               // Max 32 chars
-    pub has_claimed_starter_pack: bool,
-    pub gacha_tickets: u64,             // Number of gacha tickets owned
-    pub bug_balance: u64,               // BUG token balance (game currency)
-    pub trophies: u32,                  // Minimum is 0, starts at 0
-    pub total_wins: u32,
-    pub total_losses: u32,
-    pub win_streak: u32,                // Current win streak (resets on loss)
-    pub bump: u8,
+    pub username: String,               // Max 32 chars
+    pub has_claimed_starter_pack: bool,
+    pub gacha_tickets: u64,             // Number of gacha tickets owned
+    pub bug_balance: u64,               // BUG token balance (game currency)
+    pub trophies: u32,                  // Minimum is 0, starts at 0
+    pub total_wins: u32,
+    pub total_losses: u32,
+    pub win_streak: u32,                // Current win streak (resets on loss)
+    pub bump: u8,
+    pub rating: u32,                    // Elo skill rating (hidden MMR, starts at 1000)
+    pub season_id: u32,                 // Season the trophies below belong to
+    pub season_best_trophies: u32,      // Highest trophies reached this season
+    pub unclaimed_seasons: Vec<SeasonResult>, // Finished seasons not claimed yet, oldest first (max 4)
+    pub reward_day: u32,                // Unix day of daily_bug_earned
+    pub daily_bug_earned: u64,          // BUG earned from matches on reward_day
+    pub total_draws: u32,
+    pub tier: ArenaTier,                // Current arena tier (from trophies)
+    pub season_peak_tier: ArenaTier,    // Highest tier reached this season (for tier floors)
+    pub crafting_dust: u64,             // Earned by burning cards, spent on craft_card
+    pub collection_pages: u8,           // Collection pages created (page ids 0..collection_pages)
+}
+
+impl PlayerProfile {
+    pub const MAX_USERNAME_LEN: usize = 32;
+    pub const FREE_STARTER_TICKETS: u64 = 10;  // Free tickets for new players
+    pub const INITIAL_RATING: u32 = 1000;      // Starting Elo rating
+    pub const ELO_K_FACTOR: u32 = 32;          // Max rating change per match
+    pub const MAX_UNCLAIMED_SEASONS: usize = 4; // Older unclaimed season rewards expire
+    
+    // Calculate space needed for account
+    // 8 (discriminator) + 32 (wallet) + 4 + 32 (username) + 1 (has_claimed_starter_pack)
+    // + 8 (gacha_tickets) + 8 (bug_balance) + 4 (trophies) + 4 (total_wins) + 4 (total_losses) + 4 (win_streak) + 1 (bump)
+    // + 4 (rating) + 4 (season_id) + 4 (season_best_trophies)
+    // + 4 + (12 * 4) (unclaimed_seasons)
+    // + 4 (reward_day) + 8 (daily_bug_earned) + 4 (total_draws) + 1 (tier) + 1 (season_peak_tier)
+    // + 8 (crafting_dust) + 1 (collection_pages)
+    pub const LEN: usize = 8 + 32 + 4 + 32 + 1 + 8 + 8 + 4 + 4 + 4 + 4 + 1 + 4 + 4 + 4
+        + 4 + (SeasonResult::LEN * 4) + 4 + 8 + 4 + 1 + 1 + 8 + 1;
+}
+
+/// Drop pool of one rarity: the standard pool (set_id 0) or a card set's own pool
+#[account]
+pub struct RarityPool {
+    pub rarity: Rarity,
+    pub entries: Vec<PoolEntry>,        // Cards of this rarity with their drop weights
+    pub bump: u8,
+    pub set_id: u16,                    // 0 = standard pool, otherwise the card set it belongs to
+}
+
+impl RarityPool {
+    pub const MAX_CARDS: usize = 100;   // Max cards per rarity
+    
+    // Calculate space needed for account
+    // 8 (discriminator) + 1 (rarity) + 4 + (6 * 100) (entries vec) + 1 (bump) + 2 (set_id)
+    pub const LEN: usize = 8 + 1 + 4 + (PoolEntry::LEN * 100) + 1 + 2;
+}
+
+/// RarityPool in its original layout, before drop weights and card sets
+/// (only read by migrate_rarity_pool)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct LegacyRarityPool {
+    pub rarity: Rarity,
+    pub card_type_ids: Vec<u32>,
+    pub bump: u8,
+}
+
+impl LegacyRarityPool {
+    // 8 (discriminator) + 1 (rarity) + 4 + (4 * 100) (card_type_ids vec) + 1 (bump)
+    pub const LEN: usize = 8 + 1 + 4 + (4 * 100) + 1;
+    
+    /// The same pool as a standard RarityPool, every card at DEFAULT_WEIGHT
+    pub fn into_pool(self) -> RarityPool {
+        RarityPool {
+            rarity: self.rarity,
+            entries: self.card_type_ids
+                .into_iter()
+                .map(|card_type_id| PoolEntry { card_type_id, weight: PoolEntry::DEFAULT_WEIGHT })
+                .collect(),
+            bump: self.bump,
+            set_id: 0,
+        }
+    }
+}
+
+/// A card in a rarity pool; drop chance within the pool is weight / total weight
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PoolEntry {
+    pub card_type_id: u32,
+    pub weight: u16,                    // > 0; DEFAULT_WEIGHT for a regular card
+}
+
+impl PoolEntry {
+    pub const DEFAULT_WEIGHT: u16 = 100;
+    
+    // 4 (card_type_id) + 2 (weight)
+    pub const LEN: usize = 4 + 2;
+}
+
+/// Purchasable pack configuration, one account per pack_type
+#[account]
+pub struct PackType {
+    pub pack_type: u8,
+    pub name: String,                   // Max 32 chars
+    pub price: u64,                     // Price in BUG tokens
+    pub card_count: u8,                 // 1-10
+    pub guaranteed_rarity: Rarity,      // Minimum rarity of the last card
+    pub trait_filter: Option<TraitType>,// Only cards of this trait (None = any)
+    pub is_active: bool,                // Inactive packs can't be bought
+    pub bump: u8,
+    pub set_id: u16,                    // Draw from this card set's pools (0 = standard pools)
+    pub card_type_ids: Vec<u32>,        // Cards of trait_filter the pack may contain (max 64)
+}
+
+impl PackType {
+    pub const MAX_NAME_LEN: usize = 32;
+    pub const MAX_CARDS: usize = 10;
+    pub const MAX_FILTERED_CARDS: usize = 64;
+    
+    // 8 (discriminator) + 1 (pack_type) + 4 + 32 (name) + 8 (price) + 1 (card_count)
+    // + 1 (guaranteed_rarity) + 1 + 1 (trait_filter option) + 1 (is_active) + 1 (bump)
+    // + 2 (set_id) + 4 + 64 * 4 (card_type_ids)
+    pub const LEN: usize = 8 + 1 + 4 + 32 + 8 + 1 + 1 + 1 + 1 + 1 + 1 + 2 + 4 + Self::MAX_FILTERED_CARDS * 4;
+}
+
+/// A card set / expansion (e.g. "HTTP status" or "Unix errno" cards)
+#[account]
+pub struct CardSet {
+    pub set_id: u16,                    // 1+ (0 is the base game)
+    pub name: String,                   // Max 32 chars
+    pub release_time: i64,              // Set packs on sale from this unix timestamp
+    pub is_active: bool,                // Inactive sets can't be bought in set packs
+    pub bump: u8,
+}
+
+impl CardSet {
+    pub const MAX_NAME_LEN: usize = 32;
+    
+    // 8 (discriminator) + 2 (set_id) + 4 + 32 (name) + 8 (release_time) + 1 (is_active) + 1 (bump)
+    pub const LEN: usize = 8 + 2 + 4 + 32 + 8 + 1 + 1;
+}
+
+/// Individual card instance with rolled stats
+#[account]
+pub struct CardInstance {
+    pub mint: Pubkey,           // The NFT mint address
+    pub card_type_id: u32,      // Reference to CardTemplate
+    pub attack: u16,            // Rolled attack value
+    pub health: u16,            // Rolled health value
+    pub owner: Pubkey,          // Current owner
+    pub bump: u8,
+    pub xp: u32,                // Experience from matches played in a deck
+    pub level: u8,              // Levels gained from XP (0 = as minted)
+}
+
+impl CardInstance {
+    /// Crafting dust for burning a min-rolled card, by rarity discriminant (doubled for a max roll)
+    pub const BURN_DUST: [u64; 3] = [5, 20, 100];
+    /// Crafting dust to craft a card, by rarity discriminant
+    pub const CRAFT_DUST: [u64; 3] = [40, 160, 800];
+    
+    // 8 (discriminator) + 32 (mint) + 4 (card_type_id) + 2 (attack) + 2 (health) + 32 (owner) + 1 (bump)
+    // + 4 (xp) + 1 (level)
+    pub const LEN: usize = 8 + 32 + 4 + 2 + 2 + 32 + 1 + 4 + 1;
+}
+
+/// Player's saved deck (up to 10 cards)
+#[account]
+pub struct PlayerDeck {
+    pub owner: Pubkey,              // Player wallet
+    pub deck_index: u8,             // 0-4 (max 5 decks per player)
+    pub deck_name: String,          // Max 32 chars
+    pub card_mints: Vec<Pubkey>,    // Up to 10 card mint addresses
+    pub is_active: bool,            // false = deleted/empty
+    pub bump: u8,
+}
+
+impl PlayerDeck {
+    pub const MAX_DECKS: u8 = 5;
+    pub const MAX_CARDS: usize = 10;
+    pub const MAX_NAME_LEN: usize = 32;
+    
+    // 8 (discriminator) + 32 (owner) + 1 (deck_index) + 4 + 32 (deck_name) 
+    // + 4 + (32 * 10) (card_mints vec) + 1 (is_active) + 1 (bump)
+    pub const LEN: usize = 8 + 32 + 1 + 4 + 32 + 4 + (32 * 10) + 1 + 1;
+}
+
+/// One page of a player's card index, so all their card mints load in a few fetches
+/// Kept up to date by the instructions that mint, sell or burn cards when the page is passed
+#[account]
+pub struct Collection {
+    pub owner: Pubkey,              // Player wallet
+    pub page: u8,                   // Page id (PDA seed)
+    pub card_mints: Vec<Pubkey>,    // Up to 300 card mint addresses
+    pub bump: u8,
+}
+
+impl Collection {
+    pub const MAX_CARDS: usize = 300;
+    
+    // 8 (discriminator) + 32 (owner) + 1 (page) + 4 + (32 * 300) (card_mints vec) + 1 (bump)
+    pub const LEN: usize = 8 + 32 + 1 + 4 + (32 * 300) + 1;
+}
+
+/// Marketplace listing for a card
+#[account]
+pub struct Listing {
+    pub seller: Pubkey,             // Seller wallet
+    pub card_mint: Pubkey,          // NFT mint address
+    pub price: u64,                 // Price in BUG tokens
+    pub is_active: bool,            // true = listed, false = sold/cancelled
+    pub created_at: i64,            // Unix timestamp
+    pub bump: u8,
+}
+
+impl Listing {
+    // 8 (discriminator) + 32 (seller) + 32 (card_mint) + 8 (price) + 1 (is_active) + 8 (created_at) + 1 (bump)
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 1 + 8 + 1;
+}
+
+/// Ranked season with reward table by trophy bracket
+#[account]
+pub struct Season {
+    pub season_id: u32,                         // Starts at 1
+    pub start_time: i64,                        // Unix timestamp
+    pub end_time: i64,                          // Unix timestamp
+    pub trophy_floor: u32,                      // Trophies above this are halved at rollover
+    pub reward_tiers: Vec<SeasonRewardTier>,    // Sorted by min_trophies ascending (max 8)
+    pub bump: u8,
+}
+
+impl Season {
+    pub const MAX_REWARD_TIERS: usize = 8;
+    
+    // 8 (discriminator) + 4 (season_id) + 8 (start_time) + 8 (end_time) + 4 (trophy_floor)
+    // + 4 + (20 * 8) (reward_tiers vec) + 1 (bump)
+    pub const LEN: usize = 8 + 4 + 8 + 8 + 4 + 4 + (SeasonRewardTier::LEN * 8) + 1;
+}
+
+/// Per-player archive of a finished season (created when rewards are claimed)
+#[account]
+pub struct PlayerSeasonRecord {
+    pub wallet: Pubkey,
+    pub season_id: u32,
+    pub final_trophies: u32,        // Trophies when the season ended
+    pub best_trophies: u32,         // Highest trophies reached during the season
+    pub bug_reward: u64,            // BUG paid out
+    pub ticket_reward: u64,         // Gacha tickets paid out
+    pub bump: u8,
+}
+
+impl PlayerSeasonRecord {
+    // 8 (discriminator) + 32 (wallet) + 4 (season_id) + 4 (final_trophies) + 4 (best_trophies)
+    // + 8 (bug_reward) + 8 (ticket_reward) + 1 (bump)
+    pub const LEN: usize = 8 + 32 + 4 + 4 + 4 + 8 + 8 + 1;
+}
+
+/// Season reward for players finishing with at least `min_trophies`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SeasonRewardTier {
+    pub min_trophies: u32,
+    pub bug_reward: u64,
+    pub ticket_reward: u64,
+}
+
+impl SeasonRewardTier {
+    // 4 (min_trophies) + 8 (bug_reward) + 8 (ticket_reward)
+    pub const LEN: usize = 4 + 8 + 8;
+}
+
+/// A player's result in a finished season, kept on the profile until claimed
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SeasonResult {
+    pub season_id: u32,
+    pub final_trophies: u32,            // Trophies when the season ended
+    pub best_trophies: u32,             // Highest trophies reached during the season
+}
+
+impl SeasonResult {
+    // 4 (season_id) + 4 (final_trophies) + 4 (best_trophies)
+    pub const LEN: usize = 4 + 4 + 4;
+}
+
+/// On-chain top-N trophy ranking, sorted by trophies descending
+#[account]
+pub struct Leaderboard {
+    pub entries: Vec<LeaderboardEntry>,     // Max 100 entries
+    pub bump: u8,
+}
+
+impl Leaderboard {
+    pub const MAX_ENTRIES: usize = 100;
+    
+    // 8 (discriminator) + 4 + (72 * 100) (entries vec) + 1 (bump)
+    pub const LEN: usize = 8 + 4 + (LeaderboardEntry::LEN * 100) + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
+pub struct LeaderboardEntry {
+    pub wallet: Pubkey,
+    pub username: String,           // Max 32 chars
+    pub trophies: u32,
+}
+
+impl LeaderboardEntry {
+    // 32 (wallet) + 4 + 32 (username) + 4 (trophies)
+    pub const LEN: usize = 32 + 4 + 32 + 4;
+}
+
+/// Single-elimination tournament with an escrowed prize pool
+#[account]
+pub struct Tournament {
+    pub tournament_id: u64,
+    pub name: String,                   // Max 32 chars
+    pub entry_currency: EntryCurrency,
+    pub entry_fee: u64,
+    pub max_entrants: u8,               // 2-16
+    pub payout_bps: Vec<u16>,           // Prize share per placement (max 4, sums to 10000)
+    pub status: TournamentStatus,
+    pub entrants: Vec<Pubkey>,          // Registration order (max 16)
+    pub round: u8,                      // 0 = not started, 1 = first round
+    pub current_round: Vec<Pubkey>,     // Players in this round, paired (2i, 2i+1)
+    pub next_round: Vec<Pubkey>,        // Match winners (default = not reported yet)
+    pub eliminated: Vec<Pubkey>,        // Elimination order, champion pushed last
+    pub prize_pool: u64,                // Total escrowed entry fees
+    pub prizes_paid: bool,
+    pub created_at: i64,                // Unix timestamp
+    pub bump: u8,
+}
+
+impl Tournament {
+    pub const MAX_NAME_LEN: usize = 32;
+    pub const MAX_ENTRANTS: usize = 16;
+    pub const MAX_PAID_PLACES: usize = 4;
+    
+    // 8 (discriminator) + 8 (tournament_id) + 4 + 32 (name) + 1 (entry_currency) + 8 (entry_fee)
+    // + 1 (max_entrants) + 4 + (2 * 4) (payout_bps) + 1 (status) + 4 + (32 * 16) (entrants)
+    // + 1 (round) + 4 + (32 * 16) (current_round) + 4 + (32 * 8) (next_round)
+    // + 4 + (32 * 16) (eliminated) + 8 (prize_pool) + 1 (prizes_paid) + 8 (created_at) + 1 (bump)
+    pub const LEN: usize = 8 + 8 + 4 + 32 + 1 + 8 + 1 + 4 + (2 * 4) + 1 + 4 + (32 * 16)
+        + 1 + 4 + (32 * 16) + 4 + (32 * 8) + 4 + (32 * 16) + 8 + 1 + 8 + 1;
+    
+    /// Start a round: pair players up, a lone last player gets a bye
+    // `usize::div_ceil` is newer than the Solana platform tools' rustc
+    #[allow(clippy::manual_div_ceil)]
+    pub fn begin_round(&mut self, players: Vec<Pubkey>) {
+        let mut next_round = vec![Pubkey::default(); (players.len() + 1) / 2];
+        if players.len() % 2 == 1 {
+            next_round[players.len() / 2] = players[players.len() - 1];
+        }
+        self.current_round = players;
+        self.next_round = next_round;
+    }
+    
+    /// The two players of an unreported match in the current round
+    pub fn match_players(&self, match_index: u8) -> Result<(Pubkey, Pubkey)> {
+        let index = match_index as usize;
+        require!(index * 2 + 1 < self.current_round.len(), GameError::InvalidMatch);
+        require!(self.next_round[index] == Pubkey::default(), GameError::MatchAlreadyReported);
+        Ok((self.current_round[index * 2], self.current_round[index * 2 + 1]))
+    }
+    
+    /// Advance the winner; starts the next round or completes the tournament
+    pub fn record_match_winner(&mut self, match_index: u8, winner: Pubkey) -> Result<()> {
+        let (player_a, player_b) = self.match_players(match_index)?;
+        let loser = if winner == player_a {
+            player_b
+        } else if winner == player_b {
+            player_a
+        } else {
+            return Err(GameError::InvalidWinner.into());
+        };
+        
+        self.next_round[match_index as usize] = winner;
+        self.eliminated.push(loser);
+        
+        if self.next_round.iter().any(|player| *player == Pubkey::default()) {
+            return Ok(());
+        }
+        
+        if self.next_round.len() == 1 {
+            self.eliminated.push(winner);
+            self.status = TournamentStatus::Completed;
+        } else {
+            let advancing = self.next_round.clone();
+            self.round = self.round.checked_add(1).ok_or(GameError::NumericalOverflow)?;
+            self.begin_round(advancing);
+        }
+        Ok(())
+    }
+    
+    /// Close a tournament that never started; returns the entrants to refund
+    pub fn cancel(&mut self) -> Result<Vec<Pubkey>> {
+        require!(self.status == TournamentStatus::Registration, GameError::TournamentNotOpen);
+        self.status = TournamentStatus::Cancelled;
+        self.prize_pool = 0;
+        Ok(std::mem::take(&mut self.entrants))
+    }
+    
+    /// Final placements: champion first, then in reverse elimination order
+    pub fn placements(&self) -> Vec<Pubkey> {
+        self.eliminated.iter().rev().copied().collect()
+    }
+}
+
+/// Escrow for tournament entry fees (BUG and tickets are in-game balances)
+#[account]
+pub struct TournamentVault {
+    pub tournament: Pubkey,
+    pub bug_balance: u64,
+    pub ticket_balance: u64,
+    pub bump: u8,
+}
+
+impl TournamentVault {
+    // 8 (discriminator) + 32 (tournament) + 8 (bug_balance) + 8 (ticket_balance) + 1 (bump)
+    pub const LEN: usize = 8 + 32 + 8 + 8 + 1;
+}
+
+/// Limited-time gacha banner with its own drop table, price and pull limit
+#[account]
+pub struct Banner {
+    pub banner_id: u32,
+    pub name: String,                   // Max 32 chars
+    pub start_time: i64,                // Unix timestamp
+    pub end_time: i64,                  // Unix timestamp (exclusive)
+    pub entries: Vec<PoolEntry>,        // Drop table across rarities (max 20)
+    pub currency: EntryCurrency,        // Pulls are paid in BUG or gacha tickets
+    pub price: u64,                     // Cost of one pull
+    pub pull_limit: u32,                // Max pulls per player (0 = unlimited)
+    pub total_pulls: u64,
+    pub bump: u8,
+    pub set_id: u16,                    // Every card is from this set (0 = any)
+}
+
+impl Banner {
+    pub const MAX_NAME_LEN: usize = 32;
+    pub const MAX_ENTRIES: usize = 20;
+    
+    // 8 (discriminator) + 4 (banner_id) + 4 + 32 (name) + 8 (start_time) + 8 (end_time)
+    // + 4 + (6 * 20) (entries vec) + 1 (currency) + 8 (price) + 4 (pull_limit)
+    // + 8 (total_pulls) + 1 (bump) + 2 (set_id)
+    pub const LEN: usize = 8 + 4 + 4 + 32 + 8 + 8 + 4 + (PoolEntry::LEN * 20) + 1 + 8 + 4 + 8 + 1 + 2;
+}
+
+/// A player's pulls on a banner (created on their first pull)
+#[account]
+pub struct BannerPulls {
+    pub banner: Pubkey,
+    pub wallet: Pubkey,
+    pub pulls: u32,
+    pub bump: u8,
+}
+
+impl BannerPulls {
+    // 8 (discriminator) + 32 (banner) + 32 (wallet) + 4 (pulls) + 1 (bump)
+    pub const LEN: usize = 8 + 32 + 32 + 4 + 1;
+}
+
+/// Paid pulls waiting for the hash of the slot they were paid in (one per player)
+/// Revealed from a later slot, closed once every card is claimed
+#[account]
+pub struct PendingPull {
+    pub player: Pubkey,
+    pub source: PullSource,             // What the pull was paid for
+    pub commit_slot: u64,               // Slot the pull was paid in
+    pub card_count: u8,                 // 1-MAX_CARDS
+    pub card_type_ids: Vec<u32>,        // Rolled cards (empty until revealed)
+    pub seed: [u8; 32],                 // Rolls the cards' stats (set on reveal, see reveal)
+    pub claimed: u8,                    // Cards minted so far
+    pub bump: u8,
+}
+
+impl PendingPull {
+    pub const MAX_CARDS: usize = 10;
+    
+    // 8 (discriminator) + 32 (player) + PullSource::LEN (source) + 8 (commit_slot) + 1 (card_count)
+    // + 4 + 10 * 4 (card_type_ids) + 32 (seed) + 1 (claimed) + 1 (bump)
+    pub const LEN: usize = 8 + 32 + PullSource::LEN + 8 + 1 + 4 + Self::MAX_CARDS * 4 + 32 + 1 + 1;
+    
+    pub fn new(player: Pubkey, source: PullSource, commit_slot: u64, card_count: u8, bump: u8) -> Self {
+        Self {
+            player,
+            source,
+            commit_slot,
+            card_count,
+            card_type_ids: Vec::new(),
+            seed: [0; 32],
+            claimed: 0,
+            bump,
+        }
+    }
+    
+    /// Record the rolled cards. A pull revealed after its slot hash aged out keeps a zero
+    /// seed, so its cards get minimum stats (see pull_stats_random).
+    pub fn reveal(&mut self, card_type_ids: Vec<u32>, seed: [u8; 32], expired: bool) {
+        self.card_type_ids = card_type_ids;
+        self.seed = if expired { [0; 32] } else { seed };
+    }
+}
+
+// ============================================================================
+// Enums
+// ============================================================================
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TraitType {
+    Warrior,
+    Archer,
+    Assassin,
+    Tank,
+    Mage,
+    Support,
+}
+
+impl TraitType {
+    pub const COUNT: usize = 6;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Rarity {
+    Common,
+    Rare,
+    Legendary,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum ArenaTier {
+    Bronze,
+    Silver,
+    Gold,
+    Platinum,
+    Diamond,
+    Legend,
+}
+
+impl ArenaTier {
+    pub const COUNT: usize = 6;
+    
+    pub fn from_index(index: usize) -> ArenaTier {
+        match index {
+            0 => ArenaTier::Bronze,
+            1 => ArenaTier::Silver,
+            2 => ArenaTier::Gold,
+            3 => ArenaTier::Platinum,
+            4 => ArenaTier::Diamond,
+            _ => ArenaTier::Legend,
+        }
+    }
+    
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// A card's special combat effect; percentages are 1-100
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Ability {
+    None,
+    Lifesteal { percent: u8 },          // Heal this share of the damage dealt
+    Splash { percent: u8 },             // Also hit the target's row neighbours for this share
+    Shield { amount: u16 },             // Absorb up to this much of the first hit taken
+    DoubleAttack { percent: u8 },       // Attack again each turn, at this share of attack
+}
+
+impl Ability {
+    // 1 (variant) + 2 (largest parameter)
+    pub const LEN: usize = 1 + 2;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EntryCurrency {
+    Bug,
+    Tickets,
+}
+
+/// What a pending pull was paid for
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PullSource {
+    Gacha,
+    Pack { pack_type: u8, price: u64 },
+    Banner { banner_id: u32, pulls: u32 },      // The player's pulls on the banner, this one included
+    Craft { card_type_id: u32 },
+    Reroll { mint: Pubkey, currency: EntryCurrency, price: u64, keep_better: bool },
+}
+
+impl PullSource {
+    // 1 (variant) + 42 (largest fields)
+    pub const LEN: usize = 1 + 42;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TournamentStatus {
+    Registration,
+    InProgress,
+    Completed,
+    Cancelled,
+}
+
+/// Which configuration a ConfigUpdated event refers to
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ConfigKind {
+    Game,
+    CardCreators,
+    Economy,
+    Tiers,
+    RarityPool,
+    Leaderboard,
+    PackType,
+    Reroll,
+    Progression,
+    Synergy,
+}
+
+impl Rarity {
+    pub fn to_discriminant(&self) -> u8 {
+        match self {
+            Rarity::Common => 0,
+            Rarity::Rare => 1,
+            Rarity::Legendary => 2,
+        }
+    }
+}
+
+// ============================================================================
+// Events
+// ============================================================================
+
+#[event]
+pub struct ConfigUpdated {
+    pub authority: Pubkey,
+    pub kind: ConfigKind,
+}
+
+#[event]
+pub struct CardTemplateCreated {
+    pub card_type_id: u32,
+    pub creator: Pubkey,
+    pub name: String,
+    pub trait_type: TraitType,
+    pub rarity: Rarity,
+    pub min_attack: u16,
+    pub max_attack: u16,
+    pub min_health: u16,
+    pub max_health: u16,
+    pub set_id: u16,
+    pub ability: Ability,
+}
+
+#[event]
+pub struct CardTemplateUpdated {
+    pub card_type_id: u32,
+    pub editor: Pubkey,
+    pub version: u32,
+    pub old_values: CardTemplateValues,
+    pub new_values: CardTemplateValues,
+}
+
+#[event]
+pub struct CardTemplateRetired {
+    pub card_type_id: u32,
+    pub authority: Pubkey,
+    pub retired: bool,
+}
+
+#[event]
+pub struct PlayerRegistered {
+    pub wallet: Pubkey,
+    pub username: String,
+}
+
+/// Free starter tickets claimed
+#[event]
+pub struct TicketsClaimed {
+    pub wallet: Pubkey,
+    pub amount: u64,
+    pub total_tickets: u64,
+}
+
+/// Tickets added by the authority
+#[event]
+pub struct TicketsGranted {
+    pub wallet: Pubkey,
+    pub amount: u64,
+    pub total_tickets: u64,
+}
+
+#[event]
+pub struct TicketsPurchased {
+    pub wallet: Pubkey,
+    pub ticket_count: u64,
+    pub bug_cost: u64,
+    pub total_tickets: u64,
+    pub bug_balance: u64,
+}
+
+#[event]
+pub struct BugPurchased {
+    pub wallet: Pubkey,
+    pub sol_amount: u64,
+    pub bug_amount: u64,
+    pub bug_balance: u64,
+}
+
+/// A card NFT was minted with its rolled stats
+#[event]
+pub struct CardMinted {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub card_type_id: u32,
+    pub attack: u16,
+    pub health: u16,
+}
+
+#[event]
+pub struct CardBurned {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub card_type_id: u32,
+    pub dust: u64,
+    pub crafting_dust: u64,
+}
+
+#[event]
+pub struct CardCrafted {
+    pub owner: Pubkey,
+    pub card_type_id: u32,
+    pub dust_spent: u64,
+    pub crafting_dust: u64,
+}
+
+#[event]
+pub struct CardStatsRerolled {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub card_type_id: u32,
+    pub old_attack: u16,
+    pub old_health: u16,
+    pub attack: u16,
+    pub health: u16,
+    pub currency: EntryCurrency,
+    pub price: u64,
+}
+
+#[event]
+pub struct CardXpGained {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub xp_gained: u32,
+    pub xp: u32,
+    pub level: u8,
+    pub attack: u16,
+    pub health: u16,
+}
+
+#[event]
+pub struct PackOpened {
+    pub owner: Pubkey,
+    pub pack_type: u8,
+    pub price: u64,
+    pub card_type_ids: Vec<u32>,
+}
+
+#[event]
+pub struct DeckSaved {
+    pub owner: Pubkey,
+    pub deck_index: u8,
+    pub deck_name: String,
+    pub card_mints: Vec<Pubkey>,
+    pub synergies: Vec<ActiveSynergy>,
+}
+
+#[event]
+pub struct CollectionPageCreated {
+    pub owner: Pubkey,
+    pub page: u8,
+}
+
+#[event]
+pub struct CollectionSynced {
+    pub owner: Pubkey,
+    pub page: u8,
+    pub added: Vec<Pubkey>,
+    pub removed: Vec<Pubkey>,
+}
+
+#[event]
+pub struct DeckDeleted {
+    pub owner: Pubkey,
+    pub deck_index: u8,
+}
+
+#[event]
+pub struct ListingCreated {
+    pub seller: Pubkey,
+    pub card_mint: Pubkey,
+    pub price: u64,
+}
+
+#[event]
+pub struct ListingCancelled {
+    pub seller: Pubkey,
+    pub card_mint: Pubkey,
+}
+
+#[event]
+pub struct ListingFilled {
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub card_mint: Pubkey,
+    pub price: u64,
+    pub fee: u64,
+}
+
+/// Decisive match result with the trophy, rating and reward changes applied
+#[event]
+pub struct MatchRecorded {
+    pub winner: Pubkey,
+    pub loser: Pubkey,
+    pub trophy_gain: u32,
+    pub trophy_loss: u32,
+    pub winner_trophies: u32,
+    pub loser_trophies: u32,
+    pub rating_change: u32,
+    pub winner_reward: u64,
+    pub loser_reward: u64,
+}
+
+/// Drawn match; rating_change is from player A's point of view
+#[event]
+pub struct MatchDrawn {
+    pub player_a: Pubkey,
+    pub player_b: Pubkey,
+    pub trophy_delta: u32,
+    pub player_a_trophies: u32,
+    pub player_b_trophies: u32,
+    pub rating_change: i64,
+}
+
+#[event]
+pub struct LeaderboardReranked {
+    pub profiles: u32,
+    pub entries: u32,
+}
+
+#[event]
+pub struct TournamentCreated {
+    pub tournament_id: u64,
+    pub name: String,
+    pub entry_currency: EntryCurrency,
+    pub entry_fee: u64,
+    pub max_entrants: u8,
+}
+
+#[event]
+pub struct TournamentJoined {
+    pub tournament_id: u64,
+    pub player: Pubkey,
+    pub entrants: u8,
+    pub prize_pool: u64,
+}
+
+#[event]
+pub struct TournamentStarted {
+    pub tournament_id: u64,
+    pub bracket: Vec<Pubkey>,
+}
+
+#[event]
+pub struct TournamentMatchReported {
+    pub tournament_id: u64,
+    pub round: u8,
+    pub match_index: u8,
+    pub winner: Pubkey,
+    pub loser: Pubkey,
+    pub completed: bool,
+}
+
+#[event]
+pub struct TournamentPrizePaid {
+    pub tournament_id: u64,
+    pub place: u8,
+    pub wallet: Pubkey,
+    pub amount: u64,
+    pub currency: EntryCurrency,
+}
+
+#[event]
+pub struct TournamentCancelled {
+    pub tournament_id: u64,
+    pub refunded: u8,
+    pub entry_fee: u64,
+    pub currency: EntryCurrency,
+}
+
+#[event]
+pub struct SeasonStarted {
+    pub season_id: u32,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub trophy_floor: u32,
+}
+
+#[event]
+pub struct SeasonRewardsClaimed {
+    pub wallet: Pubkey,
+    pub season_id: u32,
+    pub final_trophies: u32,
+    pub best_trophies: u32,
+    pub bug_reward: u64,
+    pub ticket_reward: u64,
+}
+
+#[event]
+pub struct CardSetUpdated {
+    pub set_id: u16,
+    pub name: String,
+    pub release_time: i64,
+    pub is_active: bool,
+}
+
+#[event]
+pub struct BannerCreated {
+    pub banner_id: u32,
+    pub name: String,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub currency: EntryCurrency,
+    pub price: u64,
+    pub pull_limit: u32,
+}
+
+#[event]
+pub struct BannerEnded {
+    pub banner_id: u32,
+    pub end_time: i64,
+}
+
+/// Emitted when a player pays for a pull; its cards are rolled by the reveal
+#[event]
+pub struct PullCommitted {
+    pub wallet: Pubkey,
+    pub source: PullSource,
+    pub card_count: u8,
+    pub commit_slot: u64,
+}
+
+/// Emitted when a pending pull's cards are rolled (claim_pulled_cards mints them)
+#[event]
+pub struct PullRevealed {
+    pub wallet: Pubkey,
+    pub source: PullSource,
+    pub card_type_ids: Vec<u32>,
+}
+
+/// A banner pull, emitted after the CardMinted of the card it produced
+#[event]
+pub struct BannerPulled {
+    pub banner_id: u32,
+    pub wallet: Pubkey,
+    pub card_type_id: u32,
+    pub mint: Pubkey,
+    pub pulls: u32,                     // The player's pulls on this banner so far
+}
+
+/// Emitted when a player moves to a different arena tier
+#[event]
+pub struct TierChanged {
+    pub wallet: Pubkey,
+    pub old_tier: ArenaTier,
+    pub new_tier: ArenaTier,
+    pub trophies: u32,
+    pub promoted: bool,
+}
+
+// ============================================================================
+// Error Types
+// ============================================================================
+
+#[error_code]
+pub enum GameError {
+    #[msg("Card type ID already exists")]
+    DuplicateCardTypeId,
+    
+    #[msg("Invalid trait type")]
+    InvalidTrait,
+    
+    #[msg("Invalid rarity")]
+    InvalidRarity,
+    
+    #[msg("Name or description cannot be empty")]
+    EmptyString,
+    
+    #[msg("Player has already claimed starter pack")]
+    StarterPackAlreadyClaimed,
+    
+    #[msg("Insufficient BUG token balance")]
+    InsufficientBalance,
+    
+    #[msg("Unauthorized access")]
+    Unauthorized,
+    
+    #[msg("Invalid username")]
+    InvalidUsername,
+    
+    #[msg("Rarity pool is empty")]
+    EmptyRarityPool,
+    
+    #[msg("Invalid pack type")]
+    InvalidPackType,
+    
+    #[msg("Numerical overflow")]
+    NumericalOverflow,
+    
+    #[msg("Card creators list is full")]
+    CardCreatorsListFull,
+    
+    #[msg("String exceeds maximum length")]
+    StringTooLong,
+    
+    #[msg("Invalid stat range: min cannot be greater than max")]
+    InvalidStatRange,
+    
+    #[msg("Insufficient gacha tickets")]
+    InsufficientTickets,
+    
+    #[msg("Invalid draw count (must be 1-10)")]
+    InvalidDrawCount,
+    
+    #[msg("Invalid deck index (must be 0-4)")]
+    InvalidDeckIndex,
+    
+    #[msg("Too many cards in deck (max 10)")]
+    TooManyCardsInDeck,
+    
+    #[msg("Invalid price (must be greater than 0)")]
+    InvalidPrice,
+    
+    #[msg("Listing is not active")]
+    ListingNotActive,
+    
+    #[msg("Cannot buy your own card")]
+    CannotBuyOwnCard,
+    
+    #[msg("Invalid amount (must be greater than 0)")]
+    InvalidAmount,
+    
+    #[msg("Invalid season")]
+    InvalidSeason,
+    
+    #[msg("Season end time must be in the future")]
+    InvalidSeasonEndTime,
+    
+    #[msg("Season has not ended yet")]
+    SeasonNotEnded,
+    
+    #[msg("Player has no result for this season")]
+    NoSeasonResult,
+    
+    #[msg("Invalid season reward tiers (max 8, sorted by trophies)")]
+    InvalidRewardTiers,
+    
+    #[msg("Invalid tournament size (must be 2-16)")]
+    InvalidTournamentSize,
+    
+    #[msg("Invalid payouts (max 4 places, must sum to 10000 bps)")]
+    InvalidPayouts,
+    
+    #[msg("Tournament is not open for registration")]
+    TournamentNotOpen,
+    
+    #[msg("Tournament is full")]
+    TournamentFull,
+    
+    #[msg("Player already joined this tournament")]
+    AlreadyJoined,
+    
+    #[msg("Not enough entrants to start")]
+    NotEnoughEntrants,
+    
+    #[msg("Tournament is not in progress")]
+    TournamentNotInProgress,
+    
+    #[msg("Invalid match index")]
+    InvalidMatch,
+    
+    #[msg("Match result already reported")]
+    MatchAlreadyReported,
+    
+    #[msg("Winner is not a player in this match")]
+    InvalidWinner,
+    
+    #[msg("Tournament is not completed")]
+    TournamentNotCompleted,
+    
+    #[msg("Tournament prizes already paid")]
+    PrizesAlreadyPaid,
+    
+    #[msg("Placement accounts do not match tournament results")]
+    InvalidPlacementAccount,
+    
+    #[msg("Invalid tier thresholds (one per tier, ascending from 0)")]
+    InvalidTierThresholds,
+    
+    #[msg("Card template is retired")]
+    CardTemplateRetired,
+    
+    #[msg("Template accounts do not match card_type_ids")]
+    TemplateAccountMismatch,
+    
+    #[msg("Card template rarity does not match the rarity pool")]
+    RarityMismatch,
+    
+    #[msg("Rarity pool is full (max 100 cards)")]
+    RarityPoolFull,
+    
+    #[msg("Pool entry weight must be greater than 0")]
+    InvalidPoolWeight,
+    
+    #[msg("Banner must end after it starts, in the future")]
+    InvalidBannerWindow,
+    
+    #[msg("Invalid banner entries (1-20 cards, no duplicates)")]
+    InvalidBannerEntries,
+    
+    #[msg("Banner is not active")]
+    BannerNotActive,
+    
+    #[msg("Banner pull limit reached")]
+    BannerPullLimitReached,
+    
+    #[msg("Card template trait does not match the pack")]
+    TraitMismatch,
+    
+    #[msg("Invalid card set")]
+    InvalidCardSet,
+    
+    #[msg("Card set is not active or not released yet")]
+    CardSetNotAvailable,
+    
+    #[msg("Card template is not in this card set")]
+    SetMismatch,
+    
+    #[msg("Rarity pool does not match the rarity or card set")]
+    InvalidRarityPool,
+    
+    #[msg("Insufficient crafting dust")]
+    InsufficientDust,
+    
+    #[msg("Invalid level thresholds (max 20, ascending from above 0)")]
+    InvalidLevelThresholds,
+    
+    #[msg("Card progression is not configured")]
+    ProgressionNotConfigured,
+    
+    #[msg("Deck accounts do not match the match players or deck cards")]
+    InvalidDeckAccounts,
+    
+    #[msg("Invalid ability (percentages 1-100, shield above 0)")]
+    InvalidAbility,
+    
+    #[msg("Invalid synergy tiers (max 24, 1-10 units, bonus up to 10000 bps, no duplicates)")]
+    InvalidSynergyTiers,
+    
+    #[msg("Trait synergies are not configured")]
+    SynergyNotConfigured,
+    
+    #[msg("Collection page is full (max 300 cards)")]
+    CollectionFull,
+    
+    #[msg("Card is not in this collection page")]
+    CardNotInCollection,
+    
+    #[msg("Too many collection pages")]
+    TooManyCollectionPages,
+    
+    #[msg("Collection accounts must be the card instances of the given mints")]
+    InvalidCollectionCards,
+    
+    #[msg("Trait-filtered packs need the templates of their cards in remaining_accounts")]
+    EmptyTraitFilter,
+    
+    #[msg("Too many cards in a trait-filtered pack (max 64)")]
+    TooManyFilteredCards,
+    
+    #[msg("Account to migrate is not of the expected type")]
+    InvalidMigrationAccount,
+    
+    #[msg("Entrant profiles must be passed in registration order")]
+    InvalidEntrantAccount,
+    
+    #[msg("Every other collection page of the player must be passed, in page order")]
+    InvalidCollectionPage,
+    
+    #[msg("Card is already indexed on another collection page")]
+    CardOnAnotherPage,
+    
+    #[msg("Card accounts must be the template, mint, token account and card instance of every card")]
+    InvalidPackAccounts,
+    
+    #[msg("Card template is not the one rolled")]
+    CardNotRolled,
+    
+    #[msg("A player can't play a match against themselves")]
+    SamePlayer,
+    
+    #[msg("Board positions must be 0-5, one unit per position")]
+    InvalidBoardPosition,
+    
+    #[msg("A pull is 1-10 cards")]
+    InvalidPullCount,
+    
+    #[msg("Pending pull was paid for something else")]
+    InvalidPullSource,
+    
+    #[msg("Pulls can be revealed from the slot after they were paid for")]
+    PullNotReady,
+    
+    #[msg("Pull has already been revealed")]
+    PullAlreadyRevealed,
+    
+    #[msg("Pull has not been revealed yet")]
+    PullNotRevealed,
+    
+    #[msg("Reroll accounts must be the card instance and template of the rerolled card")]
+    InvalidRerollAccounts,
+}
+
+// ============================================================================
+// Instruction Contexts
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = GameConfig::LEN,
+        seeds = [b"game_config"],
+        bump
+    )]
+    pub game_config: Account<'info, GameConfig>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AddCardCreator<'info> {
+    #[account(
+        mut,
+        seeds = [b"game_config"],
+        bump = game_config.bump,
+        has_one = authority
+    )]
+    pub game_config: Account<'info, GameConfig>,
+    
+    pub authority: Signer<'info>,
+}
+
+
+
+#[derive(Accounts)]
+pub struct UpdateEconomyConfig<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = EconomyConfig::LEN,
+        seeds = [b"economy_config"],
+        bump
+    )]
+    pub economy_config: Account<'info, EconomyConfig>,
+    
+    #[account(
+        seeds = [b"game_config"],
+        bump = game_config.bump,
+        has_one = authority
+    )]
+    pub game_config: Account<'info, GameConfig>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateProgressionConfig<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = ProgressionConfig::LEN,
+        seeds = [b"progression_config"],
+        bump
+    )]
+    pub progression_config: Account<'info, ProgressionConfig>,
+    
+    #[account(
+        seeds = [b"game_config"],
+        bump = game_config.bump,
+        has_one = authority
+    )]
+    pub game_config: Account<'info, GameConfig>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateRerollConfig<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = RerollConfig::LEN,
+        seeds = [b"reroll_config"],
+        bump
+    )]
+    pub reroll_config: Account<'info, RerollConfig>,
+    
+    #[account(
+        seeds = [b"game_config"],
+        bump = game_config.bump,
+        has_one = authority
+    )]
+    pub game_config: Account<'info, GameConfig>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateSynergyConfig<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = SynergyConfig::LEN,
+        seeds = [b"synergy_config"],
+        bump
+    )]
+    pub synergy_config: Account<'info, SynergyConfig>,
+    
+    #[account(
+        seeds = [b"game_config"],
+        bump = game_config.bump,
+        has_one = authority
+    )]
+    pub game_config: Account<'info, GameConfig>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateTierConfig<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = TierConfig::LEN,
+        seeds = [b"tier_config"],
+        bump
+    )]
+    pub tier_config: Account<'info, TierConfig>,
+    
+    #[account(
+        seeds = [b"game_config"],
+        bump = game_config.bump,
+        has_one = authority
+    )]
+    pub game_config: Account<'info, GameConfig>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(card_type_id: u32)]
+pub struct CreateCardTemplate<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = CardTemplate::LEN,
+        seeds = [b"card_template", card_type_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub card_template: Account<'info, CardTemplate>,
+    
+    #[account(
+        seeds = [b"game_config"],
+        bump = game_config.bump
+    )]
+    pub game_config: Account<'info, GameConfig>,
+    
+    /// The card set of the template (omit for set 0)
+    pub card_set: Option<Account<'info, CardSet>>,
+    
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(card_type_id: u32)]
+pub struct UpdateCardTemplate<'info> {
+    #[account(
+        mut,
+        seeds = [b"card_template", card_type_id.to_le_bytes().as_ref()],
+        bump = card_template.bump
+    )]
+    pub card_template: Account<'info, CardTemplate>,
+    
+    #[account(
+        seeds = [b"game_config"],
+        bump = game_config.bump
+    )]
+    pub game_config: Account<'info, GameConfig>,
+    
+    /// Authority or the template's original creator
+    pub editor: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(pack_type: u8)]
+pub struct UpdatePackType<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = PackType::LEN,
+        seeds = [b"pack_type", &[pack_type][..]],
+        bump
+    )]
+    pub pack: Account<'info, PackType>,
+    
+    #[account(
+        seeds = [b"game_config"],
+        bump = game_config.bump,
+        has_one = authority
+    )]
+    pub game_config: Account<'info, GameConfig>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(set_id: u16)]
+pub struct UpdateCardSet<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = CardSet::LEN,
+        seeds = [b"card_set", set_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub card_set: Account<'info, CardSet>,
+    
+    #[account(
+        seeds = [b"game_config"],
+        bump = game_config.bump,
+        has_one = authority
+    )]
+    pub game_config: Account<'info, GameConfig>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(set_id: u16, rarity_discriminant: u8)]
+pub struct UpdateSetPool<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = RarityPool::LEN,
+        seeds = [b"set_pool", set_id.to_le_bytes().as_ref(), &[rarity_discriminant][..]],
+        bump
+    )]
+    pub set_pool: Account<'info, RarityPool>,
+    
+    #[account(
+        seeds = [b"card_set", set_id.to_le_bytes().as_ref()],
+        bump = card_set.bump
+    )]
+    pub card_set: Account<'info, CardSet>,
+    
+    #[account(
+        seeds = [b"game_config"],
+        bump = game_config.bump,
+        has_one = authority
+    )]
+    pub game_config: Account<'info, GameConfig>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(rarity_discriminant: u8)]
+pub struct UpdateRarityPool<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = RarityPool::LEN,
+        seeds = [b"rarity_pool", &[rarity_discriminant][..]],
+        bump
+    )]
+    pub rarity_pool: Account<'info, RarityPool>,
+    
+    #[account(
+        seeds = [b"game_config"],
+        bump = game_config.bump,
+        has_one = authority
+    )]
+    pub game_config: Account<'info, GameConfig>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(rarity_discriminant: u8)]
+pub struct RemoveFromRarityPool<'info> {
+    /// A standard or card set pool of this rarity
+    #[account(
+        mut,
+        constraint = rarity_pool.rarity.to_discriminant() == rarity_discriminant @ GameError::InvalidRarityPool
+    )]
+    pub rarity_pool: Account<'info, RarityPool>,
+    
+    #[account(
+        seeds = [b"game_config"],
+        bump = game_config.bump,
+        has_one = authority
+    )]
+    pub game_config: Account<'info, GameConfig>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(card_type_id: u32)]
+pub struct SetCardTemplateRetired<'info> {
+    #[account(
+        mut,
+        seeds = [b"card_template", card_type_id.to_le_bytes().as_ref()],
+        bump = card_template.bump
+    )]
+    pub card_template: Account<'info, CardTemplate>,
+    
+    /// The pool of the template's rarity
+    #[account(
+        mut,
+        seeds = [b"rarity_pool", &[card_template.rarity.to_discriminant()]],
+        bump = rarity_pool.bump
+    )]
+    pub rarity_pool: Account<'info, RarityPool>,
+    
+    /// The template's card set pool, if it has one
+    #[account(mut)]
+    pub set_pool: Option<Account<'info, RarityPool>>,
+    
+    #[account(
+        seeds = [b"game_config"],
+        bump = game_config.bump,
+        has_one = authority
+    )]
+    pub game_config: Account<'info, GameConfig>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterPlayer<'info> {
+    #[account(
+        init,
+        payer = player,
+        space = PlayerProfile::LEN,
+        seeds = [b"player_profile", player.key().as_ref()],
+        bump
+    )]
+    pub player_profile: Account<'info, PlayerProfile>,
+    
+    #[account(
+        seeds = [b"game_config"],
+        bump = game_config.bump
+    )]
+    pub game_config: Account<'info, GameConfig>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimStarterTickets<'info> {
+    #[account(
+        mut,
+        seeds = [b"player_profile", player.key().as_ref()],
+        bump = player_profile.bump,
+        constraint = !player_profile.has_claimed_starter_pack @ GameError::StarterPackAlreadyClaimed
+    )]
+    pub player_profile: Account<'info, PlayerProfile>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GachaDraw<'info> {
+    #[account(
+        mut,
+        seeds = [b"player_profile", player.key().as_ref()],
+        bump = player_profile.bump
+    )]
+    pub player_profile: Account<'info, PlayerProfile>,
+    
+    /// The paid draws, until their cards are claimed (fails while another pull is pending)
+    #[account(
+        init,
+        payer = player,
+        space = PendingPull::LEN,
+        seeds = [b"pending_pull", player.key().as_ref()],
+        bump
+    )]
+    pub pending_pull: Account<'info, PendingPull>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevealGachaPull<'info> {
+    #[account(
+        mut,
+        seeds = [b"pending_pull", pending_pull.player.as_ref()],
+        bump = pending_pull.bump
+    )]
+    pub pending_pull: Account<'info, PendingPull>,
+    
+    #[account(
+        seeds = [b"rarity_pool", &[Rarity::Common.to_discriminant()]],
+        bump = rarity_pool_common.bump
+    )]
+    pub rarity_pool_common: Account<'info, RarityPool>,
+    
+    #[account(
+        seeds = [b"rarity_pool", &[Rarity::Rare.to_discriminant()]],
+        bump = rarity_pool_rare.bump
+    )]
+    pub rarity_pool_rare: Account<'info, RarityPool>,
+    
+    #[account(
+        seeds = [b"rarity_pool", &[Rarity::Legendary.to_discriminant()]],
+        bump = rarity_pool_legendary.bump
+    )]
+    pub rarity_pool_legendary: Account<'info, RarityPool>,
+    
+    /// CHECK: the SlotHashes sysvar, read raw (too large to deserialize)
+    #[account(address = slot_hashes::ID)]
+    pub slot_hashes: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimPulledCards<'info> {
+    #[account(
+        mut,
+        seeds = [b"pending_pull", player.key().as_ref()],
+        bump = pending_pull.bump
+    )]
+    pub pending_pull: Account<'info, PendingPull>,
+    
+    #[account(
+        seeds = [b"game_config"],
+        bump = game_config.bump
+    )]
+    pub game_config: Account<'info, GameConfig>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    
+    /// The player's collection page to index the new cards in (optional)
+    #[account(
+        mut,
+        seeds = [b"collection", player.key().as_ref(), &[collection.page]],
+        bump = collection.bump
+    )]
+    pub collection: Option<Account<'info, Collection>>,
+}
+
+#[derive(Accounts)]
+pub struct AddGachaTickets<'info> {
+    #[account(
+        mut,
+        seeds = [b"player_profile", player_profile.wallet.as_ref()],
+        bump = player_profile.bump
+    )]
+    pub player_profile: Account<'info, PlayerProfile>,
+    
+    #[account(
+        seeds = [b"game_config"],
+        bump = game_config.bump,
+        has_one = authority
+    )]
+    pub game_config: Account<'info, GameConfig>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct BuyBugTokens<'info> {
+    #[account(
+        seeds = [b"game_config"],
+        bump = game_config.bump
+    )]
+    pub game_config: Account<'info, GameConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"player_profile", player.key().as_ref()],
+        bump = player_profile.bump
+    )]
+    pub player_profile: Account<'info, PlayerProfile>,
+    
+    /// Treasury to receive SOL
+    /// CHECK: This is the treasury wallet to receive SOL payments
+    #[account(mut)]
+    pub treasury: AccountInfo<'info>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BuyGachaTickets<'info> {
+    #[account(
+        seeds = [b"game_config"],
+        bump = game_config.bump
+    )]
+    pub game_config: Account<'info, GameConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"player_profile", player.key().as_ref()],
+        bump = player_profile.bump
+    )]
+    pub player_profile: Account<'info, PlayerProfile>,
+    
+    pub player: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(pack_type: u8)]
+pub struct PurchasePack<'info> {
+    #[account(
+        mut,
+        seeds = [b"player_profile", player.key().as_ref()],
+        bump = player_profile.bump
+    )]
+    pub player_profile: Account<'info, PlayerProfile>,
+    
+    #[account(
+        seeds = [b"pack_type", &[pack_type][..]],
+        bump = pack.bump
+    )]
+    pub pack: Account<'info, PackType>,
+    
+    /// The pack's card set (omit for standard packs)
+    pub card_set: Option<Account<'info, CardSet>>,
+    
+    /// The bought pack, until its cards are claimed (fails while another pull is pending)
+    #[account(
+        init,
+        payer = player,
+        space = PendingPull::LEN,
+        seeds = [b"pending_pull", player.key().as_ref()],
+        bump
+    )]
+    pub pending_pull: Account<'info, PendingPull>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
 }
 
-impl PlayerProfile {
-    pub const MAX_USERNAME_LEN: usize = 32;
-    pub const FREE_STARTER_TICKETS: u64 = 10;  // Free tickets for new players
-    pub const BASE_TROPHY_GAIN: u32 = 30;      // Base trophy gain per win
-    pub const TROPHY_LOSS: u32 = 30;           // Trophy loss per loss
-    pub const WIN_REWARD: u64 = 100;           // BUG tokens reward per win
+#[derive(Accounts)]
+pub struct RevealPack<'info> {
+    #[account(
+        mut,
+        seeds = [b"pending_pull", pending_pull.player.as_ref()],
+        bump = pending_pull.bump
+    )]
+    pub pending_pull: Account<'info, PendingPull>,
     
-    // Calculate space needed for account
-    // 8 (discriminator) + 32 (wallet) + 4 + 32 (username) + 1 (has_claimed_starter_pack)
-    // + 8 (gacha_tickets) + 8 (bug_balance) + 4 (trophies) + 4 (total_wins) + 4 (total_losses) + 4 (win_streak) + 1 (bump)
-    pub const LEN: usize = 8 + 32 + 4 + 32 + 1 + 8 + 8 + 4 + 4 + 4 + 4 + 1;
+    #[account(
+        seeds = [b"pack_type", &[pack.pack_type][..]],
+        bump = pack.bump
+    )]
+    pub pack: Account<'info, PackType>,
+    
+    // Standard pools, or the card set's pools for set packs
+    #[account(
+        constraint = rarity_pool_common.rarity == Rarity::Common
+            && rarity_pool_common.set_id == pack.set_id @ GameError::InvalidRarityPool
+    )]
+    pub rarity_pool_common: Account<'info, RarityPool>,
+    
+    #[account(
+        constraint = rarity_pool_rare.rarity == Rarity::Rare
+            && rarity_pool_rare.set_id == pack.set_id @ GameError::InvalidRarityPool
+    )]
+    pub rarity_pool_rare: Account<'info, RarityPool>,
+    
+    #[account(
+        constraint = rarity_pool_legendary.rarity == Rarity::Legendary
+            && rarity_pool_legendary.set_id == pack.set_id @ GameError::InvalidRarityPool
+    )]
+    pub rarity_pool_legendary: Account<'info, RarityPool>,
+    
+    /// CHECK: the SlotHashes sysvar, read raw (too large to deserialize)
+    #[account(address = slot_hashes::ID)]
+    pub slot_hashes: UncheckedAccount<'info>,
 }
 
-#[account]
-pub struct RarityPool {
-    pub rarity: Rarity,
-    pub card_type_ids: Vec<u32>,        // List of card IDs for this rarity
-    pub bump: u8,
+#[derive(Accounts)]
+pub struct BurnCard<'info> {
+    #[account(
+        mut,
+        seeds = [b"player_profile", player.key().as_ref()],
+        bump = player_profile.bump
+    )]
+    pub player_profile: Account<'info, PlayerProfile>,
+    
+    #[account(mut)]
+    pub card_mint: Account<'info, Mint>,
+    
+    /// Player's token account holding the NFT (closed after the burn)
+    #[account(
+        mut,
+        associated_token::mint = card_mint,
+        associated_token::authority = player,
+        constraint = player_card_token_account.amount == 1 @ GameError::Unauthorized
+    )]
+    pub player_card_token_account: Account<'info, TokenAccount>,
+    
+    /// Card instance with the rolled stats (closed, rent returned to the player)
+    #[account(
+        mut,
+        seeds = [b"card_instance", card_mint.key().as_ref()],
+        bump = card_instance.bump,
+        close = player
+    )]
+    pub card_instance: Account<'info, CardInstance>,
+    
+    /// Template of the burned card (rarity and stat range for the dust value)
+    #[account(
+        seeds = [b"card_template", card_instance.card_type_id.to_le_bytes().as_ref()],
+        bump = card_template.bump
+    )]
+    pub card_template: Account<'info, CardTemplate>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    
+    /// The player's collection page holding the card (optional)
+    #[account(
+        mut,
+        seeds = [b"collection", player.key().as_ref(), &[collection.page]],
+        bump = collection.bump
+    )]
+    pub collection: Option<Account<'info, Collection>>,
 }
 
-impl RarityPool {
-    pub const MAX_CARDS: usize = 100;   // Max cards per rarity
+#[derive(Accounts)]
+pub struct RerollCardStats<'info> {
+    #[account(
+        mut,
+        seeds = [b"player_profile", player.key().as_ref()],
+        bump = player_profile.bump
+    )]
+    pub player_profile: Account<'info, PlayerProfile>,
     
-    // Calculate space needed for account
-    // 8 (discriminator) + 1 (rarity) + 4 + (4 * 100) (card_type_ids vec) + 1 (bump)
-    pub const LEN: usize = 8 + 1 + 4 + (4 * 100) + 1;
+    /// Rerolls are disabled until the authority creates this
+    #[account(
+        seeds = [b"reroll_config"],
+        bump = reroll_config.bump
+    )]
+    pub reroll_config: Account<'info, RerollConfig>,
+    
+    pub card_mint: Account<'info, Mint>,
+    
+    /// Player's token account holding the NFT (proves ownership)
+    #[account(
+        associated_token::mint = card_mint,
+        associated_token::authority = player,
+        constraint = player_card_token_account.amount == 1 @ GameError::Unauthorized
+    )]
+    pub player_card_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        seeds = [b"card_instance", card_mint.key().as_ref()],
+        bump = card_instance.bump
+    )]
+    pub card_instance: Account<'info, CardInstance>,
+    
+    /// The paid reroll, until it is revealed (fails while another pull is pending)
+    #[account(
+        init,
+        payer = player,
+        space = PendingPull::LEN,
+        seeds = [b"pending_pull", player.key().as_ref()],
+        bump
+    )]
+    pub pending_pull: Account<'info, PendingPull>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
 }
 
-/// Individual card instance with rolled stats
-#[account]
-pub struct CardInstance {
-    pub mint: Pubkey,           // The NFT mint address
-    pub card_type_id: u32,      // Reference to CardTemplate
-    pub attack: u16,            // Rolled attack value
-    pub health: u16,            // Rolled health value
-    pub owner: Pubkey,          // Current owner
-    pub bump: u8,
+#[derive(Accounts)]
+pub struct RevealCardReroll<'info> {
+    #[account(
+        mut,
+        seeds = [b"pending_pull", pending_pull.player.as_ref()],
+        bump = pending_pull.bump,
+        close = player
+    )]
+    pub pending_pull: Account<'info, PendingPull>,
+    
+    /// CHECK: the player who paid, receiving the pending pull's rent
+    #[account(mut, address = pending_pull.player)]
+    pub player: UncheckedAccount<'info>,
+    
+    /// CHECK: the rerolled card's CardInstance PDA, checked in the handler (empty once the card is burned)
+    #[account(mut)]
+    pub card_instance: UncheckedAccount<'info>,
+    
+    /// Template of the card (stat ranges for the new roll; may be omitted once the card is burned)
+    pub card_template: Option<Account<'info, CardTemplate>>,
+    
+    /// Needed to reroll a card that has levelled up
+    #[account(
+        seeds = [b"progression_config"],
+        bump = progression_config.bump
+    )]
+    pub progression_config: Option<Account<'info, ProgressionConfig>>,
+    
+    /// CHECK: the SlotHashes sysvar, read raw (too large to deserialize)
+    #[account(address = slot_hashes::ID)]
+    pub slot_hashes: UncheckedAccount<'info>,
 }
 
-impl CardInstance {
-    // 8 (discriminator) + 32 (mint) + 4 (card_type_id) + 2 (attack) + 2 (health) + 32 (owner) + 1 (bump)
-    pub const LEN: usize = 8 + 32 + 4 + 2 + 2 + 32 + 1;
+#[derive(Accounts)]
+#[instruction(card_type_id: u32)]
+pub struct CraftCard<'info> {
+    #[account(
+        mut,
+        seeds = [b"player_profile", player.key().as_ref()],
+        bump = player_profile.bump
+    )]
+    pub player_profile: Account<'info, PlayerProfile>,
+    
+    #[account(
+        seeds = [b"card_template", card_type_id.to_le_bytes().as_ref()],
+        bump = card_template.bump
+    )]
+    pub card_template: Account<'info, CardTemplate>,
+    
+    /// The template's card set (omit for base game cards)
+    pub card_set: Option<Account<'info, CardSet>>,
+    
+    /// The crafted card, until it is claimed (fails while another pull is pending)
+    #[account(
+        init,
+        payer = player,
+        space = PendingPull::LEN,
+        seeds = [b"pending_pull", player.key().as_ref()],
+        bump
+    )]
+    pub pending_pull: Account<'info, PendingPull>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
 }
 
-/// Player's saved deck (up to 10 cards)
-#[account]
-pub struct PlayerDeck {
-    pub owner: Pubkey,              // Player wallet
-    pub deck_index: u8,             // 0-4 (max 5 decks per player)
-    pub deck_name: String,          // Max 32 chars
-    pub card_mints: Vec<Pubkey>,    // Up to 10 card mint addresses
-    pub is_active: bool,            // false = deleted/empty
-    pub bump: u8,
+#[derive(Accounts)]
+pub struct RevealCraftedCard<'info> {
+    #[account(
+        mut,
+        seeds = [b"pending_pull", pending_pull.player.as_ref()],
+        bump = pending_pull.bump
+    )]
+    pub pending_pull: Account<'info, PendingPull>,
+    
+    /// CHECK: the SlotHashes sysvar, read raw (too large to deserialize)
+    #[account(address = slot_hashes::ID)]
+    pub slot_hashes: UncheckedAccount<'info>,
 }
 
-impl PlayerDeck {
-    pub const MAX_DECKS: u8 = 5;
-    pub const MAX_CARDS: usize = 10;
-    pub const MAX_NAME_LEN: usize = 32;
+#[derive(Accounts)]
+#[instruction(deck_index: u8)]
+pub struct SaveDeck<'info> {
+    #[account(
+        init_if_needed,
+        payer = player,
+        space = PlayerDeck::LEN,
+        seeds = [b"player_deck", player.key().as_ref(), &[deck_index]],
+        bump
+    )]
+    pub player_deck: Account<'info, PlayerDeck>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    
+    /// Needed when the deck's cards are passed for synergies
+    #[account(
+        seeds = [b"synergy_config"],
+        bump = synergy_config.bump
+    )]
+    pub synergy_config: Option<Account<'info, SynergyConfig>>,
+}
+
+#[derive(Accounts)]
+#[instruction(deck_index: u8)]
+pub struct DeleteDeck<'info> {
+    #[account(
+        mut,
+        seeds = [b"player_deck", player.key().as_ref(), &[deck_index]],
+        bump = player_deck.bump,
+        constraint = player_deck.owner == player.key() @ GameError::Unauthorized
+    )]
+    pub player_deck: Account<'info, PlayerDeck>,
+    
+    pub player: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateCollectionPage<'info> {
+    #[account(
+        init,
+        payer = player,
+        space = Collection::LEN,
+        seeds = [b"collection", player.key().as_ref(), &[player_profile.collection_pages]],
+        bump
+    )]
+    pub collection: Account<'info, Collection>,
+    
+    #[account(
+        mut,
+        seeds = [b"player_profile", player.key().as_ref()],
+        bump = player_profile.bump
+    )]
+    pub player_profile: Account<'info, PlayerProfile>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SyncCollection<'info> {
+    #[account(
+        mut,
+        seeds = [b"collection", player.key().as_ref(), &[collection.page]],
+        bump = collection.bump
+    )]
+    pub collection: Account<'info, Collection>,
+    
+    #[account(
+        seeds = [b"player_profile", player.key().as_ref()],
+        bump = player_profile.bump
+    )]
+    pub player_profile: Account<'info, PlayerProfile>,
     
-    // 8 (discriminator) + 32 (owner) + 1 (deck_index) + 4 + 32 (deck_name) 
-    // + 4 + (32 * 10) (card_mints vec) + 1 (is_active) + 1 (bump)
-    pub const LEN: usize = 8 + 32 + 1 + 4 + 32 + 4 + (32 * 10) + 1 + 1;
-}
-
-/// Marketplace listing for a card
-#[account]
-pub struct Listing {
-    pub seller: Pubkey,             // Seller wallet
-    pub card_mint: Pubkey,          // NFT mint address
-    pub price: u64,                 // Price in BUG tokens
-    pub is_active: bool,            // true = listed, false = sold/cancelled
-    pub created_at: i64,            // Unix timestamp
-    pub bump: u8,
+    pub player: Signer<'info>,
 }
 
-impl Listing {
-    // 8 (discriminator) + 32 (seller) + 32 (card_mint) + 8 (price) + 1 (is_active) + 8 (created_at) + 1 (bump)
-    pub const LEN: usize = 8 + 32 + 32 + 8 + 1 + 8 + 1;
+#[derive(Accounts)]
+pub struct PruneCollection<'info> {
+    #[account(
+        mut,
+        seeds = [b"collection", collection.owner.as_ref(), &[collection.page]],
+        bump = collection.bump
+    )]
+    pub collection: Account<'info, Collection>,
 }
 
 // ============================================================================
-// Enums
+// Marketplace Instruction Contexts
 // ============================================================================
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
-pub enum TraitType {
-    Warrior,
-    Archer,
-    Assassin,
-}
-
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
-pub enum Rarity {
-    Common,
-    Rare,
-    Legendary,
+#[derive(Accounts)]
+pub struct ListCard<'info> {
+    #[account(
+        init,
+        payer = seller,
+        space = Listing::LEN,
+        seeds = [b"listing", card_mint.key().as_ref()],
+        bump
+    )]
+    pub listing: Account<'info, Listing>,
+    
+    pub card_mint: Account<'info, Mint>,
+    
+    /// Seller's token account holding the NFT
+    #[account(
+        mut,
+        constraint = seller_token_account.owner == seller.key(),
+        constraint = seller_token_account.mint == card_mint.key(),
+        constraint = seller_token_account.amount == 1 @ GameError::Unauthorized
+    )]
+    pub seller_token_account: Account<'info, TokenAccount>,
+    
+    /// Escrow token account (PDA-based, not ATA)
+    #[account(
+        init,
+        payer = seller,
+        seeds = [b"escrow", card_mint.key().as_ref()],
+        bump,
+        token::mint = card_mint,
+        token::authority = listing,
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
 }
 
-impl Rarity {
-    pub fn to_discriminant(&self) -> u8 {
-        match self {
-            Rarity::Common => 0,
-            Rarity::Rare => 1,
-            Rarity::Legendary => 2,
-        }
-    }
+#[derive(Accounts)]
+pub struct CancelListing<'info> {
+    #[account(
+        mut,
+        seeds = [b"listing", card_mint.key().as_ref()],
+        bump = listing.bump,
+        constraint = listing.seller == seller.key() @ GameError::Unauthorized,
+        constraint = listing.is_active @ GameError::ListingNotActive,
+        close = seller  // 关闭账户，租金返还给卖家
+    )]
+    pub listing: Account<'info, Listing>,
+    
+    pub card_mint: Account<'info, Mint>,
+    
+    /// Seller's token account to receive the NFT back
+    #[account(
+        mut,
+        constraint = seller_token_account.owner == seller.key(),
+        constraint = seller_token_account.mint == card_mint.key()
+    )]
+    pub seller_token_account: Account<'info, TokenAccount>,
+    
+    /// Escrow token account (PDA-based, will be closed in instruction)
+    #[account(
+        mut,
+        seeds = [b"escrow", card_mint.key().as_ref()],
+        bump,
+        token::mint = card_mint,
+        token::authority = listing,
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    pub token_program: Program<'info, Token>,
 }
 
-// ============================================================================
-// Error Types
-// ============================================================================
-
-#[error_code]
-pub enum GameError {
-    #[msg("Card type ID already exists")]
-    DuplicateCardTypeId,
+#[derive(Accounts)]
+pub struct BuyCard<'info> {
+    #[account(
+        mut,
+        seeds = [b"listing", card_mint.key().as_ref()],
+        bump = listing.bump,
+        constraint = listing.is_active @ GameError::ListingNotActive,
+        close = seller  // 关闭账户，租金返还给原卖家
+    )]
+    pub listing: Account<'info, Listing>,
     
-    #[msg("Invalid trait type")]
-    InvalidTrait,
+    /// CHECK: Seller account to receive rent refund
+    #[account(mut, constraint = seller.key() == listing.seller)]
+    pub seller: AccountInfo<'info>,
     
-    #[msg("Invalid rarity")]
-    InvalidRarity,
+    /// Buyer's profile (to deduct BUG)
+    #[account(
+        mut,
+        seeds = [b"player_profile", buyer.key().as_ref()],
+        bump = buyer_profile.bump
+    )]
+    pub buyer_profile: Account<'info, PlayerProfile>,
     
-    #[msg("Name or description cannot be empty")]
-    EmptyString,
+    /// Seller's profile (to add BUG)
+    #[account(
+        mut,
+        seeds = [b"player_profile", listing.seller.as_ref()],
+        bump = seller_profile.bump
+    )]
+    pub seller_profile: Account<'info, PlayerProfile>,
     
-    #[msg("Player has already claimed starter pack")]
-    StarterPackAlreadyClaimed,
+    pub card_mint: Account<'info, Mint>,
     
-    #[msg("Insufficient BUG token balance")]
-    InsufficientBalance,
+    /// Card instance to update owner
+    #[account(
+        mut,
+        seeds = [b"card_instance", card_mint.key().as_ref()],
+        bump = card_instance.bump
+    )]
+    pub card_instance: Account<'info, CardInstance>,
     
-    #[msg("Unauthorized access")]
-    Unauthorized,
+    /// Escrow token account (PDA-based, will be closed in instruction)
+    #[account(
+        mut,
+        seeds = [b"escrow", card_mint.key().as_ref()],
+        bump,
+        token::mint = card_mint,
+        token::authority = listing,
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
     
-    #[msg("Invalid username")]
-    InvalidUsername,
+    /// Buyer's token account to receive the NFT
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = card_mint,
+        associated_token::authority = buyer,
+    )]
+    pub buyer_token_account: Account<'info, TokenAccount>,
     
-    #[msg("Rarity pool is empty")]
-    EmptyRarityPool,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
     
-    #[msg("Invalid pack type")]
-    InvalidPackType,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
     
-    #[msg("Numerical overflow")]
-    NumericalOverflow,
+    /// The buyer's collection page to index the card in (optional)
+    #[account(
+        mut,
+        seeds = [b"collection", buyer.key().as_ref(), &[buyer_collection.page]],
+        bump = buyer_collection.bump
+    )]
+    pub buyer_collection: Option<Account<'info, Collection>>,
     
-    #[msg("Card creators list is full")]
-    CardCreatorsListFull,
+    /// The seller's collection page holding the card (optional)
+    #[account(
+        mut,
+        seeds = [b"collection", listing.seller.as_ref(), &[seller_collection.page]],
+        bump = seller_collection.bump
+    )]
+    pub seller_collection: Option<Account<'info, Collection>>,
+}
+
+#[derive(Accounts)]
+pub struct RecordMatchResult<'info> {
+    #[account(
+        mut,
+        seeds = [b"player_profile", winner_profile.wallet.as_ref()],
+        bump = winner_profile.bump
+    )]
+    pub winner_profile: Account<'info, PlayerProfile>,
     
-    #[msg("String exceeds maximum length")]
-    StringTooLong,
+    #[account(
+        mut,
+        seeds = [b"player_profile", loser_profile.wallet.as_ref()],
+        bump = loser_profile.bump,
+        constraint = loser_profile.wallet != winner_profile.wallet @ GameError::SamePlayer
+    )]
+    pub loser_profile: Account<'info, PlayerProfile>,
     
-    #[msg("Invalid stat range: min cannot be greater than max")]
-    InvalidStatRange,
+    #[account(
+        seeds = [b"game_config"],
+        bump = game_config.bump,
+        has_one = authority
+    )]
+    pub game_config: Account<'info, GameConfig>,
     
-    #[msg("Insufficient gacha tickets")]
-    InsufficientTickets,
+    pub authority: Signer<'info>,
     
-    #[msg("Invalid draw count (must be 1-10)")]
-    InvalidDrawCount,
+    /// EconomyConfig::DEFAULTS apply until the authority creates this
+    #[account(
+        seeds = [b"economy_config"],
+        bump = economy_config.bump
+    )]
+    pub economy_config: Option<Account<'info, EconomyConfig>>,
+    
+    /// Updated when passed (see initialize_leaderboard)
+    #[account(
+        mut,
+        seeds = [b"leaderboard"],
+        bump = leaderboard.bump
+    )]
+    pub leaderboard: Option<Account<'info, Leaderboard>>,
+    
+    /// Without it everyone stays Bronze and there are no tier floors
+    #[account(
+        seeds = [b"tier_config"],
+        bump = tier_config.bump
+    )]
+    pub tier_config: Option<Account<'info, TierConfig>>,
+    
+    /// Needed when decks are passed for card XP
+    #[account(
+        seeds = [b"progression_config"],
+        bump = progression_config.bump
+    )]
+    pub progression_config: Option<Account<'info, ProgressionConfig>>,
+}
+
+#[derive(Accounts)]
+pub struct RecordMatchDraw<'info> {
+    #[account(
+        mut,
+        seeds = [b"player_profile", player_a_profile.wallet.as_ref()],
+        bump = player_a_profile.bump
+    )]
+    pub player_a_profile: Account<'info, PlayerProfile>,
     
-    #[msg("Invalid deck index (must be 0-4)")]
-    InvalidDeckIndex,
+    #[account(
+        mut,
+        seeds = [b"player_profile", player_b_profile.wallet.as_ref()],
+        bump = player_b_profile.bump,
+        constraint = player_b_profile.wallet != player_a_profile.wallet @ GameError::SamePlayer
+    )]
+    pub player_b_profile: Account<'info, PlayerProfile>,
     
-    #[msg("Too many cards in deck (max 10)")]
-    TooManyCardsInDeck,
+    #[account(
+        seeds = [b"game_config"],
+        bump = game_config.bump,
+        has_one = authority
+    )]
+    pub game_config: Account<'info, GameConfig>,
     
-    #[msg("Invalid price (must be greater than 0)")]
-    InvalidPrice,
+    pub authority: Signer<'info>,
     
-    #[msg("Listing is not active")]
-    ListingNotActive,
+    /// EconomyConfig::DEFAULTS apply until the authority creates this
+    #[account(
+        seeds = [b"economy_config"],
+        bump = economy_config.bump
+    )]
+    pub economy_config: Option<Account<'info, EconomyConfig>>,
     
-    #[msg("Cannot buy your own card")]
-    CannotBuyOwnCard,
+    /// Updated when passed (see initialize_leaderboard)
+    #[account(
+        mut,
+        seeds = [b"leaderboard"],
+        bump = leaderboard.bump
+    )]
+    pub leaderboard: Option<Account<'info, Leaderboard>>,
     
-    #[msg("Invalid amount (must be greater than 0)")]
-    InvalidAmount,
+    /// Without it everyone stays Bronze and there are no tier floors
+    #[account(
+        seeds = [b"tier_config"],
+        bump = tier_config.bump
+    )]
+    pub tier_config: Option<Account<'info, TierConfig>>,
 }
 
 // ============================================================================
-// Instruction Contexts
+// Leaderboard Instruction Contexts
 // ============================================================================
 
 #[derive(Accounts)]
-pub struct Initialize<'info> {
+pub struct InitializeLeaderboard<'info> {
     #[account(
         init,
         payer = authority,
-        space = GameConfig::LEN,
-        seeds = [b"game_config"],
+        space = Leaderboard::LEN,
+        seeds = [b"leaderboard"],
         bump
     )]
-    pub game_config: Account<'info, GameConfig>,
-    
-    #[account(mut)]
-    pub authority: Signer<'info>,
+    pub leaderboard: Account<'info, Leaderboard>,
     
-    pub system_program: Program<'info, System>,
-}
-
-#[derive(Accounts)]
-pub struct AddCardCreator<'info> {
     #[account(
-        mut,
         seeds = [b"game_config"],
         bump = game_config.bump,
         has_one = authority
     )]
     pub game_config: Account<'info, GameConfig>,
     
+    #[account(mut)]
     pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
 }
 
-
-
 #[derive(Accounts)]
-#[instruction(card_type_id: u32)]
-pub struct CreateCardTemplate<'info> {
+pub struct RerankLeaderboard<'info> {
     #[account(
-        init,
-        payer = creator,
-        space = CardTemplate::LEN,
-        seeds = [b"card_template", card_type_id.to_le_bytes().as_ref()],
-        bump
+        mut,
+        seeds = [b"leaderboard"],
+        bump = leaderboard.bump
     )]
-    pub card_template: Account<'info, CardTemplate>,
+    pub leaderboard: Account<'info, Leaderboard>,
     
     #[account(
         seeds = [b"game_config"],
         bump = game_config.bump
     )]
     pub game_config: Account<'info, GameConfig>,
-    
-    #[account(mut)]
-    pub creator: Signer<'info>,
-    
-    pub system_program: Program<'info, System>,
 }
 
+// ============================================================================
+// Tournament Instruction Contexts
+// ============================================================================
+
 #[derive(Accounts)]
-#[instruction(rarity_discriminant: u8)]
-pub struct UpdateRarityPool<'info> {
+#[instruction(tournament_id: u64)]
+pub struct CreateTournament<'info> {
     #[account(
-        init_if_needed,
+        init,
         payer = authority,
-        space = RarityPool::LEN,
-        seeds = [b"rarity_pool", &[rarity_discriminant][..]],
+        space = Tournament::LEN,
+        seeds = [b"tournament", tournament_id.to_le_bytes().as_ref()],
         bump
     )]
-    pub rarity_pool: Account<'info, RarityPool>,
+    pub tournament: Box<Account<'info, Tournament>>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = TournamentVault::LEN,
+        seeds = [b"tournament_vault", tournament.key().as_ref()],
+        bump
+    )]
+    pub tournament_vault: Account<'info, TournamentVault>,
     
     #[account(
         seeds = [b"game_config"],
@@ -1013,44 +5382,58 @@ pub struct UpdateRarityPool<'info> {
 }
 
 #[derive(Accounts)]
-pub struct RegisterPlayer<'info> {
+pub struct JoinTournament<'info> {
     #[account(
-        init,
-        payer = player,
-        space = PlayerProfile::LEN,
+        mut,
+        seeds = [b"tournament", tournament.tournament_id.to_le_bytes().as_ref()],
+        bump = tournament.bump
+    )]
+    pub tournament: Box<Account<'info, Tournament>>,
+    
+    #[account(
+        mut,
+        seeds = [b"tournament_vault", tournament.key().as_ref()],
+        bump = tournament_vault.bump
+    )]
+    pub tournament_vault: Account<'info, TournamentVault>,
+    
+    #[account(
+        mut,
         seeds = [b"player_profile", player.key().as_ref()],
-        bump
+        bump = player_profile.bump
     )]
     pub player_profile: Account<'info, PlayerProfile>,
     
-    #[account(mut)]
     pub player: Signer<'info>,
-    
-    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct ClaimStarterTickets<'info> {
+pub struct StartTournament<'info> {
     #[account(
         mut,
-        seeds = [b"player_profile", player.key().as_ref()],
-        bump = player_profile.bump,
-        constraint = !player_profile.has_claimed_starter_pack @ GameError::StarterPackAlreadyClaimed
+        seeds = [b"tournament", tournament.tournament_id.to_le_bytes().as_ref()],
+        bump = tournament.bump
     )]
-    pub player_profile: Account<'info, PlayerProfile>,
+    pub tournament: Box<Account<'info, Tournament>>,
     
-    #[account(mut)]
-    pub player: Signer<'info>,
+    #[account(
+        seeds = [b"game_config"],
+        bump = game_config.bump,
+        has_one = authority
+    )]
+    pub game_config: Account<'info, GameConfig>,
+    
+    pub authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct GachaDraw<'info> {
+pub struct ReportTournamentMatch<'info> {
     #[account(
         mut,
-        seeds = [b"player_profile", player.key().as_ref()],
-        bump = player_profile.bump
+        seeds = [b"tournament", tournament.tournament_id.to_le_bytes().as_ref()],
+        bump = tournament.bump
     )]
-    pub player_profile: Account<'info, PlayerProfile>,
+    pub tournament: Box<Account<'info, Tournament>>,
     
     #[account(
         seeds = [b"game_config"],
@@ -1058,101 +5441,195 @@ pub struct GachaDraw<'info> {
     )]
     pub game_config: Account<'info, GameConfig>,
     
-    /// The card template to mint (client picks based on rarity roll)
+    /// Authority, or one of the two players when co-signed
+    pub reporter: Signer<'info>,
+    
+    /// The other player of the match (omit when the authority reports)
+    pub co_signer: Option<Signer<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct DistributeTournamentPrizes<'info> {
     #[account(
-        seeds = [b"card_template", card_template.card_type_id.to_le_bytes().as_ref()],
-        bump = card_template.bump
+        mut,
+        seeds = [b"tournament", tournament.tournament_id.to_le_bytes().as_ref()],
+        bump = tournament.bump
     )]
-    pub card_template: Account<'info, CardTemplate>,
+    pub tournament: Box<Account<'info, Tournament>>,
     
-    /// New mint account for the NFT card (initialized by client with game_config as mint authority)
     #[account(
         mut,
-        constraint = card_mint.mint_authority.unwrap() == game_config.key() @ GameError::Unauthorized
+        seeds = [b"tournament_vault", tournament.key().as_ref()],
+        bump = tournament_vault.bump
     )]
-    pub card_mint: Account<'info, Mint>,
+    pub tournament_vault: Account<'info, TournamentVault>,
+}
+
+#[derive(Accounts)]
+pub struct CancelTournament<'info> {
+    #[account(
+        mut,
+        seeds = [b"tournament", tournament.tournament_id.to_le_bytes().as_ref()],
+        bump = tournament.bump
+    )]
+    pub tournament: Box<Account<'info, Tournament>>,
     
-    /// Player's token account for this card mint
     #[account(
-        init,
-        payer = player,
-        associated_token::mint = card_mint,
-        associated_token::authority = player,
+        mut,
+        seeds = [b"tournament_vault", tournament.key().as_ref()],
+        bump = tournament_vault.bump
     )]
-    pub player_card_token_account: Account<'info, TokenAccount>,
+    pub tournament_vault: Account<'info, TournamentVault>,
+    
+    #[account(
+        seeds = [b"game_config"],
+        bump = game_config.bump,
+        has_one = authority
+    )]
+    pub game_config: Account<'info, GameConfig>,
     
-    /// Card instance PDA to store rolled stats
+    pub authority: Signer<'info>,
+}
+
+// ============================================================================
+// Season Instruction Contexts
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(season_id: u32)]
+pub struct RollOverSeason<'info> {
     #[account(
         init,
-        payer = player,
-        space = CardInstance::LEN,
-        seeds = [b"card_instance", card_mint.key().as_ref()],
+        payer = authority,
+        space = Season::LEN,
+        seeds = [b"season", season_id.to_le_bytes().as_ref()],
         bump
     )]
-    pub card_instance: Account<'info, CardInstance>,
+    pub season: Account<'info, Season>,
     
+    /// Current season to close out (omit while still in the pre-season)
     #[account(mut)]
-    pub player: Signer<'info>,
+    pub previous_season: Option<Account<'info, Season>>,
+    
+    #[account(
+        mut,
+        seeds = [b"game_config"],
+        bump = game_config.bump,
+        has_one = authority
+    )]
+    pub game_config: Account<'info, GameConfig>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
     
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
-pub struct AddGachaTickets<'info> {
+#[instruction(season_id: u32)]
+pub struct ClaimSeasonRewards<'info> {
     #[account(
         mut,
-        seeds = [b"player_profile", player_profile.wallet.as_ref()],
+        seeds = [b"player_profile", player.key().as_ref()],
         bump = player_profile.bump
     )]
     pub player_profile: Account<'info, PlayerProfile>,
     
+    #[account(
+        seeds = [b"season", season_id.to_le_bytes().as_ref()],
+        bump = season.bump
+    )]
+    pub season: Account<'info, Season>,
+    
+    /// Created on claim, so each season can only be claimed once
+    #[account(
+        init,
+        payer = player,
+        space = PlayerSeasonRecord::LEN,
+        seeds = [b"player_season", player.key().as_ref(), season_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub season_record: Account<'info, PlayerSeasonRecord>,
+    
     #[account(
         seeds = [b"game_config"],
-        bump = game_config.bump,
-        has_one = authority
+        bump = game_config.bump
     )]
     pub game_config: Account<'info, GameConfig>,
     
-    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
 }
 
+// ============================================================================
+// Banner Instruction Contexts
+// ============================================================================
+
 #[derive(Accounts)]
-pub struct BuyBugTokens<'info> {
+#[instruction(banner_id: u32)]
+pub struct CreateBanner<'info> {
     #[account(
-        seeds = [b"game_config"],
-        bump = game_config.bump
+        init,
+        payer = authority,
+        space = Banner::LEN,
+        seeds = [b"banner", banner_id.to_le_bytes().as_ref()],
+        bump
     )]
-    pub game_config: Account<'info, GameConfig>,
+    pub banner: Account<'info, Banner>,
     
     #[account(
-        mut,
-        seeds = [b"player_profile", player.key().as_ref()],
-        bump = player_profile.bump
+        seeds = [b"game_config"],
+        bump = game_config.bump,
+        has_one = authority
     )]
-    pub player_profile: Account<'info, PlayerProfile>,
-    
-    /// Treasury to receive SOL
-    /// CHECK: This is the treasury wallet to receive SOL payments
-    #[account(mut)]
-    pub treasury: AccountInfo<'info>,
+    pub game_config: Account<'info, GameConfig>,
     
     #[account(mut)]
-    pub player: Signer<'info>,
+    pub authority: Signer<'info>,
     
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct BuyGachaTickets<'info> {
+pub struct EndBanner<'info> {
+    #[account(
+        mut,
+        seeds = [b"banner", banner.banner_id.to_le_bytes().as_ref()],
+        bump = banner.bump
+    )]
+    pub banner: Account<'info, Banner>,
+    
     #[account(
         seeds = [b"game_config"],
-        bump = game_config.bump
+        bump = game_config.bump,
+        has_one = authority
     )]
     pub game_config: Account<'info, GameConfig>,
     
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct BannerDraw<'info> {
+    #[account(
+        mut,
+        seeds = [b"banner", banner.banner_id.to_le_bytes().as_ref()],
+        bump = banner.bump
+    )]
+    pub banner: Box<Account<'info, Banner>>,
+    
+    /// The player's pull count on this banner (created on the first pull)
+    #[account(
+        init_if_needed,
+        payer = player,
+        space = BannerPulls::LEN,
+        seeds = [b"banner_pulls", banner.key().as_ref(), player.key().as_ref()],
+        bump
+    )]
+    pub banner_pulls: Account<'info, BannerPulls>,
+    
     #[account(
         mut,
         seeds = [b"player_profile", player.key().as_ref()],
@@ -1160,355 +5637,1251 @@ pub struct BuyGachaTickets<'info> {
     )]
     pub player_profile: Account<'info, PlayerProfile>,
     
+    /// The paid pull, until its card is claimed (fails while another pull is pending)
+    #[account(
+        init,
+        payer = player,
+        space = PendingPull::LEN,
+        seeds = [b"pending_pull", player.key().as_ref()],
+        bump
+    )]
+    pub pending_pull: Account<'info, PendingPull>,
+    
+    #[account(mut)]
     pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct RollGacha<'info> {
+pub struct RevealBannerPull<'info> {
     #[account(
-        seeds = [b"rarity_pool", &[Rarity::Common.to_discriminant()]],
-        bump = rarity_pool_common.bump
-    )]
-    pub rarity_pool_common: Account<'info, RarityPool>,
-    
-    #[account(
-        seeds = [b"rarity_pool", &[Rarity::Rare.to_discriminant()]],
-        bump = rarity_pool_rare.bump
+        mut,
+        seeds = [b"pending_pull", pending_pull.player.as_ref()],
+        bump = pending_pull.bump
     )]
-    pub rarity_pool_rare: Account<'info, RarityPool>,
+    pub pending_pull: Account<'info, PendingPull>,
     
     #[account(
-        seeds = [b"rarity_pool", &[Rarity::Legendary.to_discriminant()]],
-        bump = rarity_pool_legendary.bump
+        seeds = [b"banner", banner.banner_id.to_le_bytes().as_ref()],
+        bump = banner.bump
     )]
-    pub rarity_pool_legendary: Account<'info, RarityPool>,
+    pub banner: Box<Account<'info, Banner>>,
     
-    pub player: Signer<'info>,
+    /// CHECK: the SlotHashes sysvar, read raw (too large to deserialize)
+    #[account(address = slot_hashes::ID)]
+    pub slot_hashes: UncheckedAccount<'info>,
 }
 
+// ============================================================================
+// Migration Instruction Contexts
+// ============================================================================
+
 #[derive(Accounts)]
-pub struct PurchasePack<'info> {
+pub struct MigrateCardInstance<'info> {
+    /// CHECK: A CardInstance in any earlier layout; owner and discriminator are checked in grow_account
+    #[account(mut)]
+    pub card_instance: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(rarity_discriminant: u8)]
+pub struct MigrateRarityPool<'info> {
+    /// CHECK: A standard RarityPool in its original layout; the layout is checked in the handler
     #[account(
         mut,
-        seeds = [b"player_profile", player.key().as_ref()],
-        bump = player_profile.bump
+        seeds = [b"rarity_pool", &[rarity_discriminant][..]],
+        bump
     )]
-    pub player_profile: Account<'info, PlayerProfile>,
+    pub rarity_pool: UncheckedAccount<'info>,
     
     #[account(
         seeds = [b"game_config"],
-        bump = game_config.bump
+        bump = game_config.bump,
+        has_one = authority
     )]
     pub game_config: Account<'info, GameConfig>,
     
-    #[account(
-        seeds = [b"rarity_pool", &[Rarity::Common.to_discriminant()]],
-        bump = rarity_pool_common.bump
-    )]
-    pub rarity_pool_common: Account<'info, RarityPool>,
-    
-    #[account(
-        seeds = [b"rarity_pool", &[Rarity::Rare.to_discriminant()]],
-        bump = rarity_pool_rare.bump
-    )]
-    pub rarity_pool_rare: Account<'info, RarityPool>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
     
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateGameConfig<'info> {
+    /// CHECK: The GameConfig in any earlier layout; the authority is checked in the handler
     #[account(
-        seeds = [b"rarity_pool", &[Rarity::Legendary.to_discriminant()]],
-        bump = rarity_pool_legendary.bump
+        mut,
+        seeds = [b"game_config"],
+        bump
     )]
-    pub rarity_pool_legendary: Account<'info, RarityPool>,
+    pub game_config: UncheckedAccount<'info>,
     
     #[account(mut)]
-    pub player: Signer<'info>,
+    pub authority: Signer<'info>,
     
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(deck_index: u8)]
-pub struct SaveDeck<'info> {
+pub struct MigrateCardTemplate<'info> {
+    /// CHECK: A CardTemplate in any earlier layout; owner and discriminator are checked in grow_account
+    #[account(mut)]
+    pub card_template: UncheckedAccount<'info>,
+    
     #[account(
-        init_if_needed,
-        payer = player,
-        space = PlayerDeck::LEN,
-        seeds = [b"player_deck", player.key().as_ref(), &[deck_index]],
-        bump
+        seeds = [b"game_config"],
+        bump = game_config.bump
     )]
-    pub player_deck: Account<'info, PlayerDeck>,
+    pub game_config: Account<'info, GameConfig>,
     
     #[account(mut)]
-    pub player: Signer<'info>,
+    pub payer: Signer<'info>,
     
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(deck_index: u8)]
-pub struct DeleteDeck<'info> {
+pub struct MigratePlayerProfile<'info> {
+    /// CHECK: A PlayerProfile in any earlier layout; owner and discriminator are checked in grow_account
+    #[account(mut)]
+    pub player_profile: UncheckedAccount<'info>,
+    
     #[account(
-        mut,
-        seeds = [b"player_deck", player.key().as_ref(), &[deck_index]],
-        bump = player_deck.bump,
-        constraint = player_deck.owner == player.key() @ GameError::Unauthorized
+        seeds = [b"game_config"],
+        bump = game_config.bump
     )]
-    pub player_deck: Account<'info, PlayerDeck>,
+    pub game_config: Account<'info, GameConfig>,
     
-    pub player: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
 }
 
 // ============================================================================
-// Marketplace Instruction Contexts
+// Helper Functions
 // ============================================================================
 
-#[derive(Accounts)]
-pub struct ListCard<'info> {
-    #[account(
-        init,
-        payer = seller,
-        space = Listing::LEN,
-        seeds = [b"listing", card_mint.key().as_ref()],
-        bump
-    )]
-    pub listing: Account<'info, Listing>,
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::solana_program::clock::Clock;
+
+/// Generate pseudo-random number using slot hash, clock, and player pubkey
+pub fn generate_random_u64(clock: &Clock, player: &Pubkey, salt: u64) -> u64 {
+    let mut data = Vec::new();
+    data.extend_from_slice(&clock.slot.to_le_bytes());
+    data.extend_from_slice(&clock.unix_timestamp.to_le_bytes());
+    data.extend_from_slice(player.as_ref());
+    data.extend_from_slice(&salt.to_le_bytes());
     
-    pub card_mint: Account<'info, Mint>,
+    let hash_result = hash(&data);
+    u64::from_le_bytes(hash_result.to_bytes()[0..8].try_into().unwrap())
+}
+
+/// Roll for rarity based on probabilities
+/// Common: 70%, Rare: 27%, Legendary: 3%
+pub fn roll_rarity(random_value: u64) -> Rarity {
+    let roll = (random_value % 100) as u8;
     
-    /// Seller's token account holding the NFT
-    #[account(
-        mut,
-        constraint = seller_token_account.owner == seller.key(),
-        constraint = seller_token_account.mint == card_mint.key(),
-        constraint = seller_token_account.amount == 1 @ GameError::Unauthorized
-    )]
-    pub seller_token_account: Account<'info, TokenAccount>,
+    if roll < 70 {
+        Rarity::Common
+    } else if roll < 97 {  // 70 + 27
+        Rarity::Rare
+    } else {
+        Rarity::Legendary
+    }
+}
+
+/// Select random card from rarity pool, proportionally to entry weights
+pub fn select_random_card(rarity_pool: &RarityPool, random_value: u64) -> Result<u32> {
+    select_weighted_card(&rarity_pool.entries, random_value)
+}
+
+/// Roll a gacha card: a rarity, then a weighted card of that rarity's pool
+/// (pools in Common, Rare, Legendary order)
+pub fn roll_gacha_card(pools: [&RarityPool; 3], random_value: u64) -> Result<u32> {
+    let rarity = roll_rarity(random_value);
+    select_random_card(pools[rarity.to_discriminant() as usize], card_roll(random_value))
+}
+
+/// The part of a random value left after roll_rarity used `% 100`, so the card
+/// picked within a rarity doesn't depend on which rarity came up
+pub fn card_roll(random_value: u64) -> u64 {
+    random_value / 100
+}
+
+/// Salt added to a card's index for its stats roll, so stats don't follow the card roll
+pub const PULL_STATS_SALT: u64 = 1000;
+
+/// Hash of `slot` in the raw SlotHashes sysvar data: a u64 count, then (slot, hash)
+/// entries, newest first. None once the slot has aged out (about 512 slots later).
+pub fn find_slot_hash(slot_hashes: &[u8], slot: u64) -> Option<[u8; 32]> {
+    const ENTRY_LEN: usize = 8 + 32;
+    let count = u64::from_le_bytes(slot_hashes.get(..8)?.try_into().ok()?) as usize;
+    let entry = |index: usize| slot_hashes.get(8 + index * ENTRY_LEN..8 + (index + 1) * ENTRY_LEN);
     
-    /// Escrow token account (PDA-based, not ATA)
-    #[account(
-        init,
-        payer = seller,
-        seeds = [b"escrow", card_mint.key().as_ref()],
-        bump,
-        token::mint = card_mint,
-        token::authority = listing,
-    )]
-    pub escrow_token_account: Account<'info, TokenAccount>,
+    let (mut low, mut high) = (0, count);
+    while low < high {
+        let middle = (low + high) / 2;
+        let entry = entry(middle)?;
+        let entry_slot = u64::from_le_bytes(entry[..8].try_into().ok()?);
+        if entry_slot == slot {
+            return entry[8..].try_into().ok();
+        } else if entry_slot > slot {
+            low = middle + 1;
+        } else {
+            high = middle;
+        }
+    }
+    None
+}
+
+/// Seed of a pending pull being revealed at `current_slot`: the hash of the slot it
+/// was paid in, mixed with the player so pulls paid in the same slot differ. If that
+/// hash has aged out the seed only depends on the pull, and `true` is returned so the
+/// cards are rolled as the most common outcome (see pull_card_random).
+pub fn pull_seed(pending_pull: &PendingPull, slot_hashes: &[u8], current_slot: u64) -> Result<([u8; 32], bool)> {
+    require!(pending_pull.card_type_ids.is_empty(), GameError::PullAlreadyRevealed);
+    require!(current_slot > pending_pull.commit_slot, GameError::PullNotReady);
     
-    #[account(mut)]
-    pub seller: Signer<'info>,
+    let slot_hash = find_slot_hash(slot_hashes, pending_pull.commit_slot);
+    let mut data = Vec::new();
+    data.extend_from_slice(&slot_hash.unwrap_or_default());
+    data.extend_from_slice(&pending_pull.commit_slot.to_le_bytes());
+    data.extend_from_slice(pending_pull.player.as_ref());
+    Ok((hash(&data).to_bytes(), slot_hash.is_none()))
+}
+
+/// Random value derived from a pull's seed
+pub fn seed_random_u64(seed: &[u8; 32], salt: u64) -> u64 {
+    let mut data = seed.to_vec();
+    data.extend_from_slice(&salt.to_le_bytes());
+    u64::from_le_bytes(hash(&data).to_bytes()[0..8].try_into().unwrap())
+}
+
+/// Random value rolling card `index` of a pull. A pull revealed after its slot hash
+/// aged out keeps its card roll but rolls Common, so waiting never beats revealing.
+pub fn pull_card_random(seed: &[u8; 32], index: u8, expired: bool) -> u64 {
+    let random_value = seed_random_u64(seed, index as u64);
+    if expired {
+        random_value - random_value % 100
+    } else {
+        random_value
+    }
+}
+
+/// Random value rolling the stats of card `index` of a pull. The zero seed of a pull
+/// revealed after its slot hash aged out rolls minimum stats (see PendingPull::reveal).
+pub fn pull_stats_random(seed: &[u8; 32], index: u8) -> u64 {
+    if *seed == [0; 32] {
+        return 0;
+    }
+    seed_random_u64(seed, index as u64 + PULL_STATS_SALT)
+}
+
+/// Select a card from weighted entries (rarity pool or banner drop table)
+pub fn select_weighted_card(entries: &[PoolEntry], random_value: u64) -> Result<u32> {
+    let total_weight: u64 = entries.iter().map(|entry| entry.weight as u64).sum();
+    require!(total_weight > 0, GameError::EmptyRarityPool);
     
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
+    let mut roll = random_value % total_weight;
+    for entry in entries.iter() {
+        if roll < entry.weight as u64 {
+            return Ok(entry.card_type_id);
+        }
+        roll -= entry.weight as u64;
+    }
+    Err(GameError::EmptyRarityPool.into())
 }
 
-#[derive(Accounts)]
-pub struct CancelListing<'info> {
-    #[account(
-        mut,
-        seeds = [b"listing", card_mint.key().as_ref()],
-        bump = listing.bump,
-        constraint = listing.seller == seller.key() @ GameError::Unauthorized,
-        constraint = listing.is_active @ GameError::ListingNotActive,
-        close = seller  // 关闭账户，租金返还给卖家
-    )]
-    pub listing: Account<'info, Listing>,
+/// Check that `templates[i]` is the template of `card_type_ids[i]`, is of the
+/// pool's rarity and isn't retired
+pub fn validate_pool_templates(rarity: Rarity, card_type_ids: &[u32], templates: &[CardTemplate]) -> Result<()> {
+    require!(card_type_ids.len() == templates.len(), GameError::TemplateAccountMismatch);
+    
+    for (card_type_id, template) in card_type_ids.iter().zip(templates.iter()) {
+        require!(template.card_type_id == *card_type_id, GameError::TemplateAccountMismatch);
+        require!(template.rarity == rarity, GameError::RarityMismatch);
+        require!(!template.is_retired, GameError::CardTemplateRetired);
+    }
+    Ok(())
+}
+
+/// Check that every template belongs to card set `set_id`
+pub fn validate_set_templates(set_id: u16, templates: &[CardTemplate]) -> Result<()> {
+    for template in templates.iter() {
+        require!(template.set_id == set_id, GameError::SetMismatch);
+    }
+    Ok(())
+}
+
+/// Set packs are on sale while the set is active and released
+pub fn is_card_set_available(card_set: &CardSet, now: i64) -> bool {
+    card_set.is_active && now >= card_set.release_time
+}
+
+/// Deserialize the CardTemplate accounts passed in remaining_accounts
+pub fn load_card_templates<'info>(
+    remaining_accounts: &'info [AccountInfo<'info>],
+    expected: usize,
+) -> Result<Vec<CardTemplate>> {
+    require!(remaining_accounts.len() == expected, GameError::TemplateAccountMismatch);
+    
+    let mut templates = Vec::with_capacity(expected);
+    for account_info in remaining_accounts.iter() {
+        templates.push(Account::<CardTemplate>::try_from(account_info)?.into_inner());
+    }
+    Ok(templates)
+}
+
+/// Append entries for cards that aren't in the pool yet, up to RarityPool::MAX_CARDS,
+/// and update the weight of those that are. Returns how many were added.
+pub fn add_to_pool(rarity_pool: &mut RarityPool, entries: &[PoolEntry]) -> Result<u32> {
+    let mut added = 0;
+    for entry in entries.iter() {
+        require!(entry.weight > 0, GameError::InvalidPoolWeight);
+        
+        match rarity_pool.entries.iter_mut().find(|e| e.card_type_id == entry.card_type_id) {
+            Some(existing) => existing.weight = entry.weight,
+            None => {
+                require!(
+                    rarity_pool.entries.len() < RarityPool::MAX_CARDS,
+                    GameError::RarityPoolFull
+                );
+                rarity_pool.entries.push(*entry);
+                added += 1;
+            }
+        }
+    }
+    Ok(added)
+}
+
+/// Rarity of pack card `slot`: the last card is raised to the pack's guaranteed minimum
+pub fn pack_slot_rarity(rolled: Rarity, slot: u8, card_count: u8, guaranteed_rarity: Rarity) -> Rarity {
+    let is_last = slot + 1 == card_count;
+    if is_last && rolled.to_discriminant() < guaranteed_rarity.to_discriminant() {
+        guaranteed_rarity
+    } else {
+        rolled
+    }
+}
+
+/// Rarities a pack slot may draw from, best first: the rolled one, then lower ones
+/// down to the slot's minimum, then higher ones
+pub fn pack_slot_fallbacks(rolled: Rarity, min_rarity: Rarity) -> Vec<Rarity> {
+    const RARITIES: [Rarity; 3] = [Rarity::Common, Rarity::Rare, Rarity::Legendary];
+    let rolled_index = rolled.to_discriminant() as usize;
+    let min_index = min_rarity.to_discriminant() as usize;
+    
+    let mut fallbacks = vec![rolled];
+    fallbacks.extend((min_index..rolled_index).rev().map(|index| RARITIES[index]));
+    fallbacks.extend(RARITIES[rolled_index + 1..].iter().copied());
+    fallbacks
+}
+
+/// Roll the cards of a pack from its rarity pools (Common, Rare, Legendary), slot `i`
+/// using `random_value(i)`. A slot whose rarity has no card the pack may contain
+/// draws from the closest rarity that has one (see pack_slot_fallbacks).
+pub fn roll_pack_cards(
+    pack: &PackType,
+    pools: [&RarityPool; 3],
+    random_value: impl Fn(u64) -> u64,
+) -> Result<Vec<u32>> {
+    let eligible = pack.trait_filter.map(|_| pack.card_type_ids.as_slice());
+    let mut card_type_ids = Vec::with_capacity(pack.card_count as usize);
+    
+    for slot in 0..pack.card_count {
+        let random_value = random_value(slot as u64);
+        let rolled = pack_slot_rarity(roll_rarity(random_value), slot, pack.card_count, pack.guaranteed_rarity);
+        let min_rarity = pack_slot_rarity(Rarity::Common, slot, pack.card_count, pack.guaranteed_rarity);
+        let entries = pack_slot_fallbacks(rolled, min_rarity)
+            .into_iter()
+            .map(|rarity| eligible_pool_entries(&pools[rarity.to_discriminant() as usize].entries, eligible))
+            .find(|entries| !entries.is_empty())
+            .ok_or(GameError::EmptyRarityPool)?;
+        card_type_ids.push(select_weighted_card(&entries, card_roll(random_value))?);
+    }
+    
+    Ok(card_type_ids)
+}
+
+/// Create the CardInstance PDA of a card minted through remaining_accounts
+/// (instructions minting a single card use an `init` account instead)
+pub fn create_card_instance<'info>(
+    account: &AccountInfo<'info>,
+    mut card_instance: CardInstance,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    let (address, bump) = Pubkey::find_program_address(
+        &[b"card_instance", card_instance.mint.as_ref()],
+        &crate::ID,
+    );
+    require!(account.key() == address, GameError::InvalidPackAccounts);
+    card_instance.bump = bump;
+    
+    let create_ix = anchor_lang::solana_program::system_instruction::create_account(
+        payer.key,
+        &address,
+        Rent::get()?.minimum_balance(CardInstance::LEN),
+        CardInstance::LEN as u64,
+        &crate::ID,
+    );
+    anchor_lang::solana_program::program::invoke_signed(
+        &create_ix,
+        &[payer.to_account_info(), account.clone(), system_program.to_account_info()],
+        &[&[b"card_instance", card_instance.mint.as_ref(), &[bump]]],
+    )?;
+    
+    let mut data = account.try_borrow_mut_data()?;
+    card_instance.try_serialize(&mut &mut data[..])
+}
+
+/// Pool entries a pack may draw; `eligible` limits them to those card ids (None = all)
+pub fn eligible_pool_entries(entries: &[PoolEntry], eligible: Option<&[u32]>) -> Vec<PoolEntry> {
+    match eligible {
+        Some(card_type_ids) => entries
+            .iter()
+            .filter(|entry| card_type_ids.contains(&entry.card_type_id))
+            .copied()
+            .collect(),
+        None => entries.to_vec(),
+    }
+}
+
+/// Check a banner's drop table: 1-MAX_ENTRIES cards with positive weights and no
+/// duplicates, where `templates[i]` is the live template of `entries[i]`
+pub fn validate_banner_entries(entries: &[PoolEntry], templates: &[CardTemplate]) -> Result<()> {
+    require!(
+        !entries.is_empty() && entries.len() <= Banner::MAX_ENTRIES,
+        GameError::InvalidBannerEntries
+    );
+    require!(entries.len() == templates.len(), GameError::TemplateAccountMismatch);
+    
+    for (i, (entry, template)) in entries.iter().zip(templates.iter()).enumerate() {
+        require!(entry.weight > 0, GameError::InvalidPoolWeight);
+        require!(
+            !entries[..i].iter().any(|e| e.card_type_id == entry.card_type_id),
+            GameError::InvalidBannerEntries
+        );
+        require!(template.card_type_id == entry.card_type_id, GameError::TemplateAccountMismatch);
+        require!(!template.is_retired, GameError::CardTemplateRetired);
+    }
+    Ok(())
+}
+
+/// A banner takes pulls from start_time until (not including) end_time
+pub fn is_banner_active(banner: &Banner, now: i64) -> bool {
+    now >= banner.start_time && now < banner.end_time
+}
+
+/// Card of a banner pull, where `templates[i]` is the template of `entries[i]`: a
+/// weighted pick from the drop table's cards that aren't retired, or once the pull's
+/// slot hash has aged out the most common of them of the lowest rarity on the banner
+/// (the first on a tie), so waiting never beats revealing
+pub fn roll_banner_card(
+    entries: &[PoolEntry],
+    templates: &[CardTemplate],
+    random_value: u64,
+    expired: bool,
+) -> Result<u32> {
+    require!(entries.len() == templates.len(), GameError::TemplateAccountMismatch);
+    for (entry, template) in entries.iter().zip(templates.iter()) {
+        require!(template.card_type_id == entry.card_type_id, GameError::TemplateAccountMismatch);
+    }
+    let (live_entries, live_templates): (Vec<PoolEntry>, Vec<&CardTemplate>) = entries
+        .iter()
+        .zip(templates.iter())
+        .filter(|(_, template)| !template.is_retired)
+        .map(|(entry, template)| (*entry, template))
+        .unzip();
+    require!(!live_entries.is_empty(), GameError::CardTemplateRetired);
+    if !expired {
+        return select_weighted_card(&live_entries, random_value);
+    }
+    
+    let lowest_rarity = live_templates
+        .iter()
+        .map(|template| template.rarity.to_discriminant())
+        .min()
+        .ok_or(GameError::EmptyRarityPool)?;
+    live_entries
+        .iter()
+        .zip(live_templates.iter())
+        .filter(|(_, template)| template.rarity.to_discriminant() == lowest_rarity)
+        .rev()
+        .max_by_key(|(entry, _)| entry.weight)
+        .map(|(entry, _)| entry.card_type_id)
+        .ok_or(GameError::EmptyRarityPool.into())
+}
+
+/// Remove card ids from a rarity pool, keeping the order of the rest.
+/// Returns how many were removed.
+pub fn remove_from_pool(rarity_pool: &mut RarityPool, card_type_ids: &[u32]) -> u32 {
+    let before = rarity_pool.entries.len();
+    rarity_pool.entries.retain(|entry| !card_type_ids.contains(&entry.card_type_id));
+    (before - rarity_pool.entries.len()) as u32
+}
+
+/// Fixed-point scale for Elo expected scores (10_000 = 100%)
+pub const ELO_SCALE: u64 = 10_000;
+
+/// Expected score of the weaker player for rating gaps of 0, 50, 100, ... 800,
+/// i.e. 1 / (1 + 10^(gap / 400)) scaled by ELO_SCALE
+const ELO_EXPECTED_TABLE: [u64; 17] = [
+    5000, 4285, 3599, 2966, 2403, 1917, 1510, 1177, 909,
+    698, 532, 405, 307, 232, 175, 132, 99,
+];
+
+/// Expected score of a player against an opponent, scaled by ELO_SCALE
+/// Uses linear interpolation over ELO_EXPECTED_TABLE; gaps beyond 800 are clamped
+pub fn elo_expected_score(rating: u32, opponent_rating: u32) -> u64 {
+    let gap = (rating as i64 - opponent_rating as i64).unsigned_abs().min(800);
+    let index = (gap / 50) as usize;
+    let remainder = gap % 50;
+    
+    let lower = ELO_EXPECTED_TABLE[index];
+    let upper = ELO_EXPECTED_TABLE[(index + 1).min(ELO_EXPECTED_TABLE.len() - 1)];
+    let weaker_expected = lower - (lower - upper) * remainder / 50;
+    
+    if rating >= opponent_rating {
+        ELO_SCALE - weaker_expected
+    } else {
+        weaker_expected
+    }
+}
+
+/// Rating points the winner gains (and the loser loses) for a decisive match
+/// change = K * (1 - expected_winner), rounded, at least 1
+pub fn calculate_elo_change(winner_rating: u32, loser_rating: u32) -> u32 {
+    let expected = elo_expected_score(winner_rating, loser_rating);
+    let k = PlayerProfile::ELO_K_FACTOR as u64;
+    let change = (k * (ELO_SCALE - expected) + ELO_SCALE / 2) / ELO_SCALE;
+    change.max(1) as u32
+}
+
+/// Rating change for player A when drawing against player B
+/// change = K * (0.5 - expected_a), rounded; positive when A was the underdog
+pub fn calculate_elo_draw_change(rating_a: u32, rating_b: u32) -> i64 {
+    let expected = elo_expected_score(rating_a, rating_b) as i64;
+    let k = PlayerProfile::ELO_K_FACTOR as i64;
+    let scale = ELO_SCALE as i64;
+    let numerator = k * (scale / 2 - expected);
+    
+    // Round half away from zero so the change stays symmetric
+    if numerator >= 0 {
+        (numerator + scale / 2) / scale
+    } else {
+        (numerator - scale / 2) / scale
+    }
+}
+
+/// Apply a signed rating change, clamping at 0
+pub fn apply_rating_change(rating: u32, change: i64) -> Result<u32> {
+    let updated = (rating as i64).checked_add(change).ok_or(GameError::NumericalOverflow)?;
+    u32::try_from(updated.max(0)).map_err(|_| GameError::NumericalOverflow.into())
+}
+
+/// Trophies for a win: base gain plus win streak bonus (capped if configured)
+pub fn calculate_trophy_gain(economy_config: &EconomyConfig, win_streak: u32) -> Result<u32> {
+    let streak_bonus = if economy_config.max_streak_bonus > 0 {
+        win_streak.min(economy_config.max_streak_bonus)
+    } else {
+        win_streak
+    };
     
-    pub card_mint: Account<'info, Mint>,
+    Ok(economy_config.base_trophy_gain
+        .checked_add(streak_bonus)
+        .ok_or(GameError::NumericalOverflow)?)
+}
+
+/// Days since the Unix epoch, used to reset the daily reward cap
+pub fn unix_day(unix_timestamp: i64) -> u32 {
+    (unix_timestamp.max(0) / 86_400) as u32
+}
+
+/// Credit a match reward, clamped by the daily cap. Returns the amount paid.
+pub fn grant_match_reward(
+    profile: &mut PlayerProfile,
+    amount: u64,
+    economy_config: &EconomyConfig,
+    today: u32,
+) -> Result<u64> {
+    if profile.reward_day != today {
+        profile.reward_day = today;
+        profile.daily_bug_earned = 0;
+    }
     
-    /// Seller's token account to receive the NFT back
-    #[account(
-        mut,
-        constraint = seller_token_account.owner == seller.key(),
-        constraint = seller_token_account.mint == card_mint.key()
-    )]
-    pub seller_token_account: Account<'info, TokenAccount>,
+    let granted = if economy_config.daily_reward_cap > 0 {
+        amount.min(economy_config.daily_reward_cap.saturating_sub(profile.daily_bug_earned))
+    } else {
+        amount
+    };
     
-    /// Escrow token account (PDA-based, will be closed in instruction)
-    #[account(
-        mut,
-        seeds = [b"escrow", card_mint.key().as_ref()],
-        bump,
-        token::mint = card_mint,
-        token::authority = listing,
-    )]
-    pub escrow_token_account: Account<'info, TokenAccount>,
+    profile.daily_bug_earned = profile.daily_bug_earned
+        .checked_add(granted)
+        .ok_or(GameError::NumericalOverflow)?;
+    profile.bug_balance = profile.bug_balance
+        .checked_add(granted)
+        .ok_or(GameError::NumericalOverflow)?;
     
-    #[account(mut)]
-    pub seller: Signer<'info>,
-    pub token_program: Program<'info, Token>,
+    Ok(granted)
 }
 
-#[derive(Accounts)]
-pub struct BuyCard<'info> {
-    #[account(
-        mut,
-        seeds = [b"listing", card_mint.key().as_ref()],
-        bump = listing.bump,
-        constraint = listing.is_active @ GameError::ListingNotActive,
-        close = seller  // 关闭账户，租金返还给原卖家
-    )]
-    pub listing: Account<'info, Listing>,
+/// Insert or move a player on the leaderboard after their trophies changed
+/// Keeps entries sorted by trophies (descending, ties keep the earlier entry first)
+/// and drops anyone pushed past MAX_ENTRIES.
+pub fn update_leaderboard(leaderboard: &mut Leaderboard, profile: &PlayerProfile) {
+    leaderboard.entries.retain(|entry| entry.wallet != profile.wallet);
     
-    /// CHECK: Seller account to receive rent refund
-    #[account(mut, constraint = seller.key() == listing.seller)]
-    pub seller: AccountInfo<'info>,
+    let position = leaderboard.entries
+        .iter()
+        .position(|entry| entry.trophies < profile.trophies)
+        .unwrap_or(leaderboard.entries.len());
+    if position >= Leaderboard::MAX_ENTRIES {
+        return;
+    }
     
-    /// Buyer's profile (to deduct BUG)
-    #[account(
-        mut,
-        seeds = [b"player_profile", buyer.key().as_ref()],
-        bump = buyer_profile.bump
-    )]
-    pub buyer_profile: Account<'info, PlayerProfile>,
+    leaderboard.entries.insert(position, LeaderboardEntry {
+        wallet: profile.wallet,
+        username: profile.username.clone(),
+        trophies: profile.trophies,
+    });
+    leaderboard.entries.truncate(Leaderboard::MAX_ENTRIES);
+}
+
+/// Payout shares must cover 1-4 places and sum to exactly 100%
+pub fn validate_payout_bps(payout_bps: &[u16]) -> Result<()> {
+    require!(
+        !payout_bps.is_empty() && payout_bps.len() <= Tournament::MAX_PAID_PLACES,
+        GameError::InvalidPayouts
+    );
+    let total: u32 = payout_bps.iter().map(|bps| *bps as u32).sum();
+    require!(total == 10_000, GameError::InvalidPayouts);
+    Ok(())
+}
+
+/// Shuffle entrants into bracket order (Fisher-Yates driven by a hash chain)
+pub fn seed_bracket(entrants: &[Pubkey], random_value: u64) -> Vec<Pubkey> {
+    let mut seeded = entrants.to_vec();
+    let mut random = random_value;
+    for i in (1..seeded.len()).rev() {
+        random = u64::from_le_bytes(hash(&random.to_le_bytes()).to_bytes()[0..8].try_into().unwrap());
+        let j = (random % (i as u64 + 1)) as usize;
+        seeded.swap(i, j);
+    }
+    seeded
+}
+
+/// Prize per placement. Places without a player (fewer entrants than paid places)
+/// and rounding dust go to the champion.
+pub fn calculate_tournament_payouts(
+    prize_pool: u64,
+    payout_bps: &[u16],
+    placed_players: usize,
+) -> Result<Vec<u64>> {
+    let paid_places = payout_bps.len().min(placed_players);
+    let mut payouts = Vec::with_capacity(paid_places);
+    for bps in payout_bps.iter().take(paid_places) {
+        let payout = (prize_pool as u128)
+            .checked_mul(*bps as u128)
+            .ok_or(GameError::NumericalOverflow)?
+            / 10_000;
+        payouts.push(payout as u64);
+    }
     
-    /// Seller's profile (to add BUG)
-    #[account(
-        mut,
-        seeds = [b"player_profile", listing.seller.as_ref()],
-        bump = seller_profile.bump
-    )]
-    pub seller_profile: Account<'info, PlayerProfile>,
+    let paid: u64 = payouts.iter().sum();
+    if let Some(first) = payouts.first_mut() {
+        *first = first
+            .checked_add(prize_pool - paid)
+            .ok_or(GameError::NumericalOverflow)?;
+    }
+    Ok(payouts)
+}
+
+/// Soft reset for a new season: trophies above the floor are halved
+pub fn soft_reset_trophies(trophies: u32, trophy_floor: u32) -> u32 {
+    if trophies <= trophy_floor {
+        return trophies;
+    }
+    trophy_floor + (trophies - trophy_floor) / 2
+}
+
+/// Bring a profile into the current season, queueing last season's result for its
+/// reward claim and applying the soft reset. No-op if the profile is already up to date.
+/// Once MAX_UNCLAIMED_SEASONS results wait, the oldest one expires.
+pub fn sync_player_season(profile: &mut PlayerProfile, current_season_id: u32, trophy_floor: u32) {
+    if profile.season_id >= current_season_id {
+        return;
+    }
     
-    pub card_mint: Account<'info, Mint>,
+    // The pre-season (0) has no rewards to claim
+    if profile.season_id > 0 {
+        if profile.unclaimed_seasons.len() >= PlayerProfile::MAX_UNCLAIMED_SEASONS {
+            profile.unclaimed_seasons.remove(0);
+        }
+        profile.unclaimed_seasons.push(SeasonResult {
+            season_id: profile.season_id,
+            final_trophies: profile.trophies,
+            best_trophies: profile.season_best_trophies.max(profile.trophies),
+        });
+    }
     
-    /// Card instance to update owner
-    #[account(
-        mut,
-        seeds = [b"card_instance", card_mint.key().as_ref()],
-        bump = card_instance.bump
-    )]
-    pub card_instance: Account<'info, CardInstance>,
+    profile.trophies = soft_reset_trophies(profile.trophies, trophy_floor);
+    profile.season_best_trophies = profile.trophies;
+    profile.season_peak_tier = ArenaTier::Bronze;
+    profile.season_id = current_season_id;
+}
+
+/// Remove a season's result from the profile to pay out its rewards
+pub fn take_season_result(profile: &mut PlayerProfile, season_id: u32) -> Result<SeasonResult> {
+    let index = profile
+        .unclaimed_seasons
+        .iter()
+        .position(|result| result.season_id == season_id)
+        .ok_or(GameError::NoSeasonResult)?;
+    Ok(profile.unclaimed_seasons.remove(index))
+}
+
+/// Tier thresholds must cover every ArenaTier, start at 0 and strictly ascend
+pub fn validate_tier_thresholds(tiers: &[TierThreshold]) -> Result<()> {
+    require!(tiers.len() == ArenaTier::COUNT, GameError::InvalidTierThresholds);
+    require!(tiers[0].min_trophies == 0, GameError::InvalidTierThresholds);
+    require!(
+        tiers.windows(2).all(|w| w[0].min_trophies < w[1].min_trophies),
+        GameError::InvalidTierThresholds
+    );
+    Ok(())
+}
+
+/// Highest tier whose threshold the trophy count meets
+pub fn tier_for_trophies(tier_config: &TierConfig, trophies: u32) -> ArenaTier {
+    let index = tier_config.tiers
+        .iter()
+        .rposition(|tier| trophies >= tier.min_trophies)
+        .unwrap_or(0);
+    ArenaTier::from_index(index)
+}
+
+/// Minimum trophies for a player whose season peak is `peak_tier`:
+/// the threshold of the highest floor tier at or below the peak
+pub fn tier_trophy_floor(tier_config: &TierConfig, peak_tier: ArenaTier) -> u32 {
+    tier_config.tiers
+        .iter()
+        .take(peak_tier.index() + 1)
+        .filter(|tier| tier.has_floor)
+        .map(|tier| tier.min_trophies)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Trophies left after a loss: never below `floor`, but a loss never adds
+/// trophies either (the floor can be above a player's count after a tier
+/// config change)
+pub fn trophies_after_loss(trophies: u32, trophy_loss: u32, floor: u32) -> u32 {
+    trophies.saturating_sub(trophy_loss).max(floor).min(trophies)
+}
+
+/// Record the tier the current trophies qualify for as reached this season
+pub fn note_tier_reached(profile: &mut PlayerProfile, tier_config: &TierConfig) {
+    let tier = tier_for_trophies(tier_config, profile.trophies);
+    profile.season_peak_tier = profile.season_peak_tier.max(tier);
+}
+
+/// Recompute a player's tier from trophies. Returns the event to emit if it changed.
+pub fn refresh_player_tier(profile: &mut PlayerProfile, tier_config: &TierConfig) -> Option<TierChanged> {
+    note_tier_reached(profile, tier_config);
     
-    /// Escrow token account (PDA-based, will be closed in instruction)
-    #[account(
-        mut,
-        seeds = [b"escrow", card_mint.key().as_ref()],
-        bump,
-        token::mint = card_mint,
-        token::authority = listing,
-    )]
-    pub escrow_token_account: Account<'info, TokenAccount>,
+    let old_tier = profile.tier;
+    let new_tier = tier_for_trophies(tier_config, profile.trophies);
+    if new_tier == old_tier {
+        return None;
+    }
     
-    /// Buyer's token account to receive the NFT
-    #[account(
-        init_if_needed,
-        payer = buyer,
-        associated_token::mint = card_mint,
-        associated_token::authority = buyer,
-    )]
-    pub buyer_token_account: Account<'info, TokenAccount>,
+    profile.tier = new_tier;
+    Some(TierChanged {
+        wallet: profile.wallet,
+        old_tier,
+        new_tier,
+        trophies: profile.trophies,
+        promoted: new_tier > old_tier,
+    })
+}
+
+/// Reward tiers must fit the account and be strictly ascending by trophies
+pub fn validate_season_reward_tiers(reward_tiers: &[SeasonRewardTier]) -> Result<()> {
+    require!(
+        reward_tiers.len() <= Season::MAX_REWARD_TIERS,
+        GameError::InvalidRewardTiers
+    );
+    require!(
+        reward_tiers.windows(2).all(|w| w[0].min_trophies < w[1].min_trophies),
+        GameError::InvalidRewardTiers
+    );
+    Ok(())
+}
+
+/// Highest reward tier the given trophy count qualifies for
+pub fn find_season_reward(reward_tiers: &[SeasonRewardTier], trophies: u32) -> Option<SeasonRewardTier> {
+    reward_tiers
+        .iter()
+        .rev()
+        .find(|tier| trophies >= tier.min_trophies)
+        .copied()
+}
+
+/// Validate that a string is non-empty and not just whitespace
+pub fn validate_non_empty_string(s: &str) -> Result<()> {
+    require!(!s.trim().is_empty(), GameError::EmptyString);
+    Ok(())
+}
+
+/// Validate string length
+pub fn validate_string_length(s: &str, max_len: usize) -> Result<()> {
+    require!(s.len() <= max_len, GameError::StringTooLong);
+    Ok(())
+}
+
+/// Check if signer is authorized (authority or card creator)
+pub fn is_authorized_creator(game_config: &GameConfig, signer: &Pubkey) -> bool {
+    signer == &game_config.authority || game_config.card_creators.contains(signer)
+}
+
+/// Validate and apply a balance patch, bumping the template version.
+/// Returns the values that were replaced.
+pub fn apply_card_template_update(
+    card_template: &mut CardTemplate,
+    new_values: CardTemplateValues,
+) -> Result<CardTemplateValues> {
+    require!(new_values.min_attack <= new_values.max_attack, GameError::InvalidStatRange);
+    require!(new_values.min_health <= new_values.max_health, GameError::InvalidStatRange);
+    validate_non_empty_string(&new_values.description)?;
+    validate_string_length(&new_values.description, CardTemplate::MAX_DESCRIPTION_LEN)?;
+    validate_string_length(&new_values.image_uri, CardTemplate::MAX_IMAGE_URI_LEN)?;
+    validate_ability(&new_values.ability)?;
     
-    #[account(mut)]
-    pub buyer: Signer<'info>,
+    let old_values = CardTemplateValues {
+        min_attack: card_template.min_attack,
+        max_attack: card_template.max_attack,
+        min_health: card_template.min_health,
+        max_health: card_template.max_health,
+        description: std::mem::replace(&mut card_template.description, new_values.description),
+        image_uri: std::mem::replace(&mut card_template.image_uri, new_values.image_uri),
+        ability: card_template.ability,
+    };
+    card_template.min_attack = new_values.min_attack;
+    card_template.max_attack = new_values.max_attack;
+    card_template.min_health = new_values.min_health;
+    card_template.max_health = new_values.max_health;
+    card_template.ability = new_values.ability;
+    card_template.version = card_template.version
+        .checked_add(1)
+        .ok_or(GameError::NumericalOverflow)?;
     
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
+    Ok(old_values)
 }
 
-#[derive(Accounts)]
-pub struct RecordMatchResult<'info> {
-    #[account(
-        mut,
-        seeds = [b"player_profile", winner_profile.wallet.as_ref()],
-        bump = winner_profile.bump
-    )]
-    pub winner_profile: Account<'info, PlayerProfile>,
+pub fn validate_ability(ability: &Ability) -> Result<()> {
+    let valid = match *ability {
+        Ability::None => true,
+        Ability::Lifesteal { percent } | Ability::Splash { percent } | Ability::DoubleAttack { percent } => {
+            (1..=100).contains(&percent)
+        }
+        Ability::Shield { amount } => amount > 0,
+    };
+    require!(valid, GameError::InvalidAbility);
+    Ok(())
+}
+
+/// Roll random stats within the template's min/max range
+/// Returns (actual_attack, actual_health)
+pub fn roll_card_stats(
+    min_attack: u16,
+    max_attack: u16,
+    min_health: u16,
+    max_health: u16,
+    random_value: u64,
+) -> (u16, u16) {
+    // Use different parts of the random value for attack and health
+    let attack_range = max_attack.saturating_sub(min_attack) as u64 + 1;
+    let health_range = max_health.saturating_sub(min_health) as u64 + 1;
     
-    #[account(
-        mut,
-        seeds = [b"player_profile", loser_profile.wallet.as_ref()],
-        bump = loser_profile.bump
-    )]
-    pub loser_profile: Account<'info, PlayerProfile>,
+    let actual_attack = min_attack + ((random_value % attack_range) as u16);
+    let actual_health = min_health + (((random_value >> 32) % health_range) as u16);
     
-    #[account(
-        seeds = [b"game_config"],
-        bump = game_config.bump,
-        has_one = authority
-    )]
-    pub game_config: Account<'info, GameConfig>,
+    (actual_attack, actual_health)
+}
+
+/// How high a rolled stat sits in its template range, in percent (0 = min, 100 = max)
+/// Stats outside the range (the template was patched after minting) are clamped;
+/// a fixed stat (min == max) counts as a max roll
+pub fn roll_quality_pct(value: u16, min: u16, max: u16) -> u64 {
+    if max <= min {
+        return 100;
+    }
+    let above_min = value.saturating_sub(min).min(max - min) as u64;
+    above_min * 100 / (max - min) as u64
+}
+
+/// Crafting dust for burning a card: the rarity's base value, plus up to the
+/// same again for how well attack and health rolled
+pub fn calculate_burn_dust(card_template: &CardTemplate, attack: u16, health: u16) -> u64 {
+    let base = CardInstance::BURN_DUST[card_template.rarity.to_discriminant() as usize];
+    let quality = (roll_quality_pct(attack, card_template.min_attack, card_template.max_attack)
+        + roll_quality_pct(health, card_template.min_health, card_template.max_health)) / 2;
+    base + base * quality / 100
+}
+
+/// Stats after a reroll: the new roll, or with keep_better the higher of old and new per stat
+pub fn apply_reroll(old: (u16, u16), rolled: (u16, u16), keep_better: bool) -> (u16, u16) {
+    if keep_better {
+        (old.0.max(rolled.0), old.1.max(rolled.1))
+    } else {
+        rolled
+    }
+}
+
+// `Option::is_none_or` is newer than the Solana platform tools' rustc
+#[allow(clippy::unnecessary_map_or)]
+pub fn validate_level_thresholds(level_thresholds: &[u32]) -> Result<()> {
+    require!(level_thresholds.len() <= ProgressionConfig::MAX_LEVELS, GameError::InvalidLevelThresholds);
+    require!(level_thresholds.first().map_or(true, |xp| *xp > 0), GameError::InvalidLevelThresholds);
+    require!(
+        level_thresholds.windows(2).all(|pair| pair[0] < pair[1]),
+        GameError::InvalidLevelThresholds
+    );
+    Ok(())
+}
+
+/// Levels reached with `xp` total experience (0 below the first threshold)
+pub fn level_for_xp(level_thresholds: &[u32], xp: u32) -> u8 {
+    level_thresholds.iter().take_while(|threshold| xp >= **threshold).count() as u8
+}
+
+/// Raise a stat by `levels` level-ups, capped at the template max plus max_bonus_bps of it
+/// A stat already above the cap (the template was patched down) is left as it is
+pub fn level_up_stat(stat: u16, levels: u8, per_level: u16, template_max: u16, max_bonus_bps: u16) -> u16 {
+    let cap = template_max as u64 + template_max as u64 * max_bonus_bps as u64 / 10_000;
+    let raised = stat as u64 + per_level as u64 * levels as u64;
+    raised.min(cap).min(u16::MAX as u64).max(stat as u64) as u16
+}
+
+/// Grow a program account created with an older, shorter layout to `new_len`,
+/// zero-filling the appended fields; the payer tops up the rent.
+/// Returns false (and changes nothing) for an account already at `new_len`.
+pub fn grow_account<'info>(
+    account: &AccountInfo<'info>,
+    discriminator: [u8; 8],
+    new_len: usize,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<bool> {
+    require!(account.owner == &crate::ID, GameError::InvalidMigrationAccount);
+    let len = {
+        let data = account.try_borrow_data()?;
+        require!(data.len() >= 8 && data[..8] == discriminator, GameError::InvalidMigrationAccount);
+        data.len()
+    };
+    if len >= new_len {
+        return Ok(false);
+    }
     
-    pub authority: Signer<'info>,
+    let rent_due = Rent::get()?.minimum_balance(new_len).saturating_sub(account.lamports());
+    if rent_due > 0 {
+        let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+            &payer.key(),
+            account.key,
+            rent_due,
+        );
+        anchor_lang::solana_program::program::invoke(
+            &transfer_ix,
+            &[
+                payer.to_account_info(),
+                account.clone(),
+                system_program.to_account_info(),
+            ],
+        )?;
+    }
+    account.realloc(new_len, true)?;
+    Ok(true)
 }
 
-// ============================================================================
-// Helper Functions
-// ============================================================================
+/// Set the fields of a migrated profile that don't start at zero: the starting
+/// rating, and the current season for the trophies the player already has
+pub fn init_migrated_profile(profile: &mut PlayerProfile, current_season_id: u32) {
+    if profile.rating == 0 {
+        profile.rating = PlayerProfile::INITIAL_RATING;
+    }
+    if profile.season_id == 0 {
+        profile.season_id = current_season_id;
+        profile.season_best_trophies = profile.season_best_trophies.max(profile.trophies);
+    }
+}
 
-use anchor_lang::solana_program::hash::hash;
-use anchor_lang::solana_program::clock::Clock;
+/// Set the fields of a migrated card template that don't start at zero: version 1,
+/// as on creation, and the authority as creator of cards that predate creators
+pub fn init_migrated_template(template: &mut CardTemplate, authority: Pubkey) {
+    if template.version == 0 {
+        template.version = 1;
+    }
+    if template.creator == Pubkey::default() {
+        template.creator = authority;
+    }
+}
 
-/// Generate pseudo-random number using slot hash, clock, and player pubkey
-pub fn generate_random_u64(clock: &Clock, player: &Pubkey, salt: u64) -> u64 {
-    let mut data = Vec::new();
-    data.extend_from_slice(&clock.slot.to_le_bytes());
-    data.extend_from_slice(&clock.unix_timestamp.to_le_bytes());
-    data.extend_from_slice(player.as_ref());
-    data.extend_from_slice(&salt.to_le_bytes());
+/// Raise attack and health by `levels` level-ups of the progression config
+pub fn level_up_stats(
+    (attack, health): (u16, u16),
+    levels: u8,
+    card_template: &CardTemplate,
+    progression_config: &ProgressionConfig,
+) -> (u16, u16) {
+    (
+        level_up_stat(
+            attack,
+            levels,
+            progression_config.attack_per_level,
+            card_template.max_attack,
+            progression_config.max_bonus_bps,
+        ),
+        level_up_stat(
+            health,
+            levels,
+            progression_config.health_per_level,
+            card_template.max_health,
+            progression_config.max_bonus_bps,
+        ),
+    )
+}
+
+/// Add XP to a card and apply any levels gained; returns the number of new levels
+pub fn grant_card_xp(
+    card: &mut CardInstance,
+    card_template: &CardTemplate,
+    progression_config: &ProgressionConfig,
+    xp: u32,
+) -> u8 {
+    card.xp = card.xp.saturating_add(xp);
+    let level = level_for_xp(&progression_config.level_thresholds, card.xp);
+    let levels = level.saturating_sub(card.level);
+    if levels > 0 {
+        (card.attack, card.health) = level_up_stats((card.attack, card.health), levels, card_template, progression_config);
+        card.level = level;
+    }
+    levels
+}
+
+fn is_player_deck(account_info: &AccountInfo) -> bool {
+    account_info.owner == &crate::ID
+        && account_info
+            .try_borrow_data()
+            .map(|data| data.len() >= 8 && data[..8] == <PlayerDeck as anchor_lang::Discriminator>::DISCRIMINATOR)
+            .unwrap_or(false)
+}
+
+/// Grant match XP to the cards in `accounts`: each PlayerDeck (of the winner or the
+/// loser, at most one each) is followed by a writable CardInstance and its CardTemplate
+/// for every card of the deck that gains XP. Cards must still be owned by the deck owner.
+pub fn award_deck_xp<'info>(
+    accounts: &'info [AccountInfo<'info>],
+    progression_config: &ProgressionConfig,
+    winner: Pubkey,
+    loser: Pubkey,
+) -> Result<()> {
+    let mut deck_owners: Vec<Pubkey> = Vec::new();
+    let mut card_mints: Vec<Pubkey> = Vec::new();
+    let mut index = 0;
+    
+    while index < accounts.len() {
+        let deck = Account::<PlayerDeck>::try_from(&accounts[index])?;
+        require!(
+            deck.is_active && (deck.owner == winner || deck.owner == loser) && !deck_owners.contains(&deck.owner),
+            GameError::InvalidDeckAccounts
+        );
+        deck_owners.push(deck.owner);
+        let xp = if deck.owner == winner {
+            progression_config.xp_per_win
+        } else {
+            progression_config.xp_per_loss
+        };
+        index += 1;
+        
+        while index < accounts.len() && !is_player_deck(&accounts[index]) {
+            require!(index + 1 < accounts.len() && accounts[index].is_writable, GameError::InvalidDeckAccounts);
+            let mut card = Account::<CardInstance>::try_from(&accounts[index])?;
+            let card_template = Account::<CardTemplate>::try_from(&accounts[index + 1])?;
+            require!(
+                deck.card_mints.contains(&card.mint)
+                    && card.owner == deck.owner
+                    && card_template.card_type_id == card.card_type_id
+                    && !card_mints.contains(&card.mint),
+                GameError::InvalidDeckAccounts
+            );
+            card_mints.push(card.mint);
+            
+            let levels = grant_card_xp(&mut card, &card_template, progression_config, xp);
+            card.exit(&crate::ID)?;
+            if levels > 0 {
+                msg!("Card {} reached level {}: ATK {}, HP {}", card.mint, card.level, card.attack, card.health);
+            }
+            
+            emit!(CardXpGained {
+                owner: card.owner,
+                mint: card.mint,
+                xp_gained: xp,
+                xp: card.xp,
+                level: card.level,
+                attack: card.attack,
+                health: card.health,
+            });
+            index += 2;
+        }
+    }
     
-    let hash_result = hash(&data);
-    u64::from_le_bytes(hash_result.to_bytes()[0..8].try_into().unwrap())
+    Ok(())
 }
 
-/// Roll for rarity based on probabilities
-/// Common: 70%, Rare: 27%, Legendary: 3%
-pub fn roll_rarity(random_value: u64) -> Rarity {
-    let roll = (random_value % 100) as u8;
-    
-    if roll < 70 {
-        Rarity::Common
-    } else if roll < 97 {  // 70 + 27
-        Rarity::Rare
-    } else {
-        Rarity::Legendary
+pub fn validate_synergy_tiers(tiers: &[SynergyTier]) -> Result<()> {
+    require!(tiers.len() <= SynergyConfig::MAX_TIERS, GameError::InvalidSynergyTiers);
+    for (index, tier) in tiers.iter().enumerate() {
+        require!(
+            (1..=PlayerDeck::MAX_CARDS as u8).contains(&tier.min_units)
+                && tier.attack_bonus_bps <= SynergyTier::MAX_BONUS_BPS
+                && tier.health_bonus_bps <= SynergyTier::MAX_BONUS_BPS,
+            GameError::InvalidSynergyTiers
+        );
+        require!(
+            !tiers[..index]
+                .iter()
+                .any(|other| other.trait_type == tier.trait_type && other.min_units == tier.min_units),
+            GameError::InvalidSynergyTiers
+        );
     }
+    Ok(())
 }
 
-/// Select random card from rarity pool
-pub fn select_random_card(rarity_pool: &RarityPool, random_value: u64) -> Result<u32> {
-    require!(!rarity_pool.card_type_ids.is_empty(), GameError::EmptyRarityPool);
+/// Synergies reached by a deck with cards of these traits, one per trait at most
+/// (the tier with the most units reached), in order of first appearance
+pub fn active_synergies(tiers: &[SynergyTier], traits: &[TraitType]) -> Vec<ActiveSynergy> {
+    let mut synergies: Vec<ActiveSynergy> = Vec::new();
+    for trait_type in traits {
+        if synergies.iter().any(|synergy| synergy.trait_type == *trait_type) {
+            continue;
+        }
+        let units = traits.iter().filter(|other| *other == trait_type).count() as u8;
+        let best = tiers
+            .iter()
+            .filter(|tier| tier.trait_type == *trait_type && tier.min_units <= units)
+            .max_by_key(|tier| tier.min_units);
+        if let Some(tier) = best {
+            synergies.push(ActiveSynergy {
+                trait_type: *trait_type,
+                units,
+                attack_bonus_bps: tier.attack_bonus_bps,
+                health_bonus_bps: tier.health_bonus_bps,
+            });
+        }
+    }
+    synergies
+}
+
+/// A card's (attack, health) with its trait's synergy bonus, if the deck reached one
+pub fn apply_synergy(trait_type: TraitType, attack: u16, health: u16, synergies: &[ActiveSynergy]) -> (u16, u16) {
+    let boost = |stat: u16, bonus_bps: u16| {
+        (stat as u64 + stat as u64 * bonus_bps as u64 / 10_000).min(u16::MAX as u64) as u16
+    };
+    match synergies.iter().find(|synergy| synergy.trait_type == trait_type) {
+        Some(synergy) => (boost(attack, synergy.attack_bonus_bps), boost(health, synergy.health_bonus_bps)),
+        None => (attack, health),
+    }
+}
+
+/// Mints and traits of a deck's cards from (CardInstance, CardTemplate) pairs, one
+/// pair per card in deck order; every card must be owned by `owner` and appear once
+// `usize::is_multiple_of` is newer than the Solana platform tools' rustc
+#[allow(clippy::manual_is_multiple_of)]
+pub fn deck_cards<'info>(
+    accounts: &'info [AccountInfo<'info>],
+    owner: Pubkey,
+) -> Result<(Vec<Pubkey>, Vec<TraitType>)> {
+    require!(accounts.len() % 2 == 0, GameError::InvalidDeckAccounts);
+    let mut card_mints: Vec<Pubkey> = Vec::with_capacity(accounts.len() / 2);
+    let mut traits = Vec::with_capacity(accounts.len() / 2);
+    
+    for pair in accounts.chunks_exact(2) {
+        let card = Account::<CardInstance>::try_from(&pair[0])?;
+        let card_template = Account::<CardTemplate>::try_from(&pair[1])?;
+        require!(
+            card.owner == owner
+                && card_template.card_type_id == card.card_type_id
+                && !card_mints.contains(&card.mint),
+            GameError::InvalidDeckAccounts
+        );
+        card_mints.push(card.mint);
+        traits.push(card_template.trait_type);
+    }
     
-    let index = (random_value as usize) % rarity_pool.card_type_ids.len();
-    Ok(rarity_pool.card_type_ids[index])
+    Ok((card_mints, traits))
 }
 
-/// Validate that a string is non-empty and not just whitespace
-pub fn validate_non_empty_string(s: &str) -> Result<()> {
-    require!(!s.trim().is_empty(), GameError::EmptyString);
+/// Add a card mint to a collection page (no-op if already there)
+pub fn add_to_collection(collection: &mut Collection, card_mint: Pubkey) -> Result<()> {
+    if !collection.card_mints.contains(&card_mint) {
+        require!(collection.card_mints.len() < Collection::MAX_CARDS, GameError::CollectionFull);
+        collection.card_mints.push(card_mint);
+    }
     Ok(())
 }
 
-/// Validate string length
-pub fn validate_string_length(s: &str, max_len: usize) -> Result<()> {
-    require!(s.len() <= max_len, GameError::StringTooLong);
+/// Remove a card mint from a collection page, keeping the order of the others
+pub fn remove_from_collection(collection: &mut Collection, card_mint: &Pubkey) -> Result<()> {
+    let index = collection
+        .card_mints
+        .iter()
+        .position(|mint| mint == card_mint)
+        .ok_or(GameError::CardNotInCollection)?;
+    collection.card_mints.remove(index);
     Ok(())
 }
 
-/// Check if signer is authorized (authority or card creator)
-pub fn is_authorized_creator(game_config: &GameConfig, signer: &Pubkey) -> bool {
-    signer == &game_config.authority || game_config.card_creators.contains(signer)
+/// Whether `account_info`, which must be the CardInstance PDA of `card_mint`, holds a
+/// card owned by `owner` (false once the card is burned or sold)
+pub fn card_owned_by<'info>(account_info: &'info AccountInfo<'info>, card_mint: &Pubkey, owner: Pubkey) -> Result<bool> {
+    let (card_instance, _) = Pubkey::find_program_address(
+        &[b"card_instance", card_mint.as_ref()],
+        &crate::ID,
+    );
+    require!(account_info.key() == card_instance, GameError::InvalidCollectionCards);
+    
+    Ok(account_info.owner == &crate::ID
+        && !account_info.data_is_empty()
+        && Account::<CardInstance>::try_from(account_info)?.owner == owner)
 }
 
-/// Roll random stats within the template's min/max range
-/// Returns (actual_attack, actual_health)
-pub fn roll_card_stats(
-    min_attack: u16,
-    max_attack: u16,
-    min_health: u16,
-    max_health: u16,
-    random_value: u64,
-) -> (u16, u16) {
-    // Use different parts of the random value for attack and health
-    let attack_range = max_attack.saturating_sub(min_attack) as u64 + 1;
-    let health_range = max_health.saturating_sub(min_health) as u64 + 1;
-    
-    let actual_attack = min_attack + ((random_value % attack_range) as u16);
-    let actual_health = min_health + (((random_value >> 32) % health_range) as u16);
-    
-    (actual_attack, actual_health)
+/// Whether a Collection page's account data indexes `card_mint`; reads the mint
+/// list in place, as pages are too large to deserialize several per instruction
+pub fn collection_page_contains(data: &[u8], card_mint: &Pubkey) -> bool {
+    // 8 (discriminator) + 32 (owner) + 1 (page)
+    const MINTS_OFFSET: usize = 8 + 32 + 1;
+    let len = match data.get(MINTS_OFFSET..MINTS_OFFSET + 4) {
+        Some(len) => u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize,
+        None => return false,
+    };
+    data[MINTS_OFFSET + 4..]
+        .chunks_exact(32)
+        .take(len)
+        .any(|mint| mint == card_mint.as_ref())
+}
+
+/// Crafting dust needed to craft a card of this rarity
+pub fn calculate_craft_cost(rarity: Rarity) -> u64 {
+    CardInstance::CRAFT_DUST[rarity.to_discriminant() as usize]
 }
 
 /// Mint an NFT card to a player with randomized stats
@@ -1575,9 +6948,55 @@ pub fn query_card_template(card_type_id: u32) -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use anchor_lang::prelude::*;
     use solana_program_test::*;
-    use solana_sdk::{signature::Keypair, signer::Signer, transaction::Transaction};
+    
+    fn new_test_profile() -> PlayerProfile {
+        PlayerProfile {
+            wallet: Pubkey::default(),
+            username: String::from("tester"),
+            has_claimed_starter_pack: true,
+            gacha_tickets: 0,
+            bug_balance: 0,
+            trophies: 0,
+            total_wins: 0,
+            total_losses: 0,
+            win_streak: 0,
+            bump: 0,
+            rating: PlayerProfile::INITIAL_RATING,
+            season_id: 0,
+            season_best_trophies: 0,
+            unclaimed_seasons: Vec::new(),
+            reward_day: 0,
+            daily_bug_earned: 0,
+            total_draws: 0,
+            tier: ArenaTier::Bronze,
+            season_peak_tier: ArenaTier::Bronze,
+            crafting_dust: 0,
+            collection_pages: 0,
+        }
+    }
+    
+    /// A base game Common Warrior (attack 1-2, health 3-4); tests override what they need
+    pub(crate) fn new_test_template(card_type_id: u32) -> CardTemplate {
+        CardTemplate {
+            card_type_id,
+            name: String::from("Card"),
+            trait_type: TraitType::Warrior,
+            rarity: Rarity::Common,
+            min_attack: 1,
+            max_attack: 2,
+            min_health: 3,
+            max_health: 4,
+            description: String::from("Card"),
+            image_uri: String::new(),
+            bump: 0,
+            creator: Pubkey::default(),
+            version: 1,
+            is_retired: false,
+            set_id: 0,
+            ability: Ability::None,
+        }
+    }
     
     // Feature: 404-zoo-contract, Property 1: Card template storage completeness
     // Feature: 404-zoo-contract, Property 2: Card type ID uniqueness
@@ -1632,6 +7051,1050 @@ mod tests {
         // For now, this is a placeholder structure
     }
     
+    // Feature: 404-zoo-contract, Property 30: Elo rating change
+    #[test]
+    fn test_elo_rating_change() {
+        // Equal ratings split the K factor evenly
+        assert_eq!(elo_expected_score(1000, 1000), ELO_SCALE / 2);
+        assert_eq!(calculate_elo_change(1000, 1000), 16);
+        
+        // Expected scores are symmetric
+        assert_eq!(elo_expected_score(1200, 1000) + elo_expected_score(1000, 1200), ELO_SCALE);
+        
+        // Upsets pay more than expected wins
+        assert!(calculate_elo_change(1000, 1400) > calculate_elo_change(1400, 1000));
+        assert_eq!(calculate_elo_change(1000, 1400), 29);
+        assert_eq!(calculate_elo_change(1400, 1000), 3);
+        
+        // Huge gaps are clamped and still move the rating
+        assert_eq!(calculate_elo_change(3000, 100), 1);
+        assert_eq!(calculate_elo_change(100, 3000), 32);
+    }
+    
+    // Feature: 404-zoo-contract, Property 35: Draw settlement rating change
+    #[test]
+    fn test_elo_draw_change() {
+        // Even players don't move on a draw
+        assert_eq!(calculate_elo_draw_change(1000, 1000), 0);
+        
+        // The underdog gains exactly what the favourite loses
+        let change = calculate_elo_draw_change(1000, 1400);
+        assert_eq!(change, 13);
+        assert_eq!(calculate_elo_draw_change(1400, 1000), -change);
+        
+        assert_eq!(apply_rating_change(1000, change).unwrap(), 1013);
+        assert_eq!(apply_rating_change(5, -13).unwrap(), 0);
+    }
+    
+    // Feature: 404-zoo-contract, Property 31: Season soft reset
+    // Feature: 404-zoo-contract, Property 32: Season reward bracket
+    #[test]
+    fn test_season_rollover_and_rewards() {
+        // Anything above the floor is halved, below the floor is untouched
+        assert_eq!(soft_reset_trophies(1000, 400), 700);
+        assert_eq!(soft_reset_trophies(300, 400), 300);
+        assert_eq!(soft_reset_trophies(401, 400), 400);
+        
+        let tiers = vec![
+            SeasonRewardTier { min_trophies: 0, bug_reward: 50, ticket_reward: 0 },
+            SeasonRewardTier { min_trophies: 500, bug_reward: 200, ticket_reward: 1 },
+            SeasonRewardTier { min_trophies: 1000, bug_reward: 500, ticket_reward: 5 },
+        ];
+        assert!(validate_season_reward_tiers(&tiers).is_ok());
+        assert_eq!(find_season_reward(&tiers, 499).unwrap().bug_reward, 50);
+        assert_eq!(find_season_reward(&tiers, 500).unwrap().bug_reward, 200);
+        assert_eq!(find_season_reward(&tiers, 5000).unwrap().ticket_reward, 5);
+        assert!(find_season_reward(&tiers[1..], 10).is_none());
+        
+        let unsorted = vec![tiers[1], tiers[0]];
+        assert!(validate_season_reward_tiers(&unsorted).is_err());
+        
+        // Unclaimed results queue up on the profile and never hold up a match
+        let economy_config = EconomyConfig::DEFAULTS;
+        let mut profile = new_test_profile();
+        profile.season_id = 1;
+        profile.trophies = 1000;
+        sync_player_season(&mut profile, 2, 400);
+        sync_player_season(&mut profile, 3, 400);
+        assert_eq!(profile.season_id, 3);
+        assert_eq!(
+            profile.unclaimed_seasons,
+            vec![
+                SeasonResult { season_id: 1, final_trophies: 1000, best_trophies: 1000 },
+                SeasonResult { season_id: 2, final_trophies: 700, best_trophies: 700 },
+            ]
+        );
+        profile.win_streak += 1;
+        profile.trophies += calculate_trophy_gain(&economy_config, profile.win_streak).unwrap();
+        assert_eq!(profile.trophies, 550 + 31);
+        
+        // Claims take any waiting season, in any order, once
+        assert_eq!(take_season_result(&mut profile, 2).unwrap().final_trophies, 700);
+        assert!(take_season_result(&mut profile, 2).is_err());
+        assert_eq!(take_season_result(&mut profile, 1).unwrap().best_trophies, 1000);
+        assert!(profile.unclaimed_seasons.is_empty());
+        
+        // The pre-season queues nothing, and the oldest result expires past the limit
+        let mut profile = new_test_profile();
+        for season_id in 1..=(PlayerProfile::MAX_UNCLAIMED_SEASONS as u32 + 2) {
+            sync_player_season(&mut profile, season_id, 0);
+        }
+        assert_eq!(profile.unclaimed_seasons.len(), PlayerProfile::MAX_UNCLAIMED_SEASONS);
+        assert_eq!(profile.unclaimed_seasons[0].season_id, 2);
+    }
+    
+    // Feature: 404-zoo-contract, Property 33: Configurable streak bonus cap
+    // Feature: 404-zoo-contract, Property 34: Daily match reward cap
+    #[test]
+    fn test_economy_config_rewards() {
+        let mut economy_config = EconomyConfig {
+            base_trophy_gain: 30,
+            trophy_loss: 30,
+            max_streak_bonus: 5,
+            win_reward: 100,
+            loss_reward: 10,
+            daily_reward_cap: 250,
+            bump: 0,
+            draw_trophy_delta: 0,
+        };
+        assert_eq!(calculate_trophy_gain(&economy_config, 3).unwrap(), 33);
+        assert_eq!(calculate_trophy_gain(&economy_config, 12).unwrap(), 35);
+        economy_config.max_streak_bonus = 0;
+        assert_eq!(calculate_trophy_gain(&economy_config, 12).unwrap(), 42);
+        
+        let mut profile = new_test_profile();
+        let today = unix_day(1_700_000_000);
+        assert_eq!(grant_match_reward(&mut profile, 100, &economy_config, today).unwrap(), 100);
+        assert_eq!(grant_match_reward(&mut profile, 100, &economy_config, today).unwrap(), 100);
+        assert_eq!(grant_match_reward(&mut profile, 100, &economy_config, today).unwrap(), 50);
+        assert_eq!(grant_match_reward(&mut profile, 100, &economy_config, today).unwrap(), 0);
+        assert_eq!(profile.bug_balance, 250);
+        
+        // Cap resets on the next day
+        assert_eq!(grant_match_reward(&mut profile, 100, &economy_config, today + 1).unwrap(), 100);
+        assert_eq!(profile.bug_balance, 350);
+        
+        // Loss rewards count towards the same daily cap
+        assert_eq!(grant_match_reward(&mut profile, economy_config.loss_reward, &economy_config, today + 1).unwrap(), 10);
+        assert_eq!(profile.daily_bug_earned, 110);
+        
+        // Without an EconomyConfig or TierConfig the original constants apply
+        let defaults = EconomyConfig::DEFAULTS;
+        assert_eq!(calculate_trophy_gain(&defaults, 1).unwrap(), 31);
+        assert_eq!((defaults.trophy_loss, defaults.win_reward, defaults.loss_reward), (30, 100, 0));
+        assert_eq!(grant_match_reward(&mut new_test_profile(), 100, &defaults, today).unwrap(), 100);
+        assert_eq!(tier_for_trophies(&TierConfig::NONE, 5_000), ArenaTier::Bronze);
+        assert_eq!(tier_trophy_floor(&TierConfig::NONE, ArenaTier::Legend), 0);
+    }
+    
+    // Feature: 404-zoo-contract, Property 36: Leaderboard ordering and cutoff
+    #[test]
+    fn test_leaderboard_update() {
+        let mut leaderboard = Leaderboard { entries: Vec::new(), bump: 0 };
+        let mut profiles: Vec<PlayerProfile> = (0..Leaderboard::MAX_ENTRIES as u32 + 5)
+            .map(|i| {
+                let mut profile = new_test_profile();
+                profile.wallet = Pubkey::new_unique();
+                profile.trophies = i * 10;
+                profile
+            })
+            .collect();
+        for profile in profiles.iter() {
+            update_leaderboard(&mut leaderboard, profile);
+        }
+        
+        // Only the top 100 remain, sorted descending
+        assert_eq!(leaderboard.entries.len(), Leaderboard::MAX_ENTRIES);
+        assert_eq!(leaderboard.entries[0].trophies, 1040);
+        assert_eq!(leaderboard.entries[99].trophies, 50);
+        assert!(leaderboard.entries.windows(2).all(|w| w[0].trophies >= w[1].trophies));
+        
+        // A player below the cutoff doesn't get in
+        update_leaderboard(&mut leaderboard, &profiles[0]);
+        assert!(leaderboard.entries.iter().all(|e| e.wallet != profiles[0].wallet));
+        
+        // Moving an existing player doesn't duplicate them
+        profiles[50].trophies = 5000;
+        update_leaderboard(&mut leaderboard, &profiles[50]);
+        assert_eq!(leaderboard.entries.len(), Leaderboard::MAX_ENTRIES);
+        assert_eq!(leaderboard.entries[0].wallet, profiles[50].wallet);
+        assert_eq!(leaderboard.entries.iter().filter(|e| e.wallet == profiles[50].wallet).count(), 1);
+    }
+    
+    // Feature: 404-zoo-contract, Property 37: Single-elimination bracket progression
+    // Feature: 404-zoo-contract, Property 38: Prize pool distribution by placement
+    // Feature: 404-zoo-contract, Property 55: Cancelled tournaments refund every entrant
+    #[test]
+    fn test_tournament_bracket_and_payouts() {
+        let players: Vec<Pubkey> = (0..5).map(|_| Pubkey::new_unique()).collect();
+        let seeded = seed_bracket(&players, 42);
+        assert_eq!(seeded.len(), players.len());
+        assert!(players.iter().all(|p| seeded.contains(p)));
+        
+        let mut tournament = Tournament {
+            tournament_id: 1,
+            name: String::from("Cup"),
+            entry_currency: EntryCurrency::Bug,
+            entry_fee: 100,
+            max_entrants: 8,
+            payout_bps: vec![6000, 3000, 1000],
+            status: TournamentStatus::InProgress,
+            entrants: players.clone(),
+            round: 1,
+            current_round: Vec::new(),
+            next_round: Vec::new(),
+            eliminated: Vec::new(),
+            prize_pool: 500,
+            prizes_paid: false,
+            created_at: 0,
+            bump: 0,
+        };
+        
+        // Round 1: 5 players -> 2 matches + bye for players[4]
+        tournament.begin_round(players.clone());
+        assert_eq!(tournament.next_round[2], players[4]);
+        assert!(tournament.record_match_winner(0, players[4]).is_err());
+        tournament.record_match_winner(0, players[0]).unwrap();
+        assert!(tournament.record_match_winner(0, players[1]).is_err());
+        tournament.record_match_winner(1, players[3]).unwrap();
+        
+        // Round 2: [0, 3, 4] -> 1 match + bye for players[4]
+        assert_eq!(tournament.round, 2);
+        assert_eq!(tournament.current_round, vec![players[0], players[3], players[4]]);
+        tournament.record_match_winner(0, players[3]).unwrap();
+        
+        // Final: [3, 4]
+        assert_eq!(tournament.round, 3);
+        tournament.record_match_winner(0, players[4]).unwrap();
+        assert_eq!(tournament.status, TournamentStatus::Completed);
+        
+        let placements = tournament.placements();
+        assert_eq!(placements[0], players[4]);
+        assert_eq!(placements[1], players[3]);
+        assert_eq!(placements[2], players[0]);
+        
+        let payouts = calculate_tournament_payouts(500, &tournament.payout_bps, placements.len()).unwrap();
+        assert_eq!(payouts, vec![300, 150, 50]);
+        
+        // Only a tournament still in registration can be cancelled
+        assert!(tournament.cancel().is_err());
+        tournament.status = TournamentStatus::Registration;
+        assert_eq!(tournament.cancel().unwrap(), players);
+        assert_eq!(tournament.status, TournamentStatus::Cancelled);
+        assert_eq!(tournament.prize_pool, 0);
+        assert!(tournament.entrants.is_empty());
+        assert!(tournament.cancel().is_err());
+        
+        // Unfilled places and dust go to the champion
+        let payouts = calculate_tournament_payouts(101, &[6000, 3000, 1000], 2).unwrap();
+        assert_eq!(payouts, vec![71, 30]);
+        
+        assert!(validate_payout_bps(&[5000, 4000]).is_err());
+        assert!(validate_payout_bps(&[10_000]).is_ok());
+    }
+    
+    // Feature: 404-zoo-contract, Property 39: Arena tier from trophies
+    // Feature: 404-zoo-contract, Property 40: Tier floor within a season
+    #[test]
+    fn test_arena_tiers_and_floors() {
+        let thresholds = [0, 300, 600, 1000, 1500, 2200];
+        let tier_config = TierConfig {
+            tiers: thresholds
+                .iter()
+                .map(|min| TierThreshold { min_trophies: *min, has_floor: *min == 600 || *min == 1500 })
+                .collect(),
+            bump: 0,
+        };
+        assert!(validate_tier_thresholds(&tier_config.tiers).is_ok());
+        assert!(validate_tier_thresholds(&tier_config.tiers[1..]).is_err());
+        
+        assert_eq!(tier_for_trophies(&tier_config, 0), ArenaTier::Bronze);
+        assert_eq!(tier_for_trophies(&tier_config, 599), ArenaTier::Silver);
+        assert_eq!(tier_for_trophies(&tier_config, 9999), ArenaTier::Legend);
+        
+        assert_eq!(tier_trophy_floor(&tier_config, ArenaTier::Silver), 0);
+        assert_eq!(tier_trophy_floor(&tier_config, ArenaTier::Platinum), 600);
+        assert_eq!(tier_trophy_floor(&tier_config, ArenaTier::Legend), 1500);
+        assert_eq!(trophies_after_loss(620, 30, 600), 600);
+        assert_eq!(trophies_after_loss(700, 30, 600), 670);
+        assert_eq!(trophies_after_loss(20, 30, 0), 0);
+        // Below the floor already: the loss takes nothing, and adds nothing
+        assert_eq!(trophies_after_loss(550, 30, 600), 550);
+        
+        let mut profile = new_test_profile();
+        profile.trophies = 650;
+        let promoted = refresh_player_tier(&mut profile, &tier_config).unwrap();
+        assert!(promoted.promoted);
+        assert_eq!(promoted.new_tier, ArenaTier::Gold);
+        assert!(refresh_player_tier(&mut profile, &tier_config).is_none());
+        
+        profile.trophies = 250;
+        let demoted = refresh_player_tier(&mut profile, &tier_config).unwrap();
+        assert!(!demoted.promoted);
+        assert_eq!(profile.season_peak_tier, ArenaTier::Gold);
+        
+        // A new season clears the peak
+        sync_player_season(&mut profile, 1, 0);
+        assert_eq!(profile.season_peak_tier, ArenaTier::Bronze);
+    }
+    
+    // Feature: 404-zoo-contract, Property 41: Card template balance patch
+    #[test]
+    fn test_card_template_update() {
+        let mut card_template = CardTemplate {
+            name: String::from("404 Deer"),
+            min_attack: 8,
+            max_attack: 12,
+            min_health: 20,
+            max_health: 30,
+            description: String::from("Not found"),
+            image_uri: String::from("ipfs://deer"),
+            creator: Pubkey::new_unique(),
+            ..new_test_template(404)
+        };
+        let patch = CardTemplateValues {
+            min_attack: 6,
+            max_attack: 10,
+            min_health: 22,
+            max_health: 32,
+            description: String::from("Nerfed"),
+            image_uri: String::from("ipfs://deer-v2"),
+            ability: Ability::Shield { amount: 5 },
+        };
+        
+        let old_values = apply_card_template_update(&mut card_template, patch.clone()).unwrap();
+        assert_eq!(old_values.max_attack, 12);
+        assert_eq!(old_values.description, "Not found");
+        assert_eq!(card_template.max_attack, 10);
+        assert_eq!(card_template.image_uri, "ipfs://deer-v2");
+        assert_eq!(card_template.ability, Ability::Shield { amount: 5 });
+        assert_eq!(old_values.ability, Ability::None);
+        assert_eq!(card_template.version, 2);
+        
+        // Invalid patches leave the template (and its version) untouched
+        let mut inverted = patch.clone();
+        inverted.min_health = 40;
+        assert!(apply_card_template_update(&mut card_template, inverted).is_err());
+        let mut empty = patch.clone();
+        empty.description = String::from("  ");
+        assert!(apply_card_template_update(&mut card_template, empty).is_err());
+        let mut no_shield = patch;
+        no_shield.ability = Ability::Shield { amount: 0 };
+        assert!(apply_card_template_update(&mut card_template, no_shield).is_err());
+        assert_eq!(card_template.version, 2);
+        assert_eq!(card_template.min_health, 22);
+    }
+    
+    // Feature: 404-zoo-contract, Property 42: Retired cards leave the drop pool
+    #[test]
+    fn test_remove_from_pool() {
+        let mut rarity_pool = RarityPool {
+            rarity: Rarity::Legendary,
+            entries: [404, 418, 451, 500]
+                .iter()
+                .map(|id| PoolEntry { card_type_id: *id, weight: PoolEntry::DEFAULT_WEIGHT })
+                .collect(),
+            bump: 0,
+            set_id: 0,
+        };
+        
+        assert_eq!(remove_from_pool(&mut rarity_pool, &[418, 999]), 1);
+        let remaining: Vec<u32> = rarity_pool.entries.iter().map(|e| e.card_type_id).collect();
+        assert_eq!(remaining, vec![404, 451, 500]);
+        assert_eq!(remove_from_pool(&mut rarity_pool, &[418]), 0);
+        
+        // Removed ids can never be selected
+        for random_value in 0..32 {
+            assert_ne!(select_random_card(&rarity_pool, random_value).unwrap(), 418);
+        }
+        
+        assert_eq!(remove_from_pool(&mut rarity_pool, &[404, 451, 500]), 3);
+        assert!(select_random_card(&rarity_pool, 7).is_err());
+    }
+    
+    // Feature: 404-zoo-contract, Property 43: Rarity pool consistency with templates
+    #[test]
+    fn test_rarity_pool_consistency() {
+        let template = |card_type_id: u32, rarity: Rarity| CardTemplate {
+            trait_type: TraitType::Archer,
+            rarity,
+            ..new_test_template(card_type_id)
+        };
+        
+        let rare = vec![template(302, Rarity::Rare), template(403, Rarity::Rare)];
+        assert!(validate_pool_templates(Rarity::Rare, &[302, 403], &rare).is_ok());
+        // Wrong order, missing account, wrong pool
+        assert!(validate_pool_templates(Rarity::Rare, &[403, 302], &rare).is_err());
+        assert!(validate_pool_templates(Rarity::Rare, &[302, 403], &rare[..1]).is_err());
+        assert!(validate_pool_templates(Rarity::Common, &[302, 403], &rare).is_err());
+        
+        let mut retired = template(550, Rarity::Rare);
+        retired.is_retired = true;
+        assert!(validate_pool_templates(Rarity::Rare, &[550], &[retired]).is_err());
+        
+        // Capacity is enforced; duplicates don't count against it
+        let entry = |card_type_id: u32| PoolEntry { card_type_id, weight: PoolEntry::DEFAULT_WEIGHT };
+        let mut rarity_pool = RarityPool { rarity: Rarity::Rare, entries: Vec::new(), bump: 0, set_id: 0 };
+        let entries: Vec<PoolEntry> = (0..RarityPool::MAX_CARDS as u32).map(entry).collect();
+        assert_eq!(add_to_pool(&mut rarity_pool, &entries).unwrap(), RarityPool::MAX_CARDS as u32);
+        assert_eq!(add_to_pool(&mut rarity_pool, &[entry(0), entry(1)]).unwrap(), 0);
+        assert!(add_to_pool(&mut rarity_pool, &[entry(1000)]).is_err());
+        assert_eq!(rarity_pool.entries.len(), RarityPool::MAX_CARDS);
+    }
+    
+    // Feature: 404-zoo-contract, Property 44: Weighted drops within a rarity pool
+    #[test]
+    fn test_weighted_card_selection() {
+        let mut rarity_pool = RarityPool {
+            rarity: Rarity::Legendary,
+            entries: vec![
+                PoolEntry { card_type_id: 451, weight: 100 },
+                PoolEntry { card_type_id: 500, weight: 100 },
+            ],
+            bump: 0,
+            set_id: 0,
+        };
+        
+        // Rate-up: re-weighting an existing card doesn't add a new entry
+        assert_eq!(add_to_pool(&mut rarity_pool, &[PoolEntry { card_type_id: 451, weight: 300 }]).unwrap(), 0);
+        assert!(add_to_pool(&mut rarity_pool, &[PoolEntry { card_type_id: 500, weight: 0 }]).is_err());
+        
+        // Rolls map onto cumulative weights: [0, 300) -> 451, [300, 400) -> 500
+        assert_eq!(select_random_card(&rarity_pool, 0).unwrap(), 451);
+        assert_eq!(select_random_card(&rarity_pool, 299).unwrap(), 451);
+        assert_eq!(select_random_card(&rarity_pool, 300).unwrap(), 500);
+        assert_eq!(select_random_card(&rarity_pool, 399).unwrap(), 500);
+        assert_eq!(select_random_card(&rarity_pool, 400).unwrap(), 451);
+        
+        let featured = (0..4000u64)
+            .filter(|r| select_random_card(&rarity_pool, *r).unwrap() == 451)
+            .count();
+        assert_eq!(featured, 3000);
+        
+        // Gacha draws roll a rarity, then a card of its pool: 0 rolls Common, 80 Rare, 99 Legendary
+        let pool = |rarity: Rarity, card_type_id: u32| RarityPool {
+            rarity,
+            entries: vec![PoolEntry { card_type_id, weight: 100 }],
+            bump: 0,
+            set_id: 0,
+        };
+        let (common, rare) = (pool(Rarity::Common, 401), pool(Rarity::Rare, 402));
+        let pools = [&common, &rare, &rarity_pool];
+        assert_eq!(roll_gacha_card(pools, 0).unwrap(), 401);
+        assert_eq!(roll_gacha_card(pools, 80).unwrap(), 402);
+        assert_eq!(roll_gacha_card(pools, 99).unwrap(), 451);
+    }
+    
+    // Feature: 404-zoo-contract, Property 57: Card picks are independent of the rarity roll
+    #[test]
+    fn test_weighted_drops_within_rolled_rarity() {
+        let pool = |rarity: Rarity, weights: &[u16]| RarityPool {
+            rarity,
+            entries: weights
+                .iter()
+                .enumerate()
+                .map(|(i, weight)| PoolEntry { card_type_id: i as u32, weight: *weight })
+                .collect(),
+            bump: 0,
+            set_id: 0,
+        };
+        let common = pool(Rarity::Common, &[100, 50, 50]);
+        let rare = pool(Rarity::Rare, &[30, 70]);
+        let legendary = pool(Rarity::Legendary, &[50, 50]);
+        let pools = [&common, &rare, &legendary];
+        
+        // Count the cards drawn for every rarity over whole cycles of the card roll
+        let mut drops = [[0u32; 3]; 3];
+        for random_value in 0..200 * 100u64 {
+            let rarity = roll_rarity(random_value).to_discriminant() as usize;
+            drops[rarity][roll_gacha_card(pools, random_value).unwrap() as usize] += 1;
+        }
+        
+        // 200 card rolls each for 70 Common, 27 Rare and 3 Legendary rarity rolls
+        assert_eq!(drops[0], [7_000, 3_500, 3_500]);
+        assert_eq!(drops[1], [1_620, 3_780, 0]);
+        assert_eq!(drops[2], [300, 300, 0]);
+        
+        // Pack slots pick within the rolled rarity the same way
+        let pack = PackType {
+            pack_type: 0,
+            name: String::from("Standard"),
+            price: 100,
+            card_count: 1,
+            guaranteed_rarity: Rarity::Legendary,
+            trait_filter: None,
+            is_active: true,
+            bump: 0,
+            set_id: 0,
+            card_type_ids: Vec::new(),
+        };
+        let first = (0..200 * 100u64)
+            .filter(|r| roll_pack_cards(&pack, pools, |_| *r).unwrap() == vec![0])
+            .count();
+        assert_eq!(first, 10_000);
+    }
+    
+    // Feature: 404-zoo-contract, Property 58: Pulls are rolled from a later slot's hash
+    #[test]
+    fn test_pull_commit_and_reveal() {
+        // SlotHashes data: count, then (slot, hash) newest first
+        let mut slot_hashes = 3u64.to_le_bytes().to_vec();
+        for slot in [105u64, 104, 102] {
+            slot_hashes.extend_from_slice(&slot.to_le_bytes());
+            slot_hashes.extend_from_slice(&[slot as u8; 32]);
+        }
+        assert_eq!(find_slot_hash(&slot_hashes, 105), Some([105; 32]));
+        assert_eq!(find_slot_hash(&slot_hashes, 104), Some([104; 32]));
+        assert_eq!(find_slot_hash(&slot_hashes, 102), Some([102; 32]));
+        assert_eq!(find_slot_hash(&slot_hashes, 103), None);
+        assert_eq!(find_slot_hash(&slot_hashes, 106), None);
+        assert_eq!(find_slot_hash(&slot_hashes, 90), None);
+        assert_eq!(find_slot_hash(&[], 105), None);
+        assert_eq!(find_slot_hash(&slot_hashes[..48], 102), None);
+        
+        // Not in the slot the pull was paid in; after that from the slot's hash
+        let player = Pubkey::new_unique();
+        let mut pending_pull = PendingPull::new(player, PullSource::Gacha, 104, 3, 255);
+        assert!(pull_seed(&pending_pull, &slot_hashes, 104).is_err());
+        let (seed, expired) = pull_seed(&pending_pull, &slot_hashes, 106).unwrap();
+        assert!(!expired);
+        assert_eq!(pull_seed(&pending_pull, &slot_hashes, 300).unwrap(), (seed, false));
+        
+        // Other players' pulls in the same slot roll differently
+        let other = PendingPull::new(Pubkey::new_unique(), PullSource::Gacha, 104, 3, 255);
+        assert_ne!(pull_seed(&other, &slot_hashes, 106).unwrap().0, seed);
+        
+        // Stats don't follow the card roll
+        assert_ne!(pull_card_random(&seed, 0, false), pull_stats_random(&seed, 0));
+        assert_ne!(pull_card_random(&seed, 0, false), pull_card_random(&seed, 1, false));
+        
+        // An aged-out hash rolls Common for every card
+        let stale = PendingPull::new(player, PullSource::Gacha, 50, 10, 255);
+        let (stale_seed, expired) = pull_seed(&stale, &slot_hashes, 600).unwrap();
+        assert!(expired);
+        for index in 0..10 {
+            assert_eq!(roll_rarity(pull_card_random(&stale_seed, index, expired)), Rarity::Common);
+        }
+        
+        // ...and minimum stats, crafted cards included
+        let mut crafted = PendingPull::new(player, PullSource::Craft { card_type_id: 418 }, 50, 1, 255);
+        crafted.reveal(vec![418], stale_seed, expired);
+        assert_eq!(pull_stats_random(&crafted.seed, 0), 0);
+        let template = new_test_template(418);
+        assert_eq!(
+            roll_card_stats(template.min_attack, template.max_attack, template.min_health, template.max_health, 0),
+            (template.min_attack, template.min_health)
+        );
+        
+        // A reroll is the largest source
+        let reroll = PullSource::Reroll {
+            mint: Pubkey::new_unique(),
+            currency: EntryCurrency::Bug,
+            price: 50,
+            keep_better: true,
+        };
+        assert_eq!(reroll.try_to_vec().unwrap().len(), PullSource::LEN);
+        
+        // A pull is revealed once
+        pending_pull.reveal(vec![401, 402, 401], seed, false);
+        assert_eq!(pending_pull.seed, seed);
+        assert!(pull_seed(&pending_pull, &slot_hashes, 106).is_err());
+    }
+    
+    // Feature: 404-zoo-contract, Property 45: Banner drop table and schedule
+    #[test]
+    fn test_banner_entries_and_window() {
+        let entry = |card_type_id: u32, weight: u16| PoolEntry { card_type_id, weight };
+        
+        // Mixed rarities are fine on a banner; featured 503 gets 3x the rate
+        let entries = vec![entry(500, 100), entry(503, 300)];
+        let templates = vec![new_test_template(500), new_test_template(503)];
+        assert!(validate_banner_entries(&entries, &templates).is_ok());
+        assert_eq!(select_weighted_card(&entries, 99).unwrap(), 500);
+        assert_eq!(select_weighted_card(&entries, 100).unwrap(), 503);
+        
+        // Empty, duplicate, zero weight, mismatched or retired templates
+        assert!(validate_banner_entries(&[], &[]).is_err());
+        assert!(validate_banner_entries(&[entry(500, 100), entry(500, 100)], &[new_test_template(500), new_test_template(500)]).is_err());
+        assert!(validate_banner_entries(&[entry(500, 0)], &[new_test_template(500)]).is_err());
+        assert!(validate_banner_entries(&[entry(500, 100)], &[new_test_template(503)]).is_err());
+        let mut retired = new_test_template(500);
+        retired.is_retired = true;
+        assert!(validate_banner_entries(&[entry(500, 100)], &[retired]).is_err());
+        
+        let too_many: Vec<PoolEntry> = (0..=Banner::MAX_ENTRIES as u32).map(|id| entry(id, 100)).collect();
+        let too_many_templates: Vec<CardTemplate> = (0..=Banner::MAX_ENTRIES as u32).map(new_test_template).collect();
+        assert!(validate_banner_entries(&too_many, &too_many_templates).is_err());
+        
+        // Active from start_time up to, but not including, end_time
+        let banner = Banner {
+            banner_id: 5,
+            name: String::from("5xx Server Error week"),
+            start_time: 1_000,
+            end_time: 2_000,
+            entries,
+            currency: EntryCurrency::Tickets,
+            price: 1,
+            pull_limit: 10,
+            total_pulls: 0,
+            bump: 0,
+            set_id: 0,
+        };
+        assert!(!is_banner_active(&banner, 999));
+        assert!(is_banner_active(&banner, 1_000));
+        assert!(is_banner_active(&banner, 1_999));
+        assert!(!is_banner_active(&banner, 2_000));
+        
+        // Pulls are rolled from the drop table, given the entries' templates in order
+        let legendary = CardTemplate { rarity: Rarity::Legendary, ..new_test_template(503) };
+        let templates = vec![new_test_template(500), legendary.clone()];
+        assert_eq!(roll_banner_card(&banner.entries, &templates, 99, false).unwrap(), 500);
+        assert_eq!(roll_banner_card(&banner.entries, &templates, 100, false).unwrap(), 503);
+        assert!(roll_banner_card(&banner.entries, &templates[..1], 99, false).is_err());
+        assert!(roll_banner_card(&banner.entries, &[legendary.clone(), new_test_template(500)], 99, false).is_err());
+        
+        // An aged-out slot hash never gets the featured card: the most common card of
+        // the lowest rarity, the first of them on a tie
+        for random_value in [0, 99, 100, 399] {
+            assert_eq!(roll_banner_card(&banner.entries, &templates, random_value, true).unwrap(), 500);
+        }
+        let entries = vec![entry(501, 50), entry(503, 300), entry(500, 100), entry(502, 100)];
+        let mut templates = vec![new_test_template(501), legendary, new_test_template(500), new_test_template(502)];
+        assert_eq!(roll_banner_card(&entries, &templates, 399, true).unwrap(), 500);
+        
+        // Retired cards stop dropping from live banners: [0, 50) -> 501, [50, 350) -> 503, [350, 450) -> 502
+        templates[2].is_retired = true;
+        assert_eq!(roll_banner_card(&entries, &templates, 399, true).unwrap(), 502);
+        for random_value in 0..900 {
+            assert_ne!(roll_banner_card(&entries, &templates, random_value, false).unwrap(), 500);
+        }
+        assert_eq!(roll_banner_card(&entries, &templates, 349, false).unwrap(), 503);
+        assert_eq!(roll_banner_card(&entries, &templates, 350, false).unwrap(), 502);
+        for template in templates.iter_mut() {
+            template.is_retired = true;
+        }
+        assert!(roll_banner_card(&entries, &templates, 0, false).is_err());
+    }
+    
+    // Feature: 404-zoo-contract, Property 46: Pack guarantees and restrictions
+    #[test]
+    fn test_pack_slot_rarity_and_restrictions() {
+        // Only the last slot is raised, and never lowered
+        assert_eq!(pack_slot_rarity(Rarity::Common, 0, 5, Rarity::Rare), Rarity::Common);
+        assert_eq!(pack_slot_rarity(Rarity::Common, 4, 5, Rarity::Rare), Rarity::Rare);
+        assert_eq!(pack_slot_rarity(Rarity::Legendary, 4, 5, Rarity::Rare), Rarity::Legendary);
+        assert_eq!(pack_slot_rarity(Rarity::Common, 0, 1, Rarity::Legendary), Rarity::Legendary);
+        assert_eq!(pack_slot_rarity(Rarity::Common, 9, 10, Rarity::Common), Rarity::Common);
+        
+        let entries = vec![
+            PoolEntry { card_type_id: 400, weight: 100 },
+            PoolEntry { card_type_id: 401, weight: 100 },
+            PoolEntry { card_type_id: 403, weight: 100 },
+        ];
+        assert_eq!(eligible_pool_entries(&entries, None), entries);
+        
+        // Restricted packs only draw the eligible cards that are in the pool
+        let eligible = eligible_pool_entries(&entries, Some(&[401, 999]));
+        assert_eq!(eligible, vec![PoolEntry { card_type_id: 401, weight: 100 }]);
+        for roll in 0..300 {
+            assert_eq!(select_weighted_card(&eligible, roll).unwrap(), 401);
+        }
+        
+        // No eligible card of the rolled rarity: the slot falls back to the closest
+        // rarity the pack has cards of, lower first and never below the guarantee
+        assert_eq!(pack_slot_fallbacks(Rarity::Common, Rarity::Common), vec![Rarity::Common, Rarity::Rare, Rarity::Legendary]);
+        assert_eq!(pack_slot_fallbacks(Rarity::Legendary, Rarity::Common), vec![Rarity::Legendary, Rarity::Rare, Rarity::Common]);
+        assert_eq!(pack_slot_fallbacks(Rarity::Legendary, Rarity::Rare), vec![Rarity::Legendary, Rarity::Rare]);
+        assert_eq!(pack_slot_fallbacks(Rarity::Rare, Rarity::Rare), vec![Rarity::Rare, Rarity::Legendary]);
+        
+        let pool = |rarity: Rarity, card_type_ids: &[u32]| RarityPool {
+            rarity,
+            entries: card_type_ids.iter().map(|id| PoolEntry { card_type_id: *id, weight: 100 }).collect(),
+            bump: 0,
+            set_id: 0,
+        };
+        let common = pool(Rarity::Common, &[400, 401]);
+        let rare = pool(Rarity::Rare, &[402]);
+        let legendary = pool(Rarity::Legendary, &[403]);
+        let mut pack = PackType {
+            pack_type: 1,
+            name: String::from("Archers"),
+            price: 100,
+            card_count: 5,
+            guaranteed_rarity: Rarity::Rare,
+            trait_filter: Some(TraitType::Archer),
+            is_active: true,
+            bump: 0,
+            set_id: 0,
+            card_type_ids: vec![401, 403],
+        };
+        for seed in 0..200 {
+            let cards = roll_pack_cards(&pack, [&common, &rare, &legendary], |slot| seed * 7 + slot * 13).unwrap();
+            assert_eq!(cards.len(), 5);
+            assert!(cards.iter().all(|id| pack.card_type_ids.contains(id)));
+            assert_eq!(cards[4], 403);
+        }
+        
+        // Unfiltered packs draw from the whole pool; a pack with no card at all fails
+        pack.trait_filter = None;
+        let cards = roll_pack_cards(&pack, [&common, &rare, &legendary], |slot| slot).unwrap();
+        assert!(cards.iter().all(|id| (400..=403).contains(id)));
+        
+        // Revealed after the slot hash aged out: Common, but for the guaranteed last card
+        let cards = roll_pack_cards(&pack, [&common, &rare, &legendary], |slot| pull_card_random(&[7; 32], slot as u8, true)).unwrap();
+        assert!(cards[..4].iter().all(|id| [400, 401].contains(id)));
+        assert_eq!(cards[4], 402);
+        pack.trait_filter = Some(TraitType::Archer);
+        pack.card_type_ids = vec![999];
+        assert!(roll_pack_cards(&pack, [&common, &rare, &legendary], |slot| slot).is_err());
+    }
+    
+    // Feature: 404-zoo-contract, Property 47: Card set scoping
+    #[test]
+    fn test_card_set_scoping() {
+        let template = |card_type_id: u32, set_id: u16| CardTemplate {
+            trait_type: TraitType::Assassin,
+            rarity: Rarity::Rare,
+            set_id,
+            ..new_test_template(card_type_id)
+        };
+        
+        // "HTTP status" (set 1) cards can't go into the "Unix errno" (set 2) pool
+        let http = vec![template(404, 1), template(503, 1)];
+        assert!(validate_set_templates(1, &http).is_ok());
+        assert!(validate_set_templates(2, &http).is_err());
+        assert!(validate_set_templates(1, &[template(404, 1), template(2, 0)]).is_err());
+        
+        // Set packs go on sale at release_time, and stop when the set is deactivated
+        let mut card_set = CardSet {
+            set_id: 2,
+            name: String::from("Unix errno"),
+            release_time: 5_000,
+            is_active: true,
+            bump: 0,
+        };
+        assert!(!is_card_set_available(&card_set, 4_999));
+        assert!(is_card_set_available(&card_set, 5_000));
+        card_set.is_active = false;
+        assert!(!is_card_set_available(&card_set, 6_000));
+    }
+    
+    // Feature: 404-zoo-contract, Property 48: Burn dust scales with rarity and rolls
+    #[test]
+    fn test_burn_and_craft_dust() {
+        let template = |rarity: Rarity| CardTemplate {
+            name: String::from("500 Internal Server Error"),
+            rarity,
+            min_attack: 10,
+            max_attack: 20,
+            min_health: 30,
+            max_health: 50,
+            description: String::from("Something went wrong"),
+            ..new_test_template(500)
+        };
+        
+        // Min roll gives the base value, max roll doubles it
+        let legendary = template(Rarity::Legendary);
+        assert_eq!(calculate_burn_dust(&legendary, 10, 30), 100);
+        assert_eq!(calculate_burn_dust(&legendary, 20, 50), 200);
+        assert_eq!(calculate_burn_dust(&legendary, 15, 40), 150);
+        assert!(calculate_burn_dust(&template(Rarity::Common), 20, 50) < calculate_burn_dust(&legendary, 10, 30));
+        
+        // Rolls outside a patched range are clamped
+        assert_eq!(calculate_burn_dust(&legendary, 5, 99), 150);
+        assert_eq!(roll_quality_pct(7, 7, 7), 100);
+        
+        // Burning never pays for a craft of the same rarity
+        for rarity in [Rarity::Common, Rarity::Rare, Rarity::Legendary] {
+            assert!(calculate_burn_dust(&template(rarity), 20, 50) < calculate_craft_cost(rarity));
+        }
+    }
+    
+    // Feature: 404-zoo-contract, Property 49: Stat rerolls stay in range
+    #[test]
+    fn test_stat_reroll() {
+        // Rerolls use the template's range like the original mint
+        for seed in 0..500u64 {
+            let rolled = roll_card_stats(10, 20, 30, 50, seed.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+            assert!((10..=20).contains(&rolled.0) && (30..=50).contains(&rolled.1));
+            
+            // keep_better never lowers a stat, even one above a patched-down range
+            let kept = apply_reroll((25, 31), rolled, true);
+            assert_eq!(kept, (25, rolled.1.max(31)));
+            assert_eq!(apply_reroll((25, 31), rolled, false), rolled);
+        }
+        
+        // Each stat is compared on its own
+        assert_eq!(apply_reroll((20, 30), (10, 50), true), (20, 50));
+    }
+    
+    // Feature: 404-zoo-contract, Property 50: Card levels and stat caps
+    #[test]
+    fn test_card_levelling() {
+        assert!(validate_level_thresholds(&[100, 300, 600]).is_ok());
+        assert!(validate_level_thresholds(&[]).is_ok());
+        assert!(validate_level_thresholds(&[0, 100]).is_err());
+        assert!(validate_level_thresholds(&[100, 100]).is_err());
+        assert!(validate_level_thresholds(&[1; 21]).is_err());
+        
+        let config = ProgressionConfig {
+            xp_per_win: 100,
+            xp_per_loss: 40,
+            level_thresholds: vec![100, 300, 600],
+            attack_per_level: 2,
+            health_per_level: 5,
+            max_bonus_bps: 1_000,
+            bump: 0,
+        };
+        assert_eq!(level_for_xp(&config.level_thresholds, 99), 0);
+        assert_eq!(level_for_xp(&config.level_thresholds, 300), 2);
+        assert_eq!(level_for_xp(&config.level_thresholds, 10_000), 3);
+        
+        let template = CardTemplate {
+            name: String::from("418 I'm a teapot"),
+            trait_type: TraitType::Archer,
+            rarity: Rarity::Rare,
+            min_attack: 10,
+            max_attack: 20,
+            min_health: 40,
+            max_health: 50,
+            description: String::from("Short and stout"),
+            ..new_test_template(418)
+        };
+        let mut card = CardInstance {
+            mint: Pubkey::default(),
+            card_type_id: 418,
+            attack: 19,
+            health: 40,
+            owner: Pubkey::default(),
+            bump: 0,
+            xp: 0,
+            level: 0,
+        };
+        
+        // Losses still count, level-ups apply once per level
+        assert_eq!(grant_card_xp(&mut card, &template, &config, config.xp_per_loss), 0);
+        assert_eq!(grant_card_xp(&mut card, &template, &config, config.xp_per_win), 1);
+        assert_eq!((card.level, card.attack, card.health), (1, 21, 45));
+        
+        // Attack is capped at 20 + 10%; health keeps growing below its cap of 55
+        assert_eq!(grant_card_xp(&mut card, &template, &config, 500), 2);
+        assert_eq!((card.xp, card.level, card.attack, card.health), (640, 3, 22, 55));
+        assert_eq!(grant_card_xp(&mut card, &template, &config, 1_000), 0);
+        assert_eq!((card.attack, card.health), (22, 55));
+        
+        // A stat above the cap after a balance patch is never lowered
+        assert_eq!(level_up_stat(30, 1, 2, 20, 1_000), 30);
+        
+        // A reroll of a level 3 card keeps its level-ups on top of the new roll
+        let rolled = level_up_stats((10, 40), card.level, &template, &config);
+        assert_eq!(rolled, (16, 55));
+        assert_eq!(apply_reroll((card.attack, card.health), rolled, false), (16, 55));
+    }
+    
+    // Feature: 404-zoo-contract, Property 51: Trait synergy tiers
+    #[test]
+    fn test_trait_synergies() {
+        let tier = |trait_type, min_units, bonus_bps| SynergyTier {
+            trait_type,
+            min_units,
+            attack_bonus_bps: bonus_bps,
+            health_bonus_bps: bonus_bps,
+        };
+        let tiers = vec![
+            tier(TraitType::Tank, 2, 1_000),
+            tier(TraitType::Tank, 4, 2_500),
+            tier(TraitType::Mage, 3, 2_000),
+        ];
+        assert!(validate_synergy_tiers(&tiers).is_ok());
+        assert!(validate_synergy_tiers(&[tier(TraitType::Tank, 0, 1_000)]).is_err());
+        assert!(validate_synergy_tiers(&[tier(TraitType::Tank, 11, 1_000)]).is_err());
+        assert!(validate_synergy_tiers(&[tier(TraitType::Tank, 2, 10_001)]).is_err());
+        assert!(validate_synergy_tiers(&[tier(TraitType::Tank, 2, 100), tier(TraitType::Tank, 2, 200)]).is_err());
+        assert!(validate_synergy_tiers(&[tier(TraitType::Tank, 2, 100); 25]).is_err());
+        
+        // Only the highest tier reached counts; traits below every tier get nothing
+        let traits = [
+            TraitType::Mage,
+            TraitType::Tank,
+            TraitType::Tank,
+            TraitType::Tank,
+            TraitType::Mage,
+            TraitType::Warrior,
+        ];
+        let synergies = active_synergies(&tiers, &traits);
+        assert_eq!(synergies, vec![ActiveSynergy {
+            trait_type: TraitType::Tank,
+            units: 3,
+            attack_bonus_bps: 1_000,
+            health_bonus_bps: 1_000,
+        }]);
+        let synergies = active_synergies(&tiers, &[TraitType::Tank; 5]);
+        assert_eq!((synergies[0].units, synergies[0].attack_bonus_bps), (5, 2_500));
+        assert!(active_synergies(&[], &traits).is_empty());
+        
+        assert_eq!(apply_synergy(TraitType::Tank, 20, 55, &synergies), (25, 68));
+        assert_eq!(apply_synergy(TraitType::Mage, 20, 55, &synergies), (20, 55));
+        assert_eq!(apply_synergy(TraitType::Tank, u16::MAX, 1, &synergies), (u16::MAX, 1));
+    }
+    
+    // Feature: 404-zoo-contract, Property 52: Collection pages index owned cards
+    #[test]
+    fn test_collection_pages() {
+        let mut collection = Collection {
+            owner: Pubkey::new_unique(),
+            page: 0,
+            card_mints: Vec::new(),
+            bump: 0,
+        };
+        let first = Pubkey::new_unique();
+        let second = Pubkey::new_unique();
+        
+        add_to_collection(&mut collection, first).unwrap();
+        add_to_collection(&mut collection, second).unwrap();
+        add_to_collection(&mut collection, first).unwrap();
+        assert_eq!(collection.card_mints, vec![first, second]);
+        
+        remove_from_collection(&mut collection, &first).unwrap();
+        assert_eq!(collection.card_mints, vec![second]);
+        assert!(remove_from_collection(&mut collection, &first).is_err());
+        
+        // A full page rejects new cards but still accepts ones it holds
+        collection.card_mints = (0..Collection::MAX_CARDS).map(|_| Pubkey::new_unique()).collect();
+        let held = collection.card_mints[0];
+        assert!(add_to_collection(&mut collection, Pubkey::new_unique()).is_err());
+        assert!(add_to_collection(&mut collection, held).is_ok());
+        assert_eq!(collection.card_mints.len(), Collection::MAX_CARDS);
+        
+        // Other pages are checked in place before sync_collection adds a card
+        collection.card_mints = vec![first, second];
+        let mut data = Vec::new();
+        collection.try_serialize(&mut data).unwrap();
+        data.resize(Collection::LEN, 0);
+        assert!(collection_page_contains(&data, &second));
+        assert!(!collection_page_contains(&data, &held));
+        assert!(!collection_page_contains(&data[..20], &first));
+    }
+    
+    // Feature: 404-zoo-contract, Property 53: Migrated profiles keep their data
+    #[test]
+    fn test_profile_migration() {
+        // A profile in the original layout (110 bytes), zero-filled to the current size
+        let wallet = Pubkey::new_unique();
+        let mut data = <PlayerProfile as anchor_lang::Discriminator>::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(wallet.as_ref());
+        data.extend_from_slice(&String::from("tester").try_to_vec().unwrap());
+        data.push(1);                                   // has_claimed_starter_pack
+        data.extend_from_slice(&3u64.to_le_bytes());    // gacha_tickets
+        data.extend_from_slice(&250u64.to_le_bytes());  // bug_balance
+        data.extend_from_slice(&640u32.to_le_bytes());  // trophies
+        data.extend_from_slice(&12u32.to_le_bytes());   // total_wins
+        data.extend_from_slice(&7u32.to_le_bytes());    // total_losses
+        data.extend_from_slice(&2u32.to_le_bytes());    // win_streak
+        data.push(254);                                 // bump
+        data.resize(110, 0);
+        data.resize(PlayerProfile::LEN, 0);
+        
+        let mut profile = PlayerProfile::try_deserialize(&mut &data[..]).unwrap();
+        init_migrated_profile(&mut profile, 3);
+        assert_eq!(profile.wallet, wallet);
+        assert_eq!((profile.bug_balance, profile.trophies, profile.total_losses, profile.bump), (250, 640, 7, 254));
+        assert_eq!(profile.rating, PlayerProfile::INITIAL_RATING);
+        assert_eq!((profile.season_id, profile.season_best_trophies), (3, 640));
+        assert_eq!((profile.crafting_dust, profile.collection_pages), (0, 0));
+        
+        // The migrated profile fits its account again
+        let mut resized = [0u8; PlayerProfile::LEN];
+        profile.try_serialize(&mut &mut resized[..]).unwrap();
+        
+        // Profiles that already have a rating and season are left as they are
+        let mut current = new_test_profile();
+        current.rating = 1_200;
+        current.season_id = 2;
+        init_migrated_profile(&mut current, 3);
+        assert_eq!((current.rating, current.season_id), (1_200, 2));
+    }
+    
+    // Feature: 404-zoo-contract, Property 54: Migrated rarity pools keep their cards
+    #[test]
+    fn test_rarity_pool_migration() {
+        let legacy = LegacyRarityPool { rarity: Rarity::Rare, card_type_ids: vec![418, 503], bump: 253 };
+        let mut data = <RarityPool as anchor_lang::Discriminator>::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&legacy.try_to_vec().unwrap());
+        data.resize(LegacyRarityPool::LEN, 0);
+        
+        let decoded = LegacyRarityPool::deserialize(&mut &data[8..]).unwrap();
+        assert_eq!(decoded.card_type_ids, vec![418, 503]);
+        
+        // Every card keeps the same chance, as a standard pool
+        let pool = decoded.into_pool();
+        assert_eq!(pool.rarity, Rarity::Rare);
+        assert_eq!((pool.bump, pool.set_id), (253, 0));
+        assert_eq!(
+            pool.entries,
+            vec![
+                PoolEntry { card_type_id: 418, weight: PoolEntry::DEFAULT_WEIGHT },
+                PoolEntry { card_type_id: 503, weight: PoolEntry::DEFAULT_WEIGHT },
+            ]
+        );
+        
+        // A full legacy pool fits the current layout
+        let full = LegacyRarityPool { rarity: Rarity::Common, card_type_ids: (0..100).collect(), bump: 0 };
+        let mut resized = vec![0u8; RarityPool::LEN];
+        full.into_pool().try_serialize(&mut &mut resized[..]).unwrap();
+    }
+    
+    // Feature: 404-zoo-contract, Property 56: Migrated configs and templates
+    #[test]
+    fn test_config_and_template_migration() {
+        // A game config from before seasons, with every card creator slot used, only
+        // deserializes once grown, and starts in pre-season
+        let authority = Pubkey::new_unique();
+        let mut data = <GameConfig as anchor_lang::Discriminator>::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(authority.as_ref());
+        data.extend_from_slice(&vec![Pubkey::new_unique(); GameConfig::MAX_CARD_CREATORS].try_to_vec().unwrap());
+        data.extend_from_slice(&500u64.to_le_bytes());  // normal_pack_price
+        data.push(10);                                  // starter_pack_card_count
+        data.extend_from_slice(&1_000u64.to_le_bytes());// sol_to_bug_rate
+        data.extend_from_slice(&100u64.to_le_bytes());  // ticket_price
+        data.push(255);                                 // bump
+        assert_eq!(data.len(), GameConfig::LEN - 8);
+        assert!(GameConfig::try_deserialize(&mut &data[..]).is_err());
+        
+        data.resize(GameConfig::LEN, 0);
+        let game_config = GameConfig::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!((game_config.authority, game_config.ticket_price, game_config.bump), (authority, 100, 255));
+        assert_eq!((game_config.current_season_id, game_config.season_trophy_floor), (0, 0));
+        
+        // A template in the original layout (467 bytes) with every string at its longest
+        let mut data = <CardTemplate as anchor_lang::Discriminator>::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&404u32.to_le_bytes());  // card_type_id
+        data.extend_from_slice(&"N".repeat(CardTemplate::MAX_NAME_LEN).try_to_vec().unwrap());
+        data.extend_from_slice(&[TraitType::Tank as u8, Rarity::Rare as u8]);
+        for stat in [10u16, 20, 30, 40] {
+            data.extend_from_slice(&stat.to_le_bytes());
+        }
+        data.extend_from_slice(&"D".repeat(CardTemplate::MAX_DESCRIPTION_LEN).try_to_vec().unwrap());
+        data.extend_from_slice(&"I".repeat(CardTemplate::MAX_IMAGE_URI_LEN).try_to_vec().unwrap());
+        data.push(252);                                 // bump
+        assert_eq!(data.len(), 467);
+        assert!(CardTemplate::try_deserialize(&mut &data[..]).is_err());
+        
+        data.resize(CardTemplate::LEN, 0);
+        let mut template = CardTemplate::try_deserialize(&mut &data[..]).unwrap();
+        init_migrated_template(&mut template, authority);
+        assert_eq!((template.card_type_id, template.rarity, template.max_health, template.bump), (404, Rarity::Rare, 40, 252));
+        assert_eq!((template.creator, template.version), (authority, 1));
+        assert_eq!((template.is_retired, template.set_id, template.ability), (false, 0, Ability::None));
+        
+        // Templates created since keep their creator and version
+        let creator = Pubkey::new_unique();
+        template.creator = creator;
+        template.version = 3;
+        init_migrated_template(&mut template, authority);
+        assert_eq!((template.creator, template.version), (creator, 3));
+    }
+        
     // Feature: 404-zoo-contract, Property 20: NFT metadata card type ID
     // Feature: 404-zoo-contract, Property 21: NFT mint address uniqueness
     // Feature: 404-zoo-contract, Property 23: No star levels in NFT data
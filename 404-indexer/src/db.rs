@@ -0,0 +1,493 @@
+//! SQLite store for the materialised game state.
+//!
+//! Events are applied incrementally and recorded by (signature, event_index),
+//! so replaying the same transactions twice is a no-op. Account snapshots are
+//! authoritative and overwrite whatever the events produced.
+
+use anyhow::Result;
+use rusqlite::{params, Connection};
+use std::path::Path;
+use zoo_contract::PlayerProfile;
+
+use crate::decode::{ZooAccount, ZooEvent};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS game_config (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    address TEXT NOT NULL,
+    authority TEXT NOT NULL,
+    card_creators TEXT NOT NULL,
+    normal_pack_price INTEGER NOT NULL,
+    starter_pack_card_count INTEGER NOT NULL,
+    sol_to_bug_rate INTEGER NOT NULL,
+    ticket_price INTEGER NOT NULL,
+    current_season_id INTEGER NOT NULL,
+    season_trophy_floor INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS players (
+    wallet TEXT PRIMARY KEY,
+    username TEXT NOT NULL,
+    has_claimed_starter_pack INTEGER NOT NULL DEFAULT 0,
+    gacha_tickets INTEGER NOT NULL DEFAULT 0,
+    bug_balance INTEGER NOT NULL DEFAULT 0,
+    trophies INTEGER NOT NULL DEFAULT 0,
+    rating INTEGER NOT NULL DEFAULT 0,
+    total_wins INTEGER NOT NULL DEFAULT 0,
+    total_losses INTEGER NOT NULL DEFAULT 0,
+    total_draws INTEGER NOT NULL DEFAULT 0,
+    win_streak INTEGER NOT NULL DEFAULT 0,
+    tier INTEGER NOT NULL DEFAULT 0,
+    season_id INTEGER NOT NULL DEFAULT 0,
+    season_best_trophies INTEGER NOT NULL DEFAULT 0
+);
+CREATE INDEX IF NOT EXISTS players_by_trophies ON players (trophies DESC);
+
+CREATE TABLE IF NOT EXISTS cards (
+    mint TEXT PRIMARY KEY,
+    owner TEXT NOT NULL,
+    card_type_id INTEGER NOT NULL,
+    attack INTEGER NOT NULL,
+    health INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS cards_by_owner ON cards (owner);
+
+CREATE TABLE IF NOT EXISTS listings (
+    card_mint TEXT PRIMARY KEY,
+    seller TEXT NOT NULL,
+    price INTEGER NOT NULL,
+    is_active INTEGER NOT NULL,
+    created_at INTEGER,
+    buyer TEXT
+);
+CREATE INDEX IF NOT EXISTS listings_by_active ON listings (is_active, price);
+
+CREATE TABLE IF NOT EXISTS matches (
+    signature TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    block_time INTEGER,
+    player_a TEXT NOT NULL,            -- winner, or first player of a draw
+    player_b TEXT NOT NULL,            -- loser, or second player of a draw
+    is_draw INTEGER NOT NULL,
+    trophy_gain INTEGER NOT NULL,
+    trophy_loss INTEGER NOT NULL,
+    rating_change INTEGER NOT NULL,    -- from player_a's point of view
+    PRIMARY KEY (signature, event_index)
+);
+
+CREATE TABLE IF NOT EXISTS events (
+    signature TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    block_time INTEGER,
+    name TEXT NOT NULL,
+    data BLOB NOT NULL,                -- discriminator + borsh body, as emitted
+    PRIMARY KEY (signature, event_index)
+);
+";
+
+/// Where an event was emitted
+pub struct EventSource<'a> {
+    pub signature: &'a str,
+    pub event_index: u32,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+}
+
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    pub fn open(path: &Path) -> Result<Self> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Store { conn })
+    }
+
+    #[cfg(test)]
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    pub fn begin(&self) -> Result<()> {
+        self.conn.execute_batch("BEGIN IMMEDIATE")?;
+        Ok(())
+    }
+
+    pub fn commit(&self) -> Result<()> {
+        self.conn.execute_batch("COMMIT")?;
+        Ok(())
+    }
+
+    /// Listing accounts are closed on sale/cancel, so a full snapshot only
+    /// contains active ones. Call before upserting a snapshot's listings.
+    pub fn deactivate_listings(&self) -> Result<()> {
+        self.conn.execute("UPDATE listings SET is_active = 0", [])?;
+        Ok(())
+    }
+
+    /// Overwrite the stored row with the account's current state
+    pub fn upsert_account(&self, address: &str, account: &ZooAccount) -> Result<()> {
+        match account {
+            ZooAccount::GameConfig(config) => {
+                let creators = config
+                    .card_creators
+                    .iter()
+                    .map(|c| c.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",");
+                self.conn.execute(
+                    "INSERT OR REPLACE INTO game_config (id, address, authority, card_creators,
+                        normal_pack_price, starter_pack_card_count, sol_to_bug_rate, ticket_price,
+                        current_season_id, season_trophy_floor)
+                     VALUES (1, ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                    params![
+                        address,
+                        config.authority.to_string(),
+                        creators,
+                        config.normal_pack_price,
+                        config.starter_pack_card_count,
+                        config.sol_to_bug_rate,
+                        config.ticket_price,
+                        config.current_season_id,
+                        config.season_trophy_floor,
+                    ],
+                )?;
+            }
+            ZooAccount::PlayerProfile(profile) => {
+                self.conn.execute(
+                    "INSERT INTO players (wallet, username, has_claimed_starter_pack, gacha_tickets,
+                        bug_balance, trophies, rating, total_wins, total_losses, total_draws,
+                        win_streak, tier, season_id, season_best_trophies)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
+                     ON CONFLICT (wallet) DO UPDATE SET
+                        username = excluded.username,
+                        has_claimed_starter_pack = excluded.has_claimed_starter_pack,
+                        gacha_tickets = excluded.gacha_tickets,
+                        bug_balance = excluded.bug_balance,
+                        trophies = excluded.trophies,
+                        rating = excluded.rating,
+                        total_wins = excluded.total_wins,
+                        total_losses = excluded.total_losses,
+                        total_draws = excluded.total_draws,
+                        win_streak = excluded.win_streak,
+                        tier = excluded.tier,
+                        season_id = excluded.season_id,
+                        season_best_trophies = excluded.season_best_trophies",
+                    params![
+                        profile.wallet.to_string(),
+                        profile.username,
+                        profile.has_claimed_starter_pack,
+                        profile.gacha_tickets,
+                        profile.bug_balance,
+                        profile.trophies,
+                        profile.rating,
+                        profile.total_wins,
+                        profile.total_losses,
+                        profile.total_draws,
+                        profile.win_streak,
+                        profile.tier.index(),
+                        profile.season_id,
+                        profile.season_best_trophies,
+                    ],
+                )?;
+            }
+            ZooAccount::CardInstance(card) => {
+                self.conn.execute(
+                    "INSERT OR REPLACE INTO cards (mint, owner, card_type_id, attack, health)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![
+                        card.mint.to_string(),
+                        card.owner.to_string(),
+                        card.card_type_id,
+                        card.attack,
+                        card.health,
+                    ],
+                )?;
+            }
+            ZooAccount::Listing(listing) => {
+                self.conn.execute(
+                    "INSERT OR REPLACE INTO listings (card_mint, seller, price, is_active, created_at, buyer)
+                     VALUES (?1, ?2, ?3, ?4, ?5, NULL)",
+                    params![
+                        listing.card_mint.to_string(),
+                        listing.seller.to_string(),
+                        listing.price,
+                        listing.is_active,
+                        listing.created_at,
+                    ],
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Record an event and apply it to the materialised tables.
+    /// Returns false if the event was already indexed.
+    pub fn apply_event(&self, source: &EventSource, event: &ZooEvent, data: &[u8]) -> Result<bool> {
+        let inserted = self.conn.execute(
+            "INSERT OR IGNORE INTO events (signature, event_index, slot, block_time, name, data)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                source.signature,
+                source.event_index,
+                source.slot,
+                source.block_time,
+                event.name(),
+                data,
+            ],
+        )?;
+        if inserted == 0 {
+            return Ok(false);
+        }
+
+        match event {
+            ZooEvent::PlayerRegistered(e) => {
+                self.conn.execute(
+                    "INSERT OR IGNORE INTO players (wallet, username, rating) VALUES (?1, ?2, ?3)",
+                    params![e.wallet.to_string(), e.username, PlayerProfile::INITIAL_RATING],
+                )?;
+            }
+            ZooEvent::TicketsClaimed(e) => {
+                self.conn.execute(
+                    "UPDATE players SET gacha_tickets = ?2, has_claimed_starter_pack = 1 WHERE wallet = ?1",
+                    params![e.wallet.to_string(), e.total_tickets],
+                )?;
+            }
+            ZooEvent::TicketsGranted(e) => {
+                self.conn.execute(
+                    "UPDATE players SET gacha_tickets = ?2 WHERE wallet = ?1",
+                    params![e.wallet.to_string(), e.total_tickets],
+                )?;
+            }
+            ZooEvent::TicketsPurchased(e) => {
+                self.conn.execute(
+                    "UPDATE players SET gacha_tickets = ?2, bug_balance = ?3 WHERE wallet = ?1",
+                    params![e.wallet.to_string(), e.total_tickets, e.bug_balance],
+                )?;
+            }
+            ZooEvent::BugPurchased(e) => {
+                self.conn.execute(
+                    "UPDATE players SET bug_balance = ?2 WHERE wallet = ?1",
+                    params![e.wallet.to_string(), e.bug_balance],
+                )?;
+            }
+            ZooEvent::CardMinted(e) => {
+                self.conn.execute(
+                    "INSERT OR REPLACE INTO cards (mint, owner, card_type_id, attack, health)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![e.mint.to_string(), e.owner.to_string(), e.card_type_id, e.attack, e.health],
+                )?;
+            }
+            ZooEvent::CardBurned(e) => {
+                self.conn.execute("DELETE FROM cards WHERE mint = ?1", params![e.mint.to_string()])?;
+            }
+            ZooEvent::CardStatsRerolled(e) => {
+                self.conn.execute(
+                    "UPDATE cards SET attack = ?2, health = ?3 WHERE mint = ?1",
+                    params![e.mint.to_string(), e.attack, e.health],
+                )?;
+            }
+            ZooEvent::CardXpGained(e) => {
+                self.conn.execute(
+                    "UPDATE cards SET attack = ?2, health = ?3 WHERE mint = ?1",
+                    params![e.mint.to_string(), e.attack, e.health],
+                )?;
+            }
+            ZooEvent::ListingCreated(e) => {
+                self.conn.execute(
+                    "INSERT OR REPLACE INTO listings (card_mint, seller, price, is_active, created_at, buyer)
+                     VALUES (?1, ?2, ?3, 1, ?4, NULL)",
+                    params![e.card_mint.to_string(), e.seller.to_string(), e.price, source.block_time],
+                )?;
+            }
+            ZooEvent::ListingCancelled(e) => {
+                self.conn.execute(
+                    "UPDATE listings SET is_active = 0 WHERE card_mint = ?1",
+                    params![e.card_mint.to_string()],
+                )?;
+            }
+            ZooEvent::ListingFilled(e) => {
+                self.conn.execute(
+                    "UPDATE listings SET is_active = 0, buyer = ?2 WHERE card_mint = ?1",
+                    params![e.card_mint.to_string(), e.buyer.to_string()],
+                )?;
+                self.conn.execute(
+                    "UPDATE cards SET owner = ?2 WHERE mint = ?1",
+                    params![e.card_mint.to_string(), e.buyer.to_string()],
+                )?;
+            }
+            ZooEvent::MatchRecorded(e) => {
+                self.conn.execute(
+                    "UPDATE players SET trophies = ?2, total_wins = total_wins + 1,
+                        win_streak = win_streak + 1, rating = rating + ?3,
+                        bug_balance = bug_balance + ?4
+                     WHERE wallet = ?1",
+                    params![e.winner.to_string(), e.winner_trophies, e.rating_change, e.winner_reward],
+                )?;
+                self.conn.execute(
+                    "UPDATE players SET trophies = ?2, total_losses = total_losses + 1,
+                        win_streak = 0, rating = MAX(rating - ?3, 0),
+                        bug_balance = bug_balance + ?4
+                     WHERE wallet = ?1",
+                    params![e.loser.to_string(), e.loser_trophies, e.rating_change, e.loser_reward],
+                )?;
+                self.insert_match(
+                    source,
+                    &e.winner.to_string(),
+                    &e.loser.to_string(),
+                    false,
+                    e.trophy_gain,
+                    e.trophy_loss,
+                    i64::from(e.rating_change),
+                )?;
+            }
+            ZooEvent::MatchDrawn(e) => {
+                self.conn.execute(
+                    "UPDATE players SET trophies = ?2, total_draws = total_draws + 1,
+                        rating = MAX(rating + ?3, 0)
+                     WHERE wallet = ?1",
+                    params![e.player_a.to_string(), e.player_a_trophies, e.rating_change],
+                )?;
+                self.conn.execute(
+                    "UPDATE players SET trophies = ?2, total_draws = total_draws + 1,
+                        rating = MAX(rating - ?3, 0)
+                     WHERE wallet = ?1",
+                    params![e.player_b.to_string(), e.player_b_trophies, e.rating_change],
+                )?;
+                self.insert_match(
+                    source,
+                    &e.player_a.to_string(),
+                    &e.player_b.to_string(),
+                    true,
+                    e.trophy_delta,
+                    0,
+                    e.rating_change,
+                )?;
+            }
+            ZooEvent::TierChanged(e) => {
+                self.conn.execute(
+                    "UPDATE players SET tier = ?2 WHERE wallet = ?1",
+                    params![e.wallet.to_string(), e.new_tier.index()],
+                )?;
+            }
+            ZooEvent::SeasonRewardsClaimed(e) => {
+                self.conn.execute(
+                    "UPDATE players SET bug_balance = bug_balance + ?2,
+                        gacha_tickets = gacha_tickets + ?3
+                     WHERE wallet = ?1",
+                    params![e.wallet.to_string(), e.bug_reward, e.ticket_reward],
+                )?;
+            }
+            // Only kept in the events table; the account snapshot covers their state
+            _ => {}
+        }
+
+        Ok(true)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn insert_match(
+        &self,
+        source: &EventSource,
+        player_a: &str,
+        player_b: &str,
+        is_draw: bool,
+        trophy_gain: u32,
+        trophy_loss: u32,
+        rating_change: i64,
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO matches (signature, event_index, slot, block_time, player_a,
+                player_b, is_draw, trophy_gain, trophy_loss, rating_change)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                source.signature,
+                source.event_index,
+                source.slot,
+                source.block_time,
+                player_a,
+                player_b,
+                is_draw,
+                trophy_gain,
+                trophy_loss,
+                rating_change,
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::prelude::Pubkey;
+    use anchor_lang::Event;
+    use zoo_contract::{MatchRecorded, PlayerRegistered};
+
+    fn source(signature: &str, event_index: u32) -> EventSource<'_> {
+        EventSource { signature, event_index, slot: 1, block_time: None }
+    }
+
+    fn player_row(store: &Store, wallet: &Pubkey) -> (u32, u32, u32, u32) {
+        store
+            .connection()
+            .query_row(
+                "SELECT trophies, total_wins, total_losses, rating FROM players WHERE wallet = ?1",
+                params![wallet.to_string()],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn test_match_events_update_players_once() {
+        let store = Store::open_in_memory().unwrap();
+        let winner = Pubkey::new_unique();
+        let loser = Pubkey::new_unique();
+
+        for (i, wallet) in [winner, loser].iter().enumerate() {
+            let event = PlayerRegistered { wallet: *wallet, username: format!("p{}", i) };
+            store
+                .apply_event(&source("reg", i as u32), &ZooEvent::PlayerRegistered(event), &[])
+                .unwrap();
+        }
+
+        let event = MatchRecorded {
+            winner,
+            loser,
+            trophy_gain: 30,
+            trophy_loss: 30,
+            winner_trophies: 30,
+            loser_trophies: 0,
+            rating_change: 16,
+            winner_reward: 100,
+            loser_reward: 0,
+        };
+        let data = event.data();
+        let event = ZooEvent::MatchRecorded(event);
+
+        assert!(store.apply_event(&source("match", 0), &event, &data).unwrap());
+        // Replaying the same transaction must not double count
+        assert!(!store.apply_event(&source("match", 0), &event, &data).unwrap());
+
+        assert_eq!(player_row(&store, &winner), (30, 1, 0, 1016));
+        assert_eq!(player_row(&store, &loser), (0, 0, 1, 984));
+
+        let matches: u32 = store
+            .connection()
+            .query_row("SELECT COUNT(*) FROM matches", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(matches, 1);
+    }
+}
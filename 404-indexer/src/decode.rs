@@ -0,0 +1,242 @@
+//! Decoding of zoo_contract accounts and events.
+//!
+//! Uses the contract's own types, so the indexer can never drift from the
+//! on-chain layout: accounts are matched on their Anchor discriminator and
+//! events are read from the `Program data:` lines that `emit!` writes.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountDeserialize, AnchorDeserialize, Discriminator};
+use anyhow::{bail, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ::zoo_contract::{
+    BannerCreated, BannerEnded, BannerPulled, BugPurchased, CardBurned, CardCrafted, CardInstance, CardMinted,
+    CardSetUpdated, CardStatsRerolled, CardTemplateCreated, CardTemplateRetired, CardTemplateUpdated, CardXpGained,
+    CollectionPageCreated, CollectionSynced, ConfigUpdated, DeckDeleted, DeckSaved, GameConfig, LeaderboardReranked,
+    Listing, ListingCancelled, ListingCreated, ListingFilled, MatchDrawn, MatchRecorded, PackOpened, PlayerProfile,
+    PlayerRegistered, PullCommitted, PullRevealed, SeasonRewardsClaimed, SeasonStarted, TicketsClaimed, TicketsGranted, TicketsPurchased,
+    TierChanged, TournamentCancelled, TournamentCreated, TournamentJoined, TournamentMatchReported, TournamentPrizePaid, TournamentStarted,
+};
+
+/// Accounts the indexer materialises
+pub enum ZooAccount {
+    GameConfig(GameConfig),
+    PlayerProfile(PlayerProfile),
+    CardInstance(CardInstance),
+    Listing(Listing),
+}
+
+/// Decode an account owned by the program.
+/// Returns Ok(None) for account types the indexer doesn't track.
+pub fn decode_account(data: &[u8]) -> Result<Option<ZooAccount>> {
+    if data.len() < 8 {
+        bail!("account data shorter than discriminator");
+    }
+    let disc = &data[..8];
+    let mut buf = data;
+
+    let account = if disc == GameConfig::DISCRIMINATOR {
+        ZooAccount::GameConfig(GameConfig::try_deserialize(&mut buf)?)
+    } else if disc == PlayerProfile::DISCRIMINATOR {
+        ZooAccount::PlayerProfile(PlayerProfile::try_deserialize(&mut buf)?)
+    } else if disc == CardInstance::DISCRIMINATOR {
+        ZooAccount::CardInstance(CardInstance::try_deserialize(&mut buf)?)
+    } else if disc == Listing::DISCRIMINATOR {
+        ZooAccount::Listing(Listing::try_deserialize(&mut buf)?)
+    } else {
+        return Ok(None);
+    };
+
+    Ok(Some(account))
+}
+
+macro_rules! zoo_events {
+    ($($name:ident),* $(,)?) => {
+        /// Every event emitted by the program
+        /// (events db.rs only records by name still carry their decoded body)
+        #[allow(dead_code)]
+        pub enum ZooEvent {
+            $($name($name),)*
+        }
+
+        impl ZooEvent {
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(ZooEvent::$name(_) => stringify!($name),)*
+                }
+            }
+        }
+
+        /// Decode one event payload (discriminator + borsh body).
+        /// Returns Ok(None) for discriminators this build doesn't know.
+        pub fn decode_event(data: &[u8]) -> Result<Option<ZooEvent>> {
+            if data.len() < 8 {
+                bail!("event data shorter than discriminator");
+            }
+            let (disc, mut body) = data.split_at(8);
+            $(
+                if disc == $name::DISCRIMINATOR {
+                    return Ok(Some(ZooEvent::$name($name::deserialize(&mut body)?)));
+                }
+            )*
+            Ok(None)
+        }
+    };
+}
+
+zoo_events!(
+    ConfigUpdated,
+    CardTemplateCreated,
+    CardTemplateUpdated,
+    CardTemplateRetired,
+    PlayerRegistered,
+    TicketsClaimed,
+    TicketsGranted,
+    TicketsPurchased,
+    BugPurchased,
+    CardMinted,
+    CardBurned,
+    CardCrafted,
+    CardStatsRerolled,
+    CardXpGained,
+    PackOpened,
+    DeckSaved,
+    DeckDeleted,
+    CollectionPageCreated,
+    CollectionSynced,
+    ListingCreated,
+    ListingCancelled,
+    ListingFilled,
+    MatchRecorded,
+    MatchDrawn,
+    LeaderboardReranked,
+    TournamentCreated,
+    TournamentJoined,
+    TournamentStarted,
+    TournamentMatchReported,
+    TournamentPrizePaid,
+    TournamentCancelled,
+    SeasonStarted,
+    SeasonRewardsClaimed,
+    TierChanged,
+    BannerCreated,
+    BannerEnded,
+    BannerPulled,
+    CardSetUpdated,
+    PullCommitted,
+    PullRevealed,
+);
+
+/// Extract the raw event payloads emitted by `program_id` from a transaction's logs.
+/// Tracks the invoke stack so `Program data:` lines from other programs
+/// (e.g. CPIs into token programs) are skipped.
+pub fn program_data_from_logs(logs: &[String], program_id: &Pubkey) -> Vec<Vec<u8>> {
+    let program_id = program_id.to_string();
+    let mut stack: Vec<&str> = Vec::new();
+    let mut payloads = Vec::new();
+
+    for line in logs {
+        let Some(rest) = line.strip_prefix("Program ") else {
+            continue;
+        };
+
+        if let Some(data) = rest.strip_prefix("data: ") {
+            if stack.last() == Some(&program_id.as_str()) {
+                if let Ok(bytes) = STANDARD.decode(data) {
+                    payloads.push(bytes);
+                }
+            }
+        } else if let Some((id, tail)) = rest.split_once(' ') {
+            if tail.starts_with("invoke [") {
+                stack.push(id);
+            } else if tail == "success" || tail.starts_with("failed") {
+                stack.pop();
+            }
+        }
+    }
+
+    payloads
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::{AccountSerialize, Event};
+
+    fn logs_with(data: &[u8], other_data: &[u8]) -> Vec<String> {
+        let zoo = ::zoo_contract::ID.to_string();
+        let token = Pubkey::new_unique().to_string();
+        vec![
+            format!("Program {} invoke [1]", zoo),
+            "Program log: Instruction: RecordMatchResult".to_string(),
+            format!("Program {} invoke [2]", token),
+            format!("Program data: {}", STANDARD.encode(other_data)),
+            format!("Program {} success", token),
+            format!("Program data: {}", STANDARD.encode(data)),
+            format!("Program {} consumed 4200 of 200000 compute units", zoo),
+            format!("Program {} success", zoo),
+        ]
+    }
+
+    #[test]
+    fn test_decodes_own_events_and_skips_cpi_data() {
+        let winner = Pubkey::new_unique();
+        let event = MatchRecorded {
+            winner,
+            loser: Pubkey::new_unique(),
+            trophy_gain: 31,
+            trophy_loss: 30,
+            winner_trophies: 131,
+            loser_trophies: 70,
+            rating_change: 16,
+            winner_reward: 100,
+            loser_reward: 0,
+        };
+
+        let logs = logs_with(&event.data(), b"not ours");
+        let payloads = program_data_from_logs(&logs, &::zoo_contract::ID);
+        assert_eq!(payloads.len(), 1);
+
+        match decode_event(&payloads[0]).unwrap() {
+            Some(ZooEvent::MatchRecorded(decoded)) => {
+                assert_eq!(decoded.winner, winner);
+                assert_eq!(decoded.trophy_gain, 31);
+                assert_eq!(decoded.winner_trophies, 131);
+            }
+            _ => panic!("expected MatchRecorded"),
+        }
+    }
+
+    #[test]
+    fn test_unknown_discriminators_are_ignored() {
+        assert!(decode_event(&[0u8; 16]).unwrap().is_none());
+        assert!(decode_account(&[0u8; 16]).unwrap().is_none());
+        assert!(decode_event(&[1, 2, 3]).is_err());
+    }
+
+    #[test]
+    fn test_decodes_accounts_with_trailing_space() {
+        let card = CardInstance {
+            mint: Pubkey::new_unique(),
+            card_type_id: 404,
+            attack: 12,
+            health: 30,
+            owner: Pubkey::new_unique(),
+            bump: 255,
+            xp: 0,
+            level: 0,
+        };
+        let mut data = Vec::new();
+        card.try_serialize(&mut data).unwrap();
+        data.resize(CardInstance::LEN + 16, 0);
+
+        match decode_account(&data).unwrap() {
+            Some(ZooAccount::CardInstance(decoded)) => {
+                assert_eq!(decoded.mint, card.mint);
+                assert_eq!(decoded.card_type_id, 404);
+                assert_eq!(decoded.attack, 12);
+            }
+            _ => panic!("expected CardInstance"),
+        }
+    }
+}
@@ -0,0 +1,127 @@
+//! zoo-indexer: replays zoo_contract transactions and accounts into SQLite,
+//! so leaderboard, collection and market screens can query a local database
+//! instead of calling getProgramAccounts.
+
+mod db;
+mod decode;
+mod source;
+
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+use db::{EventSource, Store};
+use source::{RawAccount, RawTransaction, Rpc};
+
+#[derive(Parser)]
+#[command(name = "zoo-indexer", about = "Index 404 ZOO on-chain state into SQLite")]
+struct Cli {
+    /// SQLite database file (created if missing)
+    #[arg(long, default_value = "zoo.sqlite")]
+    db: PathBuf,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Index a JSON dump: { "accounts": getProgramAccounts result, "transactions": [getTransaction results] }
+    Dump { path: PathBuf },
+    /// Index directly from an RPC endpoint
+    Rpc {
+        #[arg(long, default_value = "http://127.0.0.1:8899")]
+        url: String,
+        /// Max number of recent transactions to replay
+        #[arg(long, default_value_t = 1000)]
+        limit: usize,
+    },
+}
+
+#[derive(Default)]
+struct Summary {
+    transactions: usize,
+    events: usize,
+    new_events: usize,
+    accounts: usize,
+    skipped: usize,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let store = Store::open(&cli.db)?;
+
+    let (accounts, transactions) = match cli.command {
+        Command::Dump { path } => source::load_dump(&path)?,
+        Command::Rpc { url, limit } => {
+            let rpc = Rpc::new(&url);
+            // Transactions first: the snapshot taken afterwards is at least as new
+            let transactions = rpc.transactions(&zoo_contract::ID, limit)?;
+            let accounts = rpc.program_accounts(&zoo_contract::ID)?;
+            (accounts, transactions)
+        }
+    };
+
+    store.begin()?;
+    let summary = index(&store, accounts, transactions)?;
+    store.commit()?;
+
+    println!(
+        "Indexed {} transactions ({} events, {} new) and {} accounts into {}",
+        summary.transactions,
+        summary.events,
+        summary.new_events,
+        summary.accounts,
+        cli.db.display()
+    );
+    if summary.skipped > 0 {
+        println!("Skipped {} undecodable accounts/events", summary.skipped);
+    }
+
+    Ok(())
+}
+
+/// Replay events in chain order, then overwrite with the account snapshot
+fn index(store: &Store, accounts: Vec<RawAccount>, mut transactions: Vec<RawTransaction>) -> Result<Summary> {
+    let mut summary = Summary::default();
+    transactions.sort_by_key(|tx| tx.slot);
+
+    for tx in transactions.iter().filter(|tx| !tx.failed) {
+        summary.transactions += 1;
+        let payloads = decode::program_data_from_logs(&tx.logs, &zoo_contract::ID);
+
+        for (event_index, data) in payloads.iter().enumerate() {
+            match decode::decode_event(data) {
+                Ok(Some(event)) => {
+                    summary.events += 1;
+                    let source = EventSource {
+                        signature: &tx.signature,
+                        event_index: event_index as u32,
+                        slot: tx.slot,
+                        block_time: tx.block_time,
+                    };
+                    if store.apply_event(&source, &event, data)? {
+                        summary.new_events += 1;
+                    }
+                }
+                Ok(None) | Err(_) => summary.skipped += 1,
+            }
+        }
+    }
+
+    if !accounts.is_empty() {
+        store.deactivate_listings()?;
+    }
+    for account in &accounts {
+        match decode::decode_account(&account.data) {
+            Ok(Some(decoded)) => {
+                store.upsert_account(&account.address, &decoded)?;
+                summary.accounts += 1;
+            }
+            Ok(None) => {}
+            Err(_) => summary.skipped += 1,
+        }
+    }
+
+    Ok(summary)
+}
@@ -0,0 +1,188 @@
+//! Input sources: a JSON dump on disk, or a JSON-RPC endpoint (e.g. a local validator).
+//!
+//! Both use the JSON-RPC result shapes, so a dump can be produced by saving
+//! `getProgramAccounts` (base64 encoding) and `getTransaction` responses.
+
+use anchor_lang::prelude::Pubkey;
+use anyhow::{anyhow, bail, Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde_json::{json, Value};
+use std::path::Path;
+
+/// Max signatures per getSignaturesForAddress page
+const SIGNATURE_PAGE_SIZE: usize = 1000;
+
+pub struct RawAccount {
+    pub address: String,
+    pub data: Vec<u8>,
+}
+
+pub struct RawTransaction {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub failed: bool,
+    pub logs: Vec<String>,
+}
+
+/// Parse a `getProgramAccounts` result (array of `{ pubkey, account }`)
+pub fn parse_accounts(value: &Value) -> Result<Vec<RawAccount>> {
+    let entries = value.as_array().ok_or_else(|| anyhow!("accounts must be an array"))?;
+    entries
+        .iter()
+        .map(|entry| {
+            let address = entry["pubkey"]
+                .as_str()
+                .ok_or_else(|| anyhow!("account entry without pubkey"))?;
+            // data is ["<base64>", "base64"]
+            let encoded = match &entry["account"]["data"] {
+                Value::Array(parts) if parts.get(1).and_then(Value::as_str) == Some("base64") => {
+                    parts[0].as_str().unwrap_or_default()
+                }
+                _ => bail!("account {} is not base64 encoded", address),
+            };
+            Ok(RawAccount {
+                address: address.to_string(),
+                data: STANDARD.decode(encoded).with_context(|| format!("account {}", address))?,
+            })
+        })
+        .collect()
+}
+
+/// Parse a `getTransaction` result (json encoding)
+pub fn parse_transaction(value: &Value) -> Result<RawTransaction> {
+    let signature = value["transaction"]["signatures"][0]
+        .as_str()
+        .ok_or_else(|| anyhow!("transaction without signature"))?;
+    let logs = value["meta"]["logMessages"]
+        .as_array()
+        .map(|lines| lines.iter().filter_map(Value::as_str).map(String::from).collect())
+        .unwrap_or_default();
+
+    Ok(RawTransaction {
+        signature: signature.to_string(),
+        slot: value["slot"].as_u64().unwrap_or_default(),
+        block_time: value["blockTime"].as_i64(),
+        failed: !value["meta"]["err"].is_null(),
+        logs,
+    })
+}
+
+/// Load a dump file: `{ "accounts": [...], "transactions": [...] }`, both optional
+pub fn load_dump(path: &Path) -> Result<(Vec<RawAccount>, Vec<RawTransaction>)> {
+    let file = std::fs::File::open(path).with_context(|| format!("opening {}", path.display()))?;
+    let dump: Value = serde_json::from_reader(std::io::BufReader::new(file))?;
+
+    let accounts = match dump.get("accounts") {
+        Some(accounts) => parse_accounts(accounts)?,
+        None => Vec::new(),
+    };
+    let transactions = match dump.get("transactions") {
+        Some(Value::Array(txs)) => txs.iter().map(parse_transaction).collect::<Result<_>>()?,
+        Some(_) => bail!("transactions must be an array"),
+        None => Vec::new(),
+    };
+
+    Ok((accounts, transactions))
+}
+
+pub struct Rpc {
+    url: String,
+}
+
+impl Rpc {
+    pub fn new(url: &str) -> Self {
+        Rpc { url: url.to_string() }
+    }
+
+    fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let response: Value = ureq::post(&self.url)
+            .send_json(json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }))
+            .with_context(|| format!("{} request to {}", method, self.url))?
+            .into_json()?;
+
+        if let Some(error) = response.get("error") {
+            bail!("{} failed: {}", method, error);
+        }
+        Ok(response["result"].clone())
+    }
+
+    pub fn program_accounts(&self, program_id: &Pubkey) -> Result<Vec<RawAccount>> {
+        let result = self.call(
+            "getProgramAccounts",
+            json!([program_id.to_string(), { "encoding": "base64", "commitment": "confirmed" }]),
+        )?;
+        parse_accounts(&result)
+    }
+
+    /// Fetch up to `limit` of the program's most recent transactions, oldest first
+    pub fn transactions(&self, program_id: &Pubkey, limit: usize) -> Result<Vec<RawTransaction>> {
+        let mut signatures: Vec<String> = Vec::new();
+        let mut before: Option<String> = None;
+
+        while signatures.len() < limit {
+            let page_size = (limit - signatures.len()).min(SIGNATURE_PAGE_SIZE);
+            let mut options = json!({ "limit": page_size, "commitment": "confirmed" });
+            if let Some(before) = &before {
+                options["before"] = json!(before);
+            }
+
+            let page = self.call("getSignaturesForAddress", json!([program_id.to_string(), options]))?;
+            let page = page.as_array().cloned().unwrap_or_default();
+            let page_len = page.len();
+
+            signatures.extend(page.iter().filter_map(|s| s["signature"].as_str()).map(String::from));
+            before = signatures.last().cloned();
+
+            if page_len < page_size {
+                break;
+            }
+        }
+
+        // Newest first from the RPC; replay in chain order
+        signatures.reverse();
+        signatures
+            .iter()
+            .map(|signature| {
+                let result = self.call(
+                    "getTransaction",
+                    json!([signature, {
+                        "encoding": "json",
+                        "commitment": "confirmed",
+                        "maxSupportedTransactionVersion": 0
+                    }]),
+                )?;
+                parse_transaction(&result)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rpc_shapes() {
+        let accounts = json!([{
+            "pubkey": "11111111111111111111111111111111",
+            "account": { "data": [STANDARD.encode([1u8, 2, 3]), "base64"], "lamports": 1 }
+        }]);
+        let accounts = parse_accounts(&accounts).unwrap();
+        assert_eq!(accounts[0].data, vec![1, 2, 3]);
+
+        let tx = json!({
+            "slot": 42,
+            "blockTime": 1700000000,
+            "transaction": { "signatures": ["sig1"] },
+            "meta": { "err": null, "logMessages": ["Program log: hi"] }
+        });
+        let tx = parse_transaction(&tx).unwrap();
+        assert_eq!(tx.signature, "sig1");
+        assert_eq!(tx.slot, 42);
+        assert_eq!(tx.block_time, Some(1700000000));
+        assert!(!tx.failed);
+        assert_eq!(tx.logs.len(), 1);
+    }
+}
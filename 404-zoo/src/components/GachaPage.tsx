@@ -145,7 +145,7 @@ function GachaPage({ playerProfile, onProfileUpdate }: GachaPageProps) {
     setTenDrawState('loading')
     
     try {
-      // 使用 gachaDrawMultiple，一次签名 5 个交易
+      // 使用 gachaDrawMultiple，一次付费 5 抽，领取交易一次签名
       const results = await gachaDrawMultiple(playerProfile.wallet, 5)
       
       // 获取所有卡片模板（串行获取，避免 rate limit）
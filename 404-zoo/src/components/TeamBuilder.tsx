@@ -121,8 +121,7 @@ function TeamBuilder({ playerProfile }: TeamBuilderProps) {
         return
       }
 
-      const cardMints = teamCards.map(c => c.instance.mint)
-      await saveDeck(playerProfile.wallet, newIndex, deckName, cardMints)
+      await saveDeck(playerProfile.wallet, newIndex, deckName, teamCards.map(c => c.instance))
 
       alert('Deck saved successfully!')
       await loadSavedDecks()
@@ -30,6 +30,9 @@ export const TraitType = {
   Warrior: 0,
   Archer: 1,
   Assassin: 2,
+  Tank: 3,
+  Mage: 4,
+  Support: 5,
 } as const
 
 export type TraitType = (typeof TraitType)[keyof typeof TraitType]
@@ -46,6 +49,9 @@ export const TraitTypeNames: Record<TraitType, string> = {
   [TraitType.Warrior]: 'Warrior',
   [TraitType.Archer]: 'Archer',
   [TraitType.Assassin]: 'Assassin',
+  [TraitType.Tank]: 'Tank',
+  [TraitType.Mage]: 'Mage',
+  [TraitType.Support]: 'Support',
 }
 
 export const RarityNames: Record<Rarity, string> = {
@@ -390,10 +396,11 @@ export async function checkPlayerRegistered(
 // 获取所有玩家的 Profile（用于排行榜）
 export async function getAllPlayerProfiles(): Promise<PlayerProfile[]> {
   try {
-    // PlayerProfile::LEN = 8 + 32 + 4 + 32 + 1 + 8 + 8 + 4 + 4 + 4 + 4 + 1 = 110
+    // PlayerProfile::LEN = 110 in the original layout + 91 for ratings, seasons,
+    // daily rewards, tiers, crafting dust and collections = 201
     const accounts = await connection.getProgramAccounts(PROGRAM_ID, {
       filters: [
-        { dataSize: 110 }, // PlayerProfile account size
+        { dataSize: 201 }, // PlayerProfile account size
       ],
     })
 
@@ -449,6 +456,26 @@ function getGachaDrawDiscriminator(): Buffer {
   return Buffer.from([250, 36, 118, 2, 221, 236, 213, 11])
 }
 
+// Anchor instruction discriminator for "reveal_gacha_pull"
+// sha256("global:reveal_gacha_pull")[0..8]
+function getRevealGachaPullDiscriminator(): Buffer {
+  return Buffer.from([142, 21, 153, 250, 36, 122, 187, 58])
+}
+
+// Anchor instruction discriminator for "claim_pulled_cards"
+// sha256("global:claim_pulled_cards")[0..8]
+function getClaimPulledCardsDiscriminator(): Buffer {
+  return Buffer.from([211, 175, 34, 73, 108, 23, 149, 17])
+}
+
+// 获取 PendingPull PDA (已付费、待领取的抽卡)
+export function getPendingPullPDA(playerPubkey: PublicKey): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from('pending_pull'), playerPubkey.toBuffer()],
+    PROGRAM_ID
+  )
+}
+
 // 获取 GameConfig PDA
 export function getGameConfigPDA(): [PublicKey, number] {
   return PublicKey.findProgramAddressSync(
@@ -476,7 +503,7 @@ export interface CardInstance {
 
 // 解析 CardInstance 账户数据
 function parseCardInstance(data: Buffer): CardInstance {
-  // 8 bytes discriminator + 32 mint + 4 card_type_id + 2 attack + 2 health + 32 owner + 1 bump
+  // 8 bytes discriminator + 32 mint + 4 card_type_id + 2 attack + 2 health + 32 owner + 1 bump + 4 xp + 1 level
   let offset = 8 // skip discriminator
 
   const mint = new PublicKey(data.slice(offset, offset + 32))
@@ -502,7 +529,7 @@ export async function getPlayerCards(playerPubkey: PublicKey): Promise<CardInsta
     // 使用 getProgramAccounts 查询所有 CardInstance，过滤 owner
     const accounts = await connection.getProgramAccounts(PROGRAM_ID, {
       filters: [
-        { dataSize: 81 }, // CardInstance::LEN = 8 + 32 + 4 + 2 + 2 + 32 + 1 = 81
+        { dataSize: 86 }, // CardInstance::LEN = 8 + 32 + 4 + 2 + 2 + 32 + 1 + 4 (xp) + 1 (level) = 86
         {
           memcmp: {
             offset: 8 + 32 + 4 + 2 + 2, // skip discriminator + mint + card_type_id + attack + health
@@ -565,6 +592,7 @@ export async function registerPlayer(
 ): Promise<string> {
   const phantom = getPhantomProvider()
   const [playerProfilePDA] = getPlayerProfilePDA(playerPubkey)
+  const [gameConfigPDA] = getGameConfigPDA()
 
   // 构建指令数据
   const usernameBytes = Buffer.from(username, 'utf-8')
@@ -578,6 +606,7 @@ export async function registerPlayer(
   const instruction = new TransactionInstruction({
     keys: [
       { pubkey: playerProfilePDA, isSigner: false, isWritable: true },
+      { pubkey: gameConfigPDA, isSigner: false, isWritable: false },
       { pubkey: playerPubkey, isSigner: true, isWritable: true },
       { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
     ],
@@ -642,6 +671,13 @@ const TOKEN_PROGRAM_ID = new PublicKey('TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ
 const ASSOCIATED_TOKEN_PROGRAM_ID = new PublicKey('ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL')
 // Rent Sysvar
 const RENT_SYSVAR_ID = new PublicKey('SysvarRent111111111111111111111111111111111')
+// SlotHashes Sysvar
+const SLOT_HASHES_SYSVAR_ID = new PublicKey('SysvarS1otHashes111111111111111111111111111')
+// 每笔领取交易最多铸造的卡数 (每张卡 4 个账户 + mint 签名，受交易大小限制)
+const CARDS_PER_CLAIM = 2
+
+// Optional program accounts that aren't passed: Anchor reads the program id as None
+const OMITTED_ACCOUNT = { pubkey: PROGRAM_ID, isSigner: false, isWritable: false }
 
 // 获取 Associated Token Address
 function getAssociatedTokenAddress(mint: PublicKey, owner: PublicKey): PublicKey {
@@ -652,105 +688,105 @@ function getAssociatedTokenAddress(mint: PublicKey, owner: PublicKey): PublicKey
   return address
 }
 
-// 缓存已加载的卡片模板（按稀有度分组）
-let cachedCardsByRarity: Map<number, CardTemplate[]> | null = null
-
-// 加载所有卡片模板并按稀有度分组
-async function loadCardsByRarity(): Promise<Map<number, CardTemplate[]>> {
-  if (cachedCardsByRarity) {
-    return cachedCardsByRarity
-  }
+// 三个稀有度的卡池 (Common, Rare, Legendary)，reveal_gacha_pull 在链上 roll
+function getRarityPoolKeys() {
+  return [0, 1, 2].map(rarity => ({
+    pubkey: getRarityPoolPDA(rarity)[0],
+    isSigner: false,
+    isWritable: false,
+  }))
+}
 
-  const allCards = await getAllCardTemplates(50) // 扫描前50个ID
-  const byRarity = new Map<number, CardTemplate[]>()
+// reveal_gacha_pull 指令：用付费 slot 的 SlotHashes 条目 roll 出卡 (任何人都可以调用)
+function buildRevealGachaPullIx(playerPubkey: PublicKey): TransactionInstruction {
+  return new TransactionInstruction({
+    keys: [
+      { pubkey: getPendingPullPDA(playerPubkey)[0], isSigner: false, isWritable: true },
+      ...getRarityPoolKeys(),
+      { pubkey: SLOT_HASHES_SYSVAR_ID, isSigner: false, isWritable: false },
+    ],
+    programId: PROGRAM_ID,
+    data: getRevealGachaPullDiscriminator(),
+  })
+}
 
-  for (const card of allCards) {
-    const list = byRarity.get(card.rarity) || []
-    list.push(card)
-    byRarity.set(card.rarity, list)
+// 模拟 reveal_gacha_pull，得到待领取的卡 (付费的下一个 slot 起才能 reveal，未到则重试)
+async function readPendingGachaCards(playerPubkey: PublicKey): Promise<number[]> {
+  for (let attempt = 0; ; attempt++) {
+    const transaction = new Transaction().add(buildRevealGachaPullIx(playerPubkey))
+    transaction.feePayer = playerPubkey
+    transaction.recentBlockhash = (await connection.getLatestBlockhash()).blockhash
+
+    const { value } = await connection.simulateTransaction(transaction)
+    if (!value.err && value.returnData) {
+      // 返回值是 Vec<u32>: 4 字节长度 + 每张卡 4 字节
+      const data = Buffer.from(value.returnData.data[0], 'base64')
+      const count = data.readUInt32LE(0)
+      return Array.from({ length: count }, (_, i) => data.readUInt32LE(4 + i * 4))
+    }
+    if (attempt >= 10) {
+      throw new Error(`reveal_gacha_pull failed: ${JSON.stringify(value.err)}`)
+    }
+    await new Promise(resolve => setTimeout(resolve, 400))
   }
+}
 
-  cachedCardsByRarity = byRarity
-  console.log('Cards loaded by rarity:', {
-    common: byRarity.get(Rarity.Common)?.length || 0,
-    rare: byRarity.get(Rarity.Rare)?.length || 0,
-    legendary: byRarity.get(Rarity.Legendary)?.length || 0,
-  })
+// PullSource 各 variant 的字段字节数: Gacha, Pack { u8, u64 }, Banner { u32, u32 }, Craft { u32 },
+// Reroll { Pubkey, EntryCurrency, u64, bool }
+const PULL_SOURCE_FIELD_SIZES = [0, 9, 8, 4, 42]
 
-  return byRarity
+// PendingPull 类型 (只解析领取需要的字段)
+interface PendingPull {
+  isGacha: boolean
+  cardTypeIds: number[] // reveal 之前为空
+  claimed: number
 }
 
-// 清除缓存（如果需要刷新）
-export function clearCardCache() {
-  cachedCardsByRarity = null
-}
+// 解析 PendingPull 账户数据
+function parsePendingPull(data: Buffer): PendingPull {
+  // 8 (discriminator) + 32 (player) + source (1 字节 variant + 字段) + 8 (commit_slot) + 1 (card_count)
+  // + 4 + 4 * n (card_type_ids) + 32 (seed) + 1 (claimed) + 1 (bump)
+  let offset = 8 + 32 // skip discriminator, player
 
-// 客户端 roll rarity (模拟合约逻辑: 70% common, 27% rare, 3% legendary)
-function rollRarityClient(): number {
-  const roll = Math.floor(Math.random() * 100)
-  if (roll < 70) return Rarity.Common
-  if (roll < 97) return Rarity.Rare
-  return Rarity.Legendary
-}
+  const sourceVariant = data[offset]
+  offset += 1 + PULL_SOURCE_FIELD_SIZES[sourceVariant]
 
-// 使用抽奖券抽卡（单抽）
-export async function gachaDraw(
-  playerPubkey: PublicKey
-): Promise<GachaDrawResult> {
-  const phantom = getPhantomProvider()
-  const [playerProfilePDA] = getPlayerProfilePDA(playerPubkey)
-  const [gameConfigPDA] = getGameConfigPDA()
+  offset += 8 + 1 // skip commit_slot, card_count
 
-  // 1. 加载卡片模板
-  const cardsByRarity = await loadCardsByRarity()
-
-  // 2. Roll rarity 并选择卡片
-  let rarity = rollRarityClient()
-  let cards = cardsByRarity.get(rarity)
-
-  // 如果该稀有度没有卡，降级到有卡的稀有度
-  if (!cards || cards.length === 0) {
-    // 尝试其他稀有度
-    for (const r of [Rarity.Common, Rarity.Rare, Rarity.Legendary]) {
-      cards = cardsByRarity.get(r)
-      if (cards && cards.length > 0) {
-        rarity = r
-        break
-      }
-    }
-  }
+  const cardCount = data.readUInt32LE(offset)
+  offset += 4
+  const cardTypeIds = Array.from({ length: cardCount }, (_, i) => data.readUInt32LE(offset + i * 4))
+  offset += cardCount * 4
 
-  if (!cards || cards.length === 0) {
-    throw new Error('No card templates found! Please create some cards first.')
-  }
+  offset += 32 // skip seed
 
-  const selectedCard = cards[Math.floor(Math.random() * cards.length)]
-  const cardTypeId = selectedCard.cardTypeId
-  const [cardTemplatePDA] = getCardTemplatePDA(cardTypeId)
+  const claimed = data[offset]
+
+  return { isGacha: sourceVariant === 0, cardTypeIds, claimed }
+}
+
+// 获取玩家还没领完的抽卡 (没有则返回 null)
+async function fetchPendingPull(playerPubkey: PublicKey): Promise<PendingPull | null> {
+  const accountInfo = await connection.getAccountInfo(getPendingPullPDA(playerPubkey)[0])
+  return accountInfo ? parsePendingPull(Buffer.from(accountInfo.data)) : null
+}
+
+// 创建 mint account 并初始化 (mint authority = game_config PDA)
+function buildCreateMintIxs(
+  playerPubkey: PublicKey,
+  mintPubkey: PublicKey,
+  mintRent: number
+): TransactionInstruction[] {
+  const [gameConfigPDA] = getGameConfigPDA()
 
-  console.log(`Rolling: ${RarityNames[rarity as Rarity]} - ${selectedCard.name} (ID: ${cardTypeId})`)
-  
-  // 2. 创建新的 mint keypair
-  const { Keypair } = await import('@solana/web3.js')
-  const mintKeypair = Keypair.generate()
-  const mintPubkey = mintKeypair.publicKey
-  
-  // 3. 计算 PDAs
-  const playerTokenAccount = getAssociatedTokenAddress(mintPubkey, playerPubkey)
-  const [cardInstancePDA] = getCardInstancePDA(mintPubkey)
-  
-  // 4. 创建 mint account 的指令 (需要先初始化 mint)
-  const mintRent = await connection.getMinimumBalanceForRentExemption(82) // Mint account size
-  
   const createMintAccountIx = SystemProgram.createAccount({
     fromPubkey: playerPubkey,
     newAccountPubkey: mintPubkey,
-    space: 82,
+    space: 82, // Mint account size
     lamports: mintRent,
     programId: TOKEN_PROGRAM_ID,
   })
-  
-  // 5. 初始化 mint 指令 (mint authority = game_config PDA)
+
   // Use InitializeMint2 (instruction index 20) - doesn't require rent sysvar in accounts
   // InitializeMint2 instruction layout:
   // - 1 byte: instruction index (20)
@@ -768,180 +804,154 @@ export async function gachaDraw(
     programId: TOKEN_PROGRAM_ID,
     data: initMintData,
   })
-  
-  // 6. gacha_draw 指令
-  const gachaDrawIx = new TransactionInstruction({
+
+  return [createMintAccountIx, initMintIx]
+}
+
+// claim_pulled_cards 指令：按顺序铸造待领取的卡
+// 每张卡依次传 (template, mint, token account, card instance)
+function buildClaimPulledCardsIx(
+  playerPubkey: PublicKey,
+  cards: Array<{ cardTypeId: number; mintPubkey: PublicKey }>
+): TransactionInstruction {
+  const cardKeys = cards.flatMap(({ cardTypeId, mintPubkey }) => [
+    { pubkey: getCardTemplatePDA(cardTypeId)[0], isSigner: false, isWritable: false },
+    { pubkey: mintPubkey, isSigner: false, isWritable: true },
+    { pubkey: getAssociatedTokenAddress(mintPubkey, playerPubkey), isSigner: false, isWritable: true },
+    { pubkey: getCardInstancePDA(mintPubkey)[0], isSigner: false, isWritable: true },
+  ])
+
+  return new TransactionInstruction({
     keys: [
-      { pubkey: playerProfilePDA, isSigner: false, isWritable: true },
-      { pubkey: gameConfigPDA, isSigner: false, isWritable: false },
-      { pubkey: cardTemplatePDA, isSigner: false, isWritable: false },
-      { pubkey: mintPubkey, isSigner: false, isWritable: true },
-      { pubkey: playerTokenAccount, isSigner: false, isWritable: true },
-      { pubkey: cardInstancePDA, isSigner: false, isWritable: true },
+      { pubkey: getPendingPullPDA(playerPubkey)[0], isSigner: false, isWritable: true },
+      { pubkey: getGameConfigPDA()[0], isSigner: false, isWritable: false },
       { pubkey: playerPubkey, isSigner: true, isWritable: true },
       { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
       { pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
       { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
-      { pubkey: RENT_SYSVAR_ID, isSigner: false, isWritable: false },
+      OMITTED_ACCOUNT, // collection
+      ...cardKeys,
     ],
     programId: PROGRAM_ID,
-    data: getGachaDrawDiscriminator(),
+    data: getClaimPulledCardsDiscriminator(),
   })
+}
 
-  const transaction = new Transaction()
-    .add(createMintAccountIx)
-    .add(initMintIx)
-    .add(gachaDrawIx)
-  
-  transaction.feePayer = playerPubkey
-  transaction.recentBlockhash = (await connection.getLatestBlockhash()).blockhash
-  
-  // mint keypair 需要签名
-  transaction.partialSign(mintKeypair)
+// gacha_draw：扣抽奖券，记录待 reveal 的抽卡
+async function payGachaDraw(playerPubkey: PublicKey, count: number): Promise<void> {
+  const phantom = getPhantomProvider()
+  const [playerProfilePDA] = getPlayerProfilePDA(playerPubkey)
 
-  const signedTx = await phantom.signTransaction(transaction)
-  const txId = await connection.sendRawTransaction(signedTx.serialize())
+  const drawData = Buffer.alloc(9)
+  getGachaDrawDiscriminator().copy(drawData, 0)
+  drawData.writeUInt8(count, 8)
 
-  await connection.confirmTransaction(txId, 'confirmed')
-  console.log(`Gacha draw complete! Card: ${cardTypeId}, Mint: ${mintPubkey.toBase58()}, TX:`, txId)
+  const gachaDrawIx = new TransactionInstruction({
+    keys: [
+      { pubkey: playerProfilePDA, isSigner: false, isWritable: true },
+      { pubkey: getPendingPullPDA(playerPubkey)[0], isSigner: false, isWritable: true },
+      { pubkey: playerPubkey, isSigner: true, isWritable: true },
+      { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+    ],
+    programId: PROGRAM_ID,
+    data: drawData,
+  })
 
-  return { 
-    txId, 
-    cardTypeId,
-    mintAddress: mintPubkey.toBase58()
-  }
+  const drawTransaction = new Transaction().add(gachaDrawIx)
+  drawTransaction.feePayer = playerPubkey
+  drawTransaction.recentBlockhash = (await connection.getLatestBlockhash()).blockhash
+
+  const signedDrawTx = await phantom.signTransaction(drawTransaction)
+  const drawTxId = await connection.sendRawTransaction(signedDrawTx.serialize())
+  await connection.confirmTransaction(drawTxId, 'confirmed')
+  console.log(`Paid for ${count} draws, TX:`, drawTxId)
 }
 
-// 多抽 (一次签名，多次发送)
-export async function gachaDrawMultiple(
+// 抽卡：付费 (gacha_draw) -> 下一个 slot 起 reveal -> 领取 (claim_pulled_cards)
+// 卡由付费那个 slot 的哈希决定，付费时谁都不知道结果
+// 上次的抽卡没领完 (例如领取交易失败) 时不再付费，先把它领完
+async function drawGachaCards(
   playerPubkey: PublicKey,
   count: number
 ): Promise<GachaDrawResult[]> {
   const phantom = getPhantomProvider()
-  const [playerProfilePDA] = getPlayerProfilePDA(playerPubkey)
-  const [gameConfigPDA] = getGameConfigPDA()
   const { Keypair } = await import('@solana/web3.js')
 
-  // 1. 加载卡片模板
-  const cardsByRarity = await loadCardsByRarity()
-  
-  // 2. 获取 mint rent
+  // 1. 付费，或者接着领上次的抽卡
+  const pending = await fetchPendingPull(playerPubkey)
+  let cardTypeIds: number[]
+  let firstCard = 0
+  let needsReveal = true
+  if (pending && pending.cardTypeIds.length > 0) {
+    // 已经 reveal 过：卡记在账户里，从还没领的那张开始
+    cardTypeIds = pending.cardTypeIds
+    firstCard = pending.claimed
+    needsReveal = false
+    console.log(`Resuming pull: ${cardTypeIds.length - firstCard} cards left to claim`)
+  } else if (pending) {
+    if (!pending.isGacha) {
+      throw new Error('A pack, banner, craft or reroll pull is still waiting to be revealed')
+    }
+    console.log('Resuming unrevealed pull')
+    cardTypeIds = await readPendingGachaCards(playerPubkey)
+  } else {
+    await payGachaDraw(playerPubkey, count)
+    // 读取链上 roll 出的卡
+    cardTypeIds = await readPendingGachaCards(playerPubkey)
+  }
+  console.log(`Rolled: Card IDs ${cardTypeIds.join(', ')}`)
+
+  // 2. 领取交易：每笔最多 CARDS_PER_CLAIM 张卡，还没 reveal 的话第一笔先 reveal
   const mintRent = await connection.getMinimumBalanceForRentExemption(82)
   const { blockhash } = await connection.getLatestBlockhash()
-
-  // 3. 为每次抽卡构建交易
-  const transactionsData: Array<{
+  const claimsData: Array<{
     transaction: Transaction
-    mintKeypair: InstanceType<typeof Keypair>
-    cardTypeId: number
-    selectedCard: CardTemplate
+    cards: Array<{ cardTypeId: number; mintPubkey: PublicKey }>
   }> = []
 
-  for (let i = 0; i < count; i++) {
-    // Roll rarity 并选择卡片
-    let rarity = rollRarityClient()
-    let cards = cardsByRarity.get(rarity)
-
-    if (!cards || cards.length === 0) {
-      for (const r of [Rarity.Common, Rarity.Rare, Rarity.Legendary]) {
-        cards = cardsByRarity.get(r)
-        if (cards && cards.length > 0) {
-          rarity = r
-          break
-        }
-      }
-    }
-
-    if (!cards || cards.length === 0) {
-      throw new Error('No card templates found!')
-    }
-
-    const selectedCard = cards[Math.floor(Math.random() * cards.length)]
-    const cardTypeId = selectedCard.cardTypeId
-    const [cardTemplatePDA] = getCardTemplatePDA(cardTypeId)
-
-    // 创建 mint keypair
-    const mintKeypair = Keypair.generate()
-    const mintPubkey = mintKeypair.publicKey
-    const playerTokenAccount = getAssociatedTokenAddress(mintPubkey, playerPubkey)
-    const [cardInstancePDA] = getCardInstancePDA(mintPubkey)
-
-    // 创建 mint account 指令
-    const createMintAccountIx = SystemProgram.createAccount({
-      fromPubkey: playerPubkey,
-      newAccountPubkey: mintPubkey,
-      space: 82,
-      lamports: mintRent,
-      programId: TOKEN_PROGRAM_ID,
-    })
-
-    // 初始化 mint 指令
-    const initMintData = Buffer.alloc(35)
-    initMintData.writeUInt8(20, 0)
-    initMintData.writeUInt8(0, 1)
-    gameConfigPDA.toBuffer().copy(initMintData, 2)
-    initMintData.writeUInt8(0, 34)
-
-    const initMintIx = new TransactionInstruction({
-      keys: [{ pubkey: mintPubkey, isSigner: false, isWritable: true }],
-      programId: TOKEN_PROGRAM_ID,
-      data: initMintData,
-    })
-
-    // gacha_draw 指令
-    const gachaDrawIx = new TransactionInstruction({
-      keys: [
-        { pubkey: playerProfilePDA, isSigner: false, isWritable: true },
-        { pubkey: gameConfigPDA, isSigner: false, isWritable: false },
-        { pubkey: cardTemplatePDA, isSigner: false, isWritable: false },
-        { pubkey: mintPubkey, isSigner: false, isWritable: true },
-        { pubkey: playerTokenAccount, isSigner: false, isWritable: true },
-        { pubkey: cardInstancePDA, isSigner: false, isWritable: true },
-        { pubkey: playerPubkey, isSigner: true, isWritable: true },
-        { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
-        { pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
-        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
-        { pubkey: RENT_SYSVAR_ID, isSigner: false, isWritable: false },
-      ],
-      programId: PROGRAM_ID,
-      data: getGachaDrawDiscriminator(),
-    })
+  for (let start = firstCard; start < cardTypeIds.length; start += CARDS_PER_CLAIM) {
+    const mintKeypairs = cardTypeIds
+      .slice(start, start + CARDS_PER_CLAIM)
+      .map(() => Keypair.generate())
+    const cards = mintKeypairs.map((mintKeypair, i) => ({
+      cardTypeId: cardTypeIds[start + i],
+      mintPubkey: mintKeypair.publicKey,
+    }))
 
     const transaction = new Transaction()
-      .add(createMintAccountIx)
-      .add(initMintIx)
-      .add(gachaDrawIx)
+    if (needsReveal && start === firstCard) {
+      transaction.add(buildRevealGachaPullIx(playerPubkey))
+    }
+    for (const { mintPubkey } of cards) {
+      transaction.add(...buildCreateMintIxs(playerPubkey, mintPubkey, mintRent))
+    }
+    transaction.add(buildClaimPulledCardsIx(playerPubkey, cards))
 
     transaction.feePayer = playerPubkey
     transaction.recentBlockhash = blockhash
-    transaction.partialSign(mintKeypair)
+    // mint keypair 需要签名
+    transaction.partialSign(...mintKeypairs)
 
-    transactionsData.push({ transaction, mintKeypair, cardTypeId, selectedCard })
+    claimsData.push({ transaction, cards })
   }
 
-  // 4. 一次签名所有交易
-  console.log(`Signing ${count} transactions...`)
-  const transactions = transactionsData.map(d => d.transaction)
-  const signedTransactions = await phantom.signAllTransactions(transactions)
+  // 3. 一次签名所有领取交易，按顺序发送 (卡按顺序领取)
+  console.log(`Signing ${claimsData.length} claim transactions...`)
+  const signedTransactions = await phantom.signAllTransactions(claimsData.map(d => d.transaction))
 
-  // 5. 串行发送交易（避免 rate limit）
-  console.log(`Sending ${count} transactions...`)
   const results: GachaDrawResult[] = []
-
   for (let index = 0; index < signedTransactions.length; index++) {
-    const signedTx = signedTransactions[index]
     try {
-      const txId = await connection.sendRawTransaction(signedTx.serialize())
+      const txId = await connection.sendRawTransaction(signedTransactions[index].serialize())
       await connection.confirmTransaction(txId, 'confirmed')
-      const { cardTypeId, mintKeypair } = transactionsData[index]
-      console.log(`Draw ${index + 1}: Card ${cardTypeId}, TX: ${txId}`)
-      results.push({
-        txId,
-        cardTypeId,
-        mintAddress: mintKeypair.publicKey.toBase58(),
-      })
+      for (const { cardTypeId, mintPubkey } of claimsData[index].cards) {
+        console.log(`Card ${cardTypeId}, Mint: ${mintPubkey.toBase58()}, TX: ${txId}`)
+        results.push({ txId, cardTypeId, mintAddress: mintPubkey.toBase58() })
+      }
     } catch (error) {
-      console.error(`Draw ${index + 1} failed:`, error)
+      // 后面的卡要等这笔领取成功，剩下的留在 PendingPull 里
+      console.error(`Claim ${index + 1} failed:`, error)
+      break
     }
     // 每次发送间隔 200ms，避免 429
     if (index < signedTransactions.length - 1) {
@@ -949,6 +959,27 @@ export async function gachaDrawMultiple(
     }
   }
 
+  return results
+}
+
+// 使用抽奖券抽卡（单抽）
+export async function gachaDraw(
+  playerPubkey: PublicKey
+): Promise<GachaDrawResult> {
+  const [result] = await drawGachaCards(playerPubkey, 1)
+  if (!result) {
+    throw new Error('Gacha draw was paid but the card could not be claimed')
+  }
+  console.log(`Gacha draw complete! Card: ${result.cardTypeId}, Mint: ${result.mintAddress}, TX:`, result.txId)
+  return result
+}
+
+// 多抽 (一次付费，领取交易一次签名)
+export async function gachaDrawMultiple(
+  playerPubkey: PublicKey,
+  count: number
+): Promise<GachaDrawResult[]> {
+  const results = await drawGachaCards(playerPubkey, count)
   console.log(`Multi-draw complete! ${results.length}/${count} successful`)
   return results
 }
@@ -1049,15 +1080,29 @@ function getDeleteDeckDiscriminator(): Buffer {
   return Buffer.from([200, 213, 71, 120, 161, 65, 82, 101])
 }
 
+// 获取 SynergyConfig PDA
+export function getSynergyConfigPDA(): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from('synergy_config')],
+    PROGRAM_ID
+  )
+}
+
 // 保存卡组到链上
+// Once the admin has set up trait synergies, the cards' instances and templates are
+// passed instead of the mints, so the program checks ownership and reports the
+// synergies reached (a full deck with both doesn't fit in one transaction)
 export async function saveDeck(
   playerPubkey: PublicKey,
   deckIndex: number,
   deckName: string,
-  cardMints: PublicKey[]
+  cards: CardInstance[]
 ): Promise<string> {
   const phantom = getPhantomProvider()
   const [playerDeckPDA] = getPlayerDeckPDA(playerPubkey, deckIndex)
+  const [synergyConfigPDA] = getSynergyConfigPDA()
+  const hasSynergyConfig = (await connection.getAccountInfo(synergyConfigPDA)) !== null
+  const cardMints = hasSynergyConfig ? [] : cards.map(card => card.mint)
 
   // 构建指令数据
   // discriminator (8) + deck_index (1 byte) + deck_name (4 + len) + card_mints vec (4 + 32*n)
@@ -1099,11 +1144,21 @@ export async function saveDeck(
     playerDeckPDA: playerDeckPDA.toBase58(),
   })
 
+  const cardKeys = hasSynergyConfig
+    ? cards.flatMap(card => [
+        { pubkey: getCardInstancePDA(card.mint)[0], isSigner: false, isWritable: false },
+        { pubkey: getCardTemplatePDA(card.cardTypeId)[0], isSigner: false, isWritable: false },
+      ])
+    : []
+
   const instruction = new TransactionInstruction({
     keys: [
       { pubkey: playerDeckPDA, isSigner: false, isWritable: true },
       { pubkey: playerPubkey, isSigner: true, isWritable: true },
       { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      // synergy_config (optional): the program id stands in for a missing account
+      { pubkey: hasSynergyConfig ? synergyConfigPDA : PROGRAM_ID, isSigner: false, isWritable: false },
+      ...cardKeys,
     ],
     programId: PROGRAM_ID,
     data,
@@ -1441,6 +1496,8 @@ export async function buyCard(
       { pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
       { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
       { pubkey: RENT_SYSVAR_ID, isSigner: false, isWritable: false },
+      OMITTED_ACCOUNT, // buyer_collection
+      OMITTED_ACCOUNT, // seller_collection
     ],
     programId: PROGRAM_ID,
     data: getBuyCardDiscriminator(),